@@ -1,11 +1,47 @@
-use std::path::PathBuf;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::img_cli::batch::is_pattern;
+use crate::img_cli::error::CommandError;
+use crate::png::ancillary::{PhysicalDimensions, Time};
+use crate::png::apng::{BlendOp, DisposeOp};
+use crate::png::atomic::WriteOptions;
+use crate::png::image::PngError;
+use crate::png::palette::PaletteMapping;
+use crate::stego::lsb::Channel;
 
 #[derive(Debug, Parser)]
 pub struct CliArgs {
     #[clap(subcommand)]
     pub command: Commands,
+
+    /// Output format of print, info, lint, decode, diff, text list, text get, palette list and apng list; json and yaml are meant for scripts
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct BatchArgs {
+    /// Also take PNG files from the subdirectories of directory inputs
+    #[arg(long, global = true)]
+    pub recursive: bool,
+
+    /// Number of files processed in parallel when a command runs on several
+    #[arg(long, global = true, default_value_t = NonZeroUsize::MIN)]
+    pub jobs: NonZeroUsize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Yaml,
 }
 
 #[derive(Debug, Subcommand)]
@@ -14,32 +50,555 @@ pub enum Commands {
     Decode(DecodeArgs),
     Remove(RemoveArgs),
     Print(PrintArgs),
+    Info(InfoArgs),
+    Lint(LintArgs),
+    Strip(StripArgs),
+    Diff(DiffArgs),
+    SetDpi(SetDpiArgs),
+    Touch(TouchArgs),
+    Text(TextArgs),
+    Chunk(ChunkArgs),
+    Palette(PaletteArgs),
+    Apng(ApngArgs),
     Manage(ManageArgs)
 }
 
 #[derive(Debug, Clone, Args)]
 pub struct EncodeArgs {
-    pub file_path: PathBuf,
-    pub chunk_type: String,
-    pub message: String,
-    pub output_file: Option<PathBuf>
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    /// Chunk type to store the message in; left out with --mode lsb
+    #[arg(short, long)]
+    pub chunk_type: Option<String>,
+
+    /// Message to hide
+    #[arg(short, long, conflicts_with = "input_file")]
+    pub message: Option<String>,
+
+    /// Read the payload from this file instead of --message, or from stdin with -
+    #[arg(long)]
+    pub input_file: Option<PathBuf>,
+
+    /// Write the new image here instead of overwriting the input file
+    #[arg(short, long)]
+    pub output_file: Option<PathBuf>,
+
+    /// Split the payload across chunks of at most this many bytes, each with a sequence header
+    #[arg(long, value_name = "MAX_SIZE")]
+    pub split: Option<usize>,
+
+    /// Hide the message in its own chunk, or in the least significant bits of the pixels
+    #[arg(long, value_enum, default_value_t = EmbedMode::Chunk)]
+    pub mode: EmbedMode,
+
+    #[command(flatten)]
+    pub lsb: LsbArgs,
+
+    /// Where to place the new chunk: before-iend, after-ihdr, end, or a chunk index
+    #[arg(long, default_value = "before-iend")]
+    pub position: ChunkPosition,
+
+    /// Encrypt the message with a key derived from this passphrase
+    #[arg(long, conflicts_with = "key_file")]
+    pub passphrase: Option<String>,
+
+    /// Encrypt the message with a key derived from the contents of this file
+    #[arg(long)]
+    pub key_file: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub write: WriteArgs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EmbedMode {
+    Chunk,
+    Lsb,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct LsbArgs {
+    /// Number of low bits used in each sample (--mode lsb)
+    #[arg(long, default_value_t = 1)]
+    pub bits: u8,
+
+    /// Channels to use: red, green, blue, gray, alpha; defaults to every color channel (--mode lsb)
+    #[arg(long, value_delimiter = ',')]
+    pub channels: Vec<Channel>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct WriteArgs {
+    /// Keep a copy of the file being replaced, named after it plus SUFFIX
+    #[arg(long, value_name = "SUFFIX", num_args = 0..=1, require_equals = true, default_missing_value = ".bak")]
+    pub backup: Option<String>,
+
+    /// Keep the access and modification times of the file being replaced
+    #[arg(long)]
+    pub preserve_mtime: bool,
+}
+
+impl WriteArgs {
+    pub fn options(&self) -> WriteOptions {
+        WriteOptions {
+            backup: self.backup.clone(),
+            preserve_mtime: self.preserve_mtime,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkPosition {
+    BeforeIend,
+    AfterIhdr,
+    End,
+    Index(usize),
 }
 
 #[derive(Debug, Clone, Args)]
 pub struct DecodeArgs {
-    pub file_path: PathBuf,
-    pub chunk_type: String 
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    /// Chunk type the message is stored in; left out with --mode lsb
+    #[arg(short, long)]
+    pub chunk_type: Option<String>,
+
+    /// Reassemble a message that was split across chunks with encode --split
+    #[arg(long)]
+    pub split: bool,
+
+    /// Decode every chunk of the type instead of only the first
+    #[arg(long, conflicts_with_all = ["split", "index", "output_file"])]
+    pub all: bool,
+
+    /// Decode the chunk at this index, as listed by print; the chunk type may be left out
+    #[arg(long, conflicts_with = "split")]
+    pub index: Option<usize>,
+
+    #[arg(long, value_enum, default_value_t = EmbedMode::Chunk)]
+    pub mode: EmbedMode,
+
+    #[command(flatten)]
+    pub lsb: LsbArgs,
+
+    /// Decrypt the message with a key derived from this passphrase
+    #[arg(long, conflicts_with = "key_file")]
+    pub passphrase: Option<String>,
+
+    /// Decrypt the message with a key derived from the contents of this file
+    #[arg(long)]
+    pub key_file: Option<PathBuf>,
+
+    /// Write the raw payload to this file instead of printing it, or to stdout with -
+    #[arg(short, long)]
+    pub output_file: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Args)]
 pub struct RemoveArgs {
-    pub file_path: PathBuf,
-    pub chunk_type: String
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    /// Chunk type to remove
+    #[arg(short, long)]
+    pub chunk_type: Option<String>,
+
+    /// Remove every chunk of the type instead of only the first
+    #[arg(long, conflicts_with = "index")]
+    pub all: bool,
+
+    /// Remove the chunk at this index, as listed by print; it must have the given type
+    #[arg(long)]
+    pub index: Option<usize>,
+
+    #[command(flatten)]
+    pub write: WriteArgs,
 }
 
 #[derive(Debug, Clone, Args)]
 pub struct PrintArgs {
-    pub file_path: PathBuf
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    #[arg(long)]
+    pub lenient: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct InfoArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>
+}
+
+// Takes in a --position value and see if we can construct a ChunkPosition out of it
+impl FromStr for ChunkPosition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "before-iend" => Ok(ChunkPosition::BeforeIend),
+            "after-ihdr" => Ok(ChunkPosition::AfterIhdr),
+            "end" => Ok(ChunkPosition::End),
+            _ => s
+                .parse::<usize>()
+                .map(ChunkPosition::Index)
+                .map_err(|_| format!("invalid position '{}', expected before-iend, after-ihdr, end or an index", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct LintArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct StripArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    /// Write the stripped image here instead of overwriting the input file
+    #[arg(short, long)]
+    pub output_file: Option<PathBuf>,
+
+    /// Ancillary chunk types to keep, e.g. sRGB,gAMA,iCCP; every other ancillary chunk is removed
+    #[arg(long, value_delimiter = ',', conflicts_with = "remove")]
+    pub keep: Vec<String>,
+
+    /// Only remove these ancillary chunk types, e.g. tEXt,zTXt,iTXt,eXIf
+    #[arg(long, value_delimiter = ',')]
+    pub remove: Vec<String>,
+
+    #[command(flatten)]
+    pub write: WriteArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct DiffArgs {
+    pub old_file: PathBuf,
+    pub new_file: PathBuf,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct SetDpiArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    /// Resolution in dots per inch, or HORIZONTALxVERTICAL, e.g. 300 or 300x600; stored in pHYs
+    #[arg(long)]
+    pub dpi: Option<PhysicalDimensions>,
+
+    #[command(flatten)]
+    pub write: WriteArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct TouchArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    /// Modification time to store in tIME, in UTC, e.g. 2024-05-01T12:30:00Z
+    #[arg(long, default_value = "now")]
+    pub time: Time,
+
+    #[command(flatten)]
+    pub write: WriteArgs,
+}
+
+/*TEXT ARGS*/
+#[derive(Debug, Parser)]
+pub struct TextArgs {
+    #[clap(subcommand)]
+    pub text_command: TextCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TextCommands {
+    List(TextListArgs),
+    Get(TextGetArgs),
+    Set(TextSetArgs),
+    Delete(TextDeleteArgs),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TextChunkKind {
+    Text,
+    Ztxt,
+    Itxt,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct TextListArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct TextGetArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    #[arg(short, long)]
+    pub keyword: Option<String>
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct TextSetArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    #[arg(short, long)]
+    pub keyword: Option<String>,
+
+    #[arg(short, long)]
+    pub text: Option<String>,
+
+    #[arg(long, value_enum, default_value_t = TextChunkKind::Text)]
+    pub kind: TextChunkKind,
+
+    /// Compress the text (iTXt only, zTXt is always compressed)
+    #[arg(long)]
+    pub compress: bool,
+
+    /// Language tag (iTXt only)
+    #[arg(long, default_value = "")]
+    pub language: String,
+
+    /// Translated keyword (iTXt only)
+    #[arg(long, default_value = "")]
+    pub translated_keyword: String,
+
+    #[command(flatten)]
+    pub write: WriteArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct TextDeleteArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    #[arg(short, long)]
+    pub keyword: Option<String>,
+
+    #[command(flatten)]
+    pub write: WriteArgs,
+}
+
+/*CHUNK ARGS*/
+#[derive(Debug, Parser)]
+pub struct ChunkArgs {
+    #[clap(subcommand)]
+    pub chunk_command: ChunkCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ChunkCommands {
+    Extract(ChunkExtractArgs),
+    Inject(ChunkInjectArgs),
+    Set(ChunkSetArgs),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkSelector {
+    Type(String),
+    Index(usize),
+}
+
+impl FromStr for ChunkSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<usize>() {
+            Ok(idx) => Ok(ChunkSelector::Index(idx)),
+            Err(_) => Ok(ChunkSelector::Type(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ChunkExtractArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    /// Chunk type (the first chunk of that type) or chunk index
+    #[arg(short, long)]
+    pub chunk: Option<ChunkSelector>,
+
+    /// File the chunk is written to, or stdout with -
+    #[arg(short, long)]
+    pub output_file: PathBuf,
+
+    /// Write the whole chunk record: length, type, data and CRC
+    #[arg(long)]
+    pub record: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ChunkInjectArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    /// Type of the new chunk; left out with --record, which carries its own type
+    #[arg(short, long, conflicts_with = "record")]
+    pub chunk_type: Option<String>,
+
+    /// File holding the chunk data, or stdin with -
+    #[arg(long)]
+    pub data_file: PathBuf,
+
+    /// The data file holds a whole chunk record, as written by chunk extract --record; its CRC is checked
+    #[arg(long)]
+    pub record: bool,
+
+    /// Where to place the chunk: before-iend, after-ihdr, end, or a chunk index
+    #[arg(long, default_value = "before-iend")]
+    pub at: ChunkPosition,
+
+    #[command(flatten)]
+    pub write: WriteArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ChunkSetArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    /// One of gAMA, cHRM, sRGB, pHYs, tIME, bKGD, sBIT or hIST
+    #[arg(short, long)]
+    pub chunk_type: Option<String>,
+
+    /// The value, e.g. 0.45455 for gAMA, perceptual for sRGB, 300 (DPI) for pHYs or rgb:255,255,255 for bKGD
+    #[arg(long)]
+    pub value: Option<String>,
+
+    #[command(flatten)]
+    pub write: WriteArgs,
+}
+
+/*PALETTE ARGS*/
+#[derive(Debug, Parser)]
+pub struct PaletteArgs {
+    #[clap(subcommand)]
+    pub palette_command: PaletteCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PaletteCommands {
+    List(PaletteListArgs),
+    Export(PaletteExportArgs),
+    Recolor(PaletteRecolorArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct PaletteListArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct PaletteExportArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    /// GIMP palette (.gpl) file the palette is written to, or stdout with -
+    #[arg(short, long)]
+    pub output_file: PathBuf,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct PaletteRecolorArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    /// FROM=TO pair, repeatable: FROM is a palette index or a #color, TO the new #color, e.g. 3=#ff0000 or #ff0000=#00ff0080
+    #[arg(short, long = "map", value_name = "FROM=TO")]
+    pub mappings: Vec<PaletteMapping>,
+
+    #[command(flatten)]
+    pub write: WriteArgs,
+}
+
+/*APNG ARGS*/
+#[derive(Debug, Parser)]
+pub struct ApngArgs {
+    #[clap(subcommand)]
+    pub apng_command: ApngCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ApngCommands {
+    List(ApngListArgs),
+    Extract(ApngExtractArgs),
+    Assemble(ApngAssembleArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ApngListArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ApngExtractArgs {
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>,
+
+    /// Directory the frames are written to, as frame_000.png, frame_001.png, ...
+    #[arg(short, long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Only extract the frame with this index
+    #[arg(long)]
+    pub frame: Option<usize>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ApngAssembleArgs {
+    pub output_file: PathBuf,
+
+    /// Frames in display order; the first sets the canvas size and is shown by viewers without APNG support
+    #[arg(required = true)]
+    pub frames: Vec<PathBuf>,
+
+    /// How long each frame is shown, in milliseconds
+    #[arg(long, default_value_t = 100)]
+    pub delay: u16,
+
+    /// Number of times to play the animation, 0 for forever
+    #[arg(long, default_value_t = 0)]
+    pub plays: u32,
+
+    /// What happens to a frame's region before the next frame: none, background or previous
+    #[arg(long, default_value = "none")]
+    pub dispose: DisposeOp,
+
+    /// How a frame is drawn over the canvas: source replaces it, over alpha-blends onto it
+    #[arg(long, default_value = "source")]
+    pub blend: BlendOp,
 }
 
 /*MANAGE ARGS*/
@@ -59,7 +618,9 @@ pub enum ManageCommands {
 
 #[derive(Debug, Clone, Args)]
 pub struct DeleteArgs {
-    pub file_path: PathBuf
+    /// PNG files, directories or glob patterns
+    #[arg(required = true)]
+    pub file_paths: Vec<PathBuf>
 }
 
 #[derive(Debug, Clone, Args)]
@@ -82,5 +643,169 @@ pub struct ConvertArgs {
     #[arg(short = 'w')]
     pub convert_to_webp: bool,
 
-    pub input_path: PathBuf,
+    /// Image files, directories or glob patterns
+    #[arg(required = true)]
+    pub input_paths: Vec<PathBuf>,
+}
+
+/*LEGACY POSITIONALS*/
+/**
+ * Reads the file-first positional form of older releases, e.g. `encode FILE TYPE MESSAGE [OUTPUT]`,
+ * `decode FILE TYPE`, `remove FILE TYPE` or `text set FILE KEYWORD TEXT`.
+ *
+ * A command only reads it when a value it needs is missing from the options. The first positional is
+ * then the one input and the rest fill the missing values in their old order. Where the value is
+ * optional, as in `strip FILE OUTPUT` or `decode FILE TYPE --index N`, it is only read that way when it
+ * is neither an existing path nor a glob pattern.
+ *
+ * @returns resolve_positionals - Moves the legacy positionals of the command into its options.
+ */
+impl CliArgs {
+    pub fn resolve_positionals(&mut self) -> Result<(), CommandError> {
+        match &mut self.command {
+            Commands::Encode(args) => {
+                let needs_type = args.mode == EmbedMode::Chunk && args.chunk_type.is_none();
+                let needs_message = args.message.is_none() && args.input_file.is_none();
+                let mut legacy = Legacy::take(&mut args.file_paths, needs_type || needs_message);
+                if needs_type {
+                    args.chunk_type = legacy.next()?;
+                }
+                if needs_message {
+                    args.message = legacy.next()?;
+                }
+                if args.output_file.is_none() {
+                    args.output_file = legacy.next()?.map(PathBuf::from);
+                }
+                legacy.finish()
+            }
+            Commands::Decode(args) => {
+                // With --index the type is optional, so a second input is only taken as one when it names no file
+                let needs_type = args.mode == EmbedMode::Chunk
+                    && args.chunk_type.is_none()
+                    && (args.index.is_none() || args.file_paths.len() == 2 && is_value(&args.file_paths[1]));
+                let mut legacy = Legacy::take(&mut args.file_paths, needs_type);
+                if args.chunk_type.is_none() {
+                    args.chunk_type = legacy.next()?;
+                }
+                legacy.finish()
+            }
+            Commands::Remove(args) => {
+                let mut legacy = Legacy::take(&mut args.file_paths, args.chunk_type.is_none());
+                if args.chunk_type.is_none() {
+                    args.chunk_type = legacy.next()?;
+                }
+                legacy.finish()
+            }
+            Commands::Strip(args) => {
+                let needs_output = args.output_file.is_none() && args.file_paths.len() == 2 && is_value(&args.file_paths[1]);
+                let mut legacy = Legacy::take(&mut args.file_paths, needs_output);
+                if args.output_file.is_none() {
+                    args.output_file = legacy.next()?.map(PathBuf::from);
+                }
+                legacy.finish()
+            }
+            Commands::SetDpi(args) => {
+                let mut legacy = Legacy::take(&mut args.file_paths, args.dpi.is_none());
+                if args.dpi.is_none() {
+                    args.dpi = legacy.next()?.map(|dpi| dpi.parse()).transpose()?;
+                }
+                legacy.finish()
+            }
+            Commands::Text(TextArgs { text_command: TextCommands::Get(args) }) => {
+                let mut legacy = Legacy::take(&mut args.file_paths, args.keyword.is_none());
+                if args.keyword.is_none() {
+                    args.keyword = legacy.next()?;
+                }
+                legacy.finish()
+            }
+            Commands::Text(TextArgs { text_command: TextCommands::Set(args) }) => {
+                let mut legacy = Legacy::take(&mut args.file_paths, args.keyword.is_none() || args.text.is_none());
+                if args.keyword.is_none() {
+                    args.keyword = legacy.next()?;
+                }
+                if args.text.is_none() {
+                    args.text = legacy.next()?;
+                }
+                legacy.finish()
+            }
+            Commands::Text(TextArgs { text_command: TextCommands::Delete(args) }) => {
+                let mut legacy = Legacy::take(&mut args.file_paths, args.keyword.is_none());
+                if args.keyword.is_none() {
+                    args.keyword = legacy.next()?;
+                }
+                legacy.finish()
+            }
+            Commands::Chunk(ChunkArgs { chunk_command: ChunkCommands::Extract(args) }) => {
+                let mut legacy = Legacy::take(&mut args.file_paths, args.chunk.is_none());
+                if args.chunk.is_none() {
+                    args.chunk = legacy.next()?.map(|chunk| chunk.parse()).transpose().map_err(CommandError::UnexpectedArgument)?;
+                }
+                legacy.finish()
+            }
+            Commands::Chunk(ChunkArgs { chunk_command: ChunkCommands::Inject(args) }) => {
+                let mut legacy = Legacy::take(&mut args.file_paths, args.chunk_type.is_none() && !args.record);
+                if args.chunk_type.is_none() {
+                    args.chunk_type = legacy.next()?;
+                }
+                legacy.finish()
+            }
+            Commands::Chunk(ChunkArgs { chunk_command: ChunkCommands::Set(args) }) => {
+                let mut legacy = Legacy::take(&mut args.file_paths, args.chunk_type.is_none() || args.value.is_none());
+                if args.chunk_type.is_none() {
+                    args.chunk_type = legacy.next()?;
+                }
+                if args.value.is_none() {
+                    args.value = legacy.next()?;
+                }
+                legacy.finish()
+            }
+            Commands::Palette(PaletteArgs { palette_command: PaletteCommands::Recolor(args) }) => {
+                let mut legacy = Legacy::take(&mut args.file_paths, args.mappings.is_empty());
+                while let Some(mapping) = legacy.next()? {
+                    args.mappings.push(mapping.parse().map_err(PngError::from)?);
+                }
+                legacy.finish()
+            }
+            Commands::Apng(ApngArgs { apng_command: ApngCommands::Extract(args) }) => {
+                let mut legacy = Legacy::take(&mut args.file_paths, args.output_dir.is_none());
+                if args.output_dir.is_none() {
+                    args.output_dir = legacy.next()?.map(PathBuf::from);
+                }
+                legacy.finish()
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// A positional that is neither an existing path nor a glob pattern cannot be an input
+fn is_value(path: &Path) -> bool {
+    !path.exists() && !is_pattern(&path.to_string_lossy())
+}
+
+// The positionals after the first input, when a command reads them as legacy values
+struct Legacy {
+    values: std::vec::IntoIter<PathBuf>,
+}
+
+impl Legacy {
+    fn take(file_paths: &mut Vec<PathBuf>, missing: bool) -> Legacy {
+        let values = if missing && !file_paths.is_empty() { file_paths.split_off(1) } else { Vec::new() };
+        Legacy { values: values.into_iter() }
+    }
+
+    fn next(&mut self) -> Result<Option<String>, CommandError> {
+        self.values
+            .next()
+            .map(|value| value.into_os_string().into_string().map_err(|value| CommandError::UnexpectedArgument(value.to_string_lossy().into_owned())))
+            .transpose()
+    }
+
+    // A value left over once every missing one is filled has no place in the old form either
+    fn finish(mut self) -> Result<(), CommandError> {
+        match self.values.next() {
+            Some(value) => Err(CommandError::UnexpectedArgument(value.display().to_string())),
+            None => Ok(()),
+        }
+    }
 }
@@ -0,0 +1,169 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::img_cli::args::{BatchArgs, OutputFormat};
+use crate::img_cli::error::CommandError;
+use crate::img_cli::output::{self, Output, Value};
+
+pub struct Batch {
+    files: Vec<PathBuf>,
+    single: bool,
+    jobs: usize,
+}
+
+/**
+ * The files a command runs on, expanded from the paths, directories and glob patterns it was given.
+ *
+ * A single plain path runs the command as usual, so its output and exit code are unchanged. Anything
+ * else runs the command once per file and carries on past failures; the status of every file and a
+ * summary are printed to stderr at the end, which keeps stdout for the command's own output.
+ *
+ * With several files, the text output of each file is printed whole once the file is done, and the
+ * structured values of `--format json` and `yaml` are printed at the end as one array, in file order.
+ *
+ * @returns resolve - Expands the inputs; a directory gives the `.png` files in it, and in its subdirectories with `--recursive`.
+ * @returns files - Returns the expanded files, sorted within each input.
+ * @returns single_file_only - Fails if an option that names a single output is set while there are several files.
+ * @returns run - Runs the command on every file, on up to `--jobs` threads, printing its output in the given format.
+ */
+impl Batch {
+    pub fn resolve(inputs: &[PathBuf], options: &BatchArgs) -> Result<Batch, CommandError> {
+        let mut files = Vec::new();
+
+        for input in inputs {
+            let pattern = input.to_string_lossy();
+            let found = if is_pattern(&pattern) && !input.exists() {
+                glob_files(&pattern)?
+            } else if input.is_dir() {
+                let mut found = Vec::new();
+                collect_pngs(input, options.recursive, &mut found)?;
+                found
+            } else {
+                files.push(input.clone());
+                continue;
+            };
+
+            if found.is_empty() {
+                return Err(CommandError::NoMatches(pattern.into_owned()));
+            }
+            files.extend(found);
+        }
+
+        let single = inputs.len() == 1 && files.len() == 1 && files[0] == inputs[0];
+        Ok(Batch { files, single, jobs: options.jobs.get() })
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    pub fn single_file_only(self, option: &'static str, is_set: bool) -> Result<Batch, CommandError> {
+        if is_set && !self.single {
+            return Err(CommandError::SingleFileOnly(option));
+        }
+        Ok(self)
+    }
+
+    pub fn run<F>(&self, format: OutputFormat, command: F) -> Result<(), CommandError>
+    where
+        F: Fn(&Path, &mut Output) -> Result<(), CommandError> + Sync,
+    {
+        if self.single {
+            return command(&self.files[0], &mut Output::direct(format));
+        }
+
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::with_capacity(self.files.len()));
+
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.min(self.files.len()) {
+                scope.spawn(|| {
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(file) = self.files.get(index) else {
+                            break;
+                        };
+                        let mut out = Output::buffered(format);
+                        let mut result = command(file, &mut out);
+                        let document = match out.flush() {
+                            Ok(document) => document,
+                            Err(e) => {
+                                result = result.and(Err(CommandError::io(Path::new("-"), e)));
+                                None
+                            }
+                        };
+                        results.lock().unwrap_or_else(|e| e.into_inner()).push((index, result, document));
+                    }
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+        results.sort_by_key(|(index, _, _)| *index);
+
+        let mut failed = 0;
+        let mut documents = Vec::new();
+        for (index, result, document) in results.iter_mut() {
+            let file = self.files[*index].display();
+            match result {
+                Ok(()) => eprintln!("ok: {}", file),
+                Err(e) => {
+                    failed += 1;
+                    eprintln!("failed: {}: {} (exit code {})", file, e, e.exit_code());
+                }
+            }
+            documents.extend(document.take());
+        }
+        eprintln!("{} file(s): {} ok, {} failed", results.len(), results.len() - failed, failed);
+
+        if !documents.is_empty() {
+            output::print(&Value::Array(documents), format);
+        }
+
+        if failed > 0 {
+            return Err(CommandError::BatchFailed { failed, total: results.len() });
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn is_pattern(input: &str) -> bool {
+    input.contains(['*', '?', '['])
+}
+
+fn glob_files(pattern: &str) -> Result<Vec<PathBuf>, CommandError> {
+    let mut files = Vec::new();
+
+    for entry in glob::glob(pattern).map_err(CommandError::InvalidPattern)? {
+        let path = entry.map_err(|e| {
+            let path = e.path().to_path_buf();
+            CommandError::io(&path, e.into())
+        })?;
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+// Sorted so every run visits the files in the same order
+fn collect_pngs(dir: &Path, recursive: bool, files: &mut Vec<PathBuf>) -> Result<(), CommandError> {
+    let mut entries = fs::read_dir(dir)
+        .and_then(|entries| entries.map(|entry| entry.map(|e| e.path())).collect::<Result<Vec<_>, _>>())
+        .map_err(|e| CommandError::io(dir, e))?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            if recursive {
+                collect_pngs(&path, recursive, files)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png")) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
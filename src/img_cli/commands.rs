@@ -1,147 +1,1027 @@
 use std::fs::File;
-use std::path::Path;
-use std::{fmt, fs};
-use std::io::copy;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::fs;
+use std::io::{copy, BufReader, Read};
 use reqwest::blocking::get;
 
 use crate::img_cli::args::
 {DecodeArgs, 
 EncodeArgs, 
 PrintArgs, 
-RemoveArgs};
+RemoveArgs,
+StripArgs,
+DiffArgs};
 use crate::png::image::{Png, PngError};
+use crate::png::ancillary::Ancillary;
+use crate::png::palette::Palette;
+use crate::png::apng::{ApngError, FrameOptions};
 use crate::png::chunk::Chunk;
+use crate::png::diff;
+use crate::png::ihdr::Ihdr;
+use crate::png::lint::{has_errors, lint_chunks, Severity};
+use crate::png::reader::{ChunkHeader, PngReader};
 #[allow(deprecated)]
 use image::io::Reader as ImageReader;
 
-use super::args::{ConvertArgs, DeleteArgs, DownloadFromInternetArgs};
+use crate::png::chunk_type::ChunkType;
+use crate::png::text::TextEntry;
+use crate::png::encoder::EncoderOptions;
+use crate::stego::crypto;
+use crate::stego::lsb::{self, LsbOptions};
+use crate::stego::split::{self, Piece, SplitError};
+
+use super::error::CommandError;
+use super::output::{self, Output, Value};
+
+use super::args::{
+    ApngAssembleArgs,
+    ApngExtractArgs,
+    ChunkExtractArgs,
+    ChunkInjectArgs,
+    ChunkSelector,
+    ChunkSetArgs,
+    ChunkPosition,
+    ConvertArgs,
+    DownloadFromInternetArgs,
+    EmbedMode,
+    LsbArgs,
+    OutputFormat,
+    PaletteExportArgs,
+    PaletteRecolorArgs,
+    SetDpiArgs,
+    TextChunkKind,
+    TextDeleteArgs,
+    TextGetArgs,
+    TextSetArgs,
+    TouchArgs,
+    WriteArgs,
+};
 extern crate reqwest;
 
-#[derive(Debug)]
-#[allow(dead_code)] // NOTE to self: fix this stupid linting issue?
-pub enum CommandError {
-    DownloadError,
-    DeleteFileError,
-    ConversionError,
-    FailedToFindURL,
+// The message encode hides, read once so that --input-file - (stdin) works for every file of a batch
+pub fn read_message(args: &EncodeArgs) -> Result<Vec<u8>, CommandError> {
+    match (&args.message, &args.input_file) {
+        (_, Some(input_file)) => read_input(input_file).map_err(|e| CommandError::io(input_file, e)),
+        (Some(message), None) => Ok(message.as_bytes().to_vec()),
+        (None, None) => Err(CommandError::MissingArgument("message")),
+    }
 }
-impl fmt::Display for CommandError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            CommandError::DeleteFileError => write!(f, "File does not exist cannot display"),
-            CommandError::ConversionError => write!(f, "Failed to convert file"),
-            CommandError::DownloadError => write!(f, "Failed to download file from the internet"),
-            CommandError::FailedToFindURL => write!(f, "Failed to find URL from the internet"),
+
+#[allow(dead_code)]
+pub fn encode(file_path: &Path, args: &EncodeArgs, message: &[u8], out: &mut Output) -> Result<(), CommandError> {
+    let mut png = Png::from_file(file_path)?;
+    let secret = read_secret(&args.passphrase, &args.key_file)?;
+
+    let chunk_type = match (args.mode, &args.chunk_type) {
+        (EmbedMode::Chunk, Some(chunk_type)) => Some(chunk_type),
+        (EmbedMode::Chunk, None) => return Err(CommandError::MissingArgument("chunk type")),
+        (EmbedMode::Lsb, Some(chunk_type)) => return Err(CommandError::UnexpectedArgument(chunk_type.clone())),
+        (EmbedMode::Lsb, None) => None,
+    };
+
+    let payload = match &secret {
+        Some(secret) => crypto::encrypt(message, secret)?,
+        None => message.to_vec(),
+    };
+
+    match chunk_type {
+        Some(chunk_type) => {
+            let pieces = match args.split {
+                Some(max_size) => split::split(&payload, max_size)?,
+                None => vec![payload],
+            };
+
+            let start = insertion_index(&png, args.position)?;
+            for (i, piece) in pieces.into_iter().enumerate() {
+                png.insert_chunk(start + i, Chunk::new(ChunkType::from_str(chunk_type)?, piece))?;
+            }
+        }
+        None if args.split.is_some() => {
+            return Err(CommandError::UnexpectedArgument("--split".to_string()));
+        }
+        None => {
+            let options = lsb_options(&args.lsb, secret);
+            let mut pixels = png.pixels()?;
+            out.note(format!("capacity: {} bytes, payload: {} bytes", lsb::capacity(&pixels, &options)?, payload.len()));
+            lsb::embed(&mut pixels, &payload, &options)?;
+
+            let encoder = EncoderOptions {
+                interlaced: png.ihdr()?.is_interlaced(),
+                ..EncoderOptions::default()
+            };
+            png.replace_pixels(&pixels, &encoder)?;
         }
     }
-}
 
-impl std::error::Error for CommandError {}
+    if let Some(output_file) = &args.output_file {
+        png.write_file_with(output_file, &args.write.options())?
+    } else {
+        png.write_file_with(file_path, &args.write.options())?
+    }
+
+    Ok(())
+}
 
 #[allow(dead_code)]
-pub fn encode(args: &EncodeArgs) -> Result<(),  Box<dyn std::error::Error>> {
-    let mut png = Png::from_file(&args.file_path)?;
-    let chunk = Chunk::from_strings(&args.chunk_type, &args.message)?;
-    png.append_chunk(chunk);
+pub fn decode(file_path: &Path, args: &DecodeArgs, out: &mut Output) -> Result<(), CommandError> {
+    let format = out.format();
+    let mut reader = open_png(file_path, false)?;
+    let secret = read_secret(&args.passphrase, &args.key_file)?;
+    let to_stdout = args.output_file.as_ref().is_some_and(|path| path.as_os_str() == "-");
+    let mut capacity = None;
+
+    // Each message with the index of the chunk it came from; split and LSB messages have none
+    let messages: Vec<(Option<usize>, Vec<u8>)> = match args.mode {
+        EmbedMode::Chunk => {
+            let chunks = match args.index {
+                Some(index) => vec![find_chunk_at(&mut reader, index, args.chunk_type.as_deref())?],
+                None => {
+                    let chunk_type = args.chunk_type.as_ref().ok_or(CommandError::MissingArgument("chunk type"))?;
+                    let found = find_chunks(&mut reader, chunk_type, args.split || args.all)?;
+                    if found.count == 0 {
+                        return Err(PngError::ChunkNotFound(chunk_type.clone()).into());
+                    }
+                    if found.count > 1 && !(args.split || args.all) {
+                        out.note(format!("note: {} more {} chunk(s) found, use --all to decode them all", found.count - 1, chunk_type));
+                    }
+                    found.chunks
+                }
+            };
+
+            if args.split {
+                let chunks: Vec<Chunk> = chunks.into_iter().map(|(_, chunk)| chunk).collect();
+                vec![(None, join_pieces(&chunks, out)?)]
+            } else if args.all {
+                chunks.into_iter().map(|(idx, chunk)| (Some(idx), chunk.data().to_vec())).collect()
+            } else {
+                let (idx, chunk) = chunks.into_iter().next().expect("at least one chunk was found");
+                vec![(Some(idx), chunk.data().to_vec())]
+            }
+        }
+        EmbedMode::Lsb => {
+            if args.all || args.index.is_some() {
+                let option = if args.all { "--all" } else { "--index" };
+                return Err(CommandError::UnexpectedArgument(option.to_string()));
+            }
+
+            let pixels = Png::from_reader(reader)?.pixels()?;
+            let options = lsb_options(&args.lsb, secret.clone());
+            let bytes = lsb::capacity(&pixels, &options)?;
+            capacity = Some(bytes);
+
+            // Keep stdout clean for the payload when it is written there
+            if to_stdout {
+                out.note(format!("capacity: {} bytes", bytes));
+            } else if format == OutputFormat::Text {
+                out.line(format!("capacity: {} bytes", bytes));
+            }
+            vec![(None, lsb::extract(&pixels, &options)?)]
+        }
+    };
+
+    let messages = match &secret {
+        Some(secret) => messages
+            .into_iter()
+            .map(|(idx, message)| Ok((idx, crypto::decrypt(&message, secret)?)))
+            .collect::<Result<Vec<_>, CommandError>>()?,
+        None => messages,
+    };
+
+    if args.all {
+        return print_messages(file_path, args, secret.is_some(), &messages, out);
+    }
+    let (_, message) = &messages[0];
 
     if let Some(output_file) = &args.output_file {
-        png.write_file(output_file)?
-    } else {
-        png.write_file(&args.file_path)?
+        write_output(output_file, message, out).map_err(|e| CommandError::io(output_file, e))?;
+        if to_stdout || format == OutputFormat::Text {
+            return Ok(());
+        }
+    }
+
+    let (text, hex) = message_text(message);
+
+    match format {
+        OutputFormat::Text => match text {
+            Some(text) => out.line(format!("msg: {}", text)),
+            None => out.line(format!("msg ({} bytes, not UTF-8, hex): {}", message.len(), hex)),
+        },
+        _ => {
+            let mode = match args.mode {
+                EmbedMode::Chunk => "chunk",
+                EmbedMode::Lsb => "lsb",
+            };
+            let value = Value::object(vec![
+                ("file", file_path.display().to_string().into()),
+                ("mode", mode.into()),
+                ("chunk_type", args.chunk_type.clone().filter(|_| args.mode == EmbedMode::Chunk).into()),
+                ("capacity", capacity.into()),
+                ("encrypted", secret.is_some().into()),
+                ("length", message.len().into()),
+                ("utf8", text.is_some().into()),
+                ("message", text.into()),
+                ("hex", hex.into()),
+                ("output_file", args.output_file.as_ref().map(|path| path.display().to_string()).into()),
+            ]);
+            out.document(value);
+        }
+    }
+
+    Ok(())
+}
+
+// The messages of decode --all, one per chunk
+fn print_messages(
+    file_path: &Path,
+    args: &DecodeArgs,
+    encrypted: bool,
+    messages: &[(Option<usize>, Vec<u8>)],
+    out: &mut Output,
+) -> Result<(), CommandError> {
+    let messages = messages.iter().filter_map(|(idx, message)| Some(((*idx)?, message)));
+
+    match out.format() {
+        OutputFormat::Text => {
+            for (idx, message) in messages {
+                match message_text(message) {
+                    (Some(text), _) => out.line(format!("chunk#{} msg: {}", idx, text)),
+                    (None, hex) => out.line(format!("chunk#{} msg ({} bytes, not UTF-8, hex): {}", idx, message.len(), hex)),
+                }
+            }
+        }
+        _ => {
+            let messages = messages
+                .map(|(idx, message)| {
+                    let (text, hex) = message_text(message);
+                    Value::object(vec![
+                        ("index", idx.into()),
+                        ("length", message.len().into()),
+                        ("utf8", text.is_some().into()),
+                        ("message", text.into()),
+                        ("hex", hex.into()),
+                    ])
+                })
+                .collect();
+            let value = Value::object(vec![
+                ("file", file_path.display().to_string().into()),
+                ("mode", "chunk".into()),
+                ("chunk_type", args.chunk_type.clone().into()),
+                ("encrypted", encrypted.into()),
+                ("messages", Value::Array(messages)),
+            ]);
+            out.document(value);
+        }
     }
 
     Ok(())
 }
 
+// The message as text if it is UTF-8, and always as hex
+fn message_text(message: &[u8]) -> (Option<&str>, String) {
+    (std::str::from_utf8(message).ok(), message.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
 #[allow(dead_code)]
-pub fn decode(args: &DecodeArgs) -> Result<(), Box<dyn std::error::Error>> {
-    let png = Png::from_file(&args.file_path)?;
+pub fn remove(file_path: &Path, args: &RemoveArgs, out: &mut Output) -> Result<(), CommandError> {
+    let chunk_type = args.chunk_type.as_deref().ok_or(CommandError::MissingArgument("chunk type"))?;
+    let mut png = Png::from_file(file_path)?;
+
+    match args.index {
+        Some(index) => {
+            check_chunk_type(index, &png.chunk_at(index)?.chunk_type().to_string(), Some(chunk_type))?;
+            png.remove_chunk_at(index)?;
+        }
+        None if args.all => {
+            let removed = png.remove_all(chunk_type);
+            if removed.is_empty() {
+                return Err(PngError::ChunkNotFound(chunk_type.to_string()).into());
+            }
+            out.line(format!("Removed {} {} chunk(s)", removed.len(), chunk_type));
+        }
+        None => {
+            png.remove_chunk(chunk_type)?;
+        }
+    }
     
-    match png.chunk_by_type(&args.chunk_type) {
-        Some(chunk) => {
-            println!("msg: {}", chunk.data_as_string()?);
-            Ok(())
+    png.write_file_with(file_path, &args.write.options())?;
+
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn print_chunks(file_path: &Path, args: &PrintArgs, out: &mut Output) -> Result<(), CommandError> {
+    let mut reader = open_png(file_path, args.lenient)?;
+
+    if out.format() != OutputFormat::Text {
+        let (chunks, _) = read_chunk_values(&mut reader)?;
+        let value = Value::object(vec![
+            ("file", file_path.display().to_string().into()),
+            ("size", reader.offset().into()),
+            ("chunks", Value::Array(chunks)),
+            ("corrupted_chunks", Value::Array(reader.corrupted_chunks().iter().map(Value::from).collect())),
+        ]);
+        out.document(value);
+        return Ok(());
+    }
+
+    let mut headers = Vec::new();
+
+    // Every body is read through its CRC, so a corrupted chunk fails here or, with --lenient, is listed below
+    while let Some(header) = reader.next_header()? {
+        reader.skip_body()?;
+        headers.push(header);
+    }
+    print_summary(file_path, reader.offset(), &headers, out);
+
+    for corrupted in reader.corrupted_chunks() {
+        out.line(format!("  corrupted: {}", corrupted));
+    }
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn info(file_path: &Path, out: &mut Output) -> Result<(), CommandError> {
+    let mut reader = open_png(file_path, false)?;
+
+    if out.format() != OutputFormat::Text {
+        let (chunks, ihdr) = read_chunk_values(&mut reader)?;
+        let value = Value::object(vec![
+            ("file", file_path.display().to_string().into()),
+            ("size", reader.offset().into()),
+            ("ihdr", Value::from(&ihdr?)),
+            ("chunks", Value::Array(chunks)),
+        ]);
+        out.document(value);
+        return Ok(());
+    }
+
+    let Layout { headers, chunks, ihdr } = read_layout(&mut reader, &Ancillary::CHUNK_TYPES)?;
+    let ihdr = ihdr?;
+
+    print_summary(file_path, reader.offset(), &headers, out);
+    for line in ihdr.to_string().lines() {
+        out.line(format!("  {}", line));
+    }
+    for chunk in &chunks {
+        match Ancillary::from_chunk(chunk) {
+            Some(Ok(value)) => out.line(format!("  {}: {}", chunk.chunk_type(), value)),
+            Some(Err(e)) => out.line(format!("  {}: invalid, {}", chunk.chunk_type(), e)),
+            None => {}
         }
-        None => Err(Box::new(PngError::InvalidChunk))
     }
+    Ok(())
 }
 
 #[allow(dead_code)]
-pub fn remove(args: &RemoveArgs) -> Result<(), Box<dyn std::error::Error>> {
-    let mut png = Png::from_file(&args.file_path)?;
-    png.remove_chunk(&args.chunk_type)?;
-    
-    png.write_file(&args.file_path)?;
+pub fn lint_file(file_path: &Path, out: &mut Output) -> Result<(), CommandError> {
+    let format = out.format();
+    // Strict reader: every body is read and checked against its CRC, so a corrupted chunk is never OK
+    let mut reader = open_png(file_path, false)?;
+    let Layout { headers, ihdr, .. } = read_layout(&mut reader, &[])?;
+    let types: Vec<String> = headers.into_iter().map(|h| h.chunk_type).collect();
+    let diagnostics = lint_chunks(&types, ihdr);
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+
+    if format == OutputFormat::Text {
+        for diagnostic in &diagnostics {
+            out.line(diagnostic);
+        }
+    } else {
+        let value = Value::object(vec![
+            ("file", file_path.display().to_string().into()),
+            ("valid", (!has_errors(&diagnostics)).into()),
+            ("errors", errors.into()),
+            ("warnings", (diagnostics.len() - errors).into()),
+            ("diagnostics", Value::Array(diagnostics.iter().map(Value::from).collect())),
+        ]);
+        out.document(value);
+    }
 
+    if has_errors(&diagnostics) {
+        return Err(CommandError::ValidationFailed(errors));
+    }
+
+    if format == OutputFormat::Text {
+        out.line(format!("{}: OK", file_path.display()));
+    }
     Ok(())
 }
 
 #[allow(dead_code)]
-pub fn print_chunks(args: &PrintArgs) -> Result<(), Box<dyn std::error::Error>> {
-    let png = Png::from_file(&args.file_path)?;
-    println!(
-        "File: {}, Size: {}",
-        &args.file_path.display(),
-        png.as_bytes().len()
-    );
+pub fn strip(file_path: &Path, args: &StripArgs, out: &mut Output) -> Result<(), CommandError> {
+    let keep = parse_chunk_types(&args.keep)?;
+    let remove = parse_chunk_types(&args.remove)?;
+    if let Some(critical) = remove.iter().find(|t| t.is_critical()) {
+        return Err(CommandError::UnexpectedArgument(critical.to_string()));
+    }
 
-    for (i, chunk) in png.chunks().iter().enumerate() {
-        println!(
-            "  chunk#{}{{ chunk_type: {}, data_length: {}}}",
-            i,
-            chunk.chunk_type(),
-            chunk.length(),
-        );
+    let mut png = Png::from_file(file_path)?;
+    let removed = png.retain(|chunk| {
+        let chunk_type = chunk.chunk_type();
+        if chunk_type.is_critical() {
+            true
+        } else if remove.is_empty() {
+            keep.contains(chunk_type)
+        } else {
+            !remove.contains(chunk_type)
+        }
+    });
+
+    let mut saved = 0;
+    for chunk in &removed {
+        let size = chunk.as_bytes().len();
+        saved += size;
+        out.line(format!("Removed {} ({} bytes)", chunk.chunk_type(), size));
     }
+    out.line(format!("Removed {} chunk(s), saved {} bytes", removed.len(), saved));
+
+    png.write_file_with(args.output_file.as_deref().unwrap_or(file_path), &args.write.options())?;
     Ok(())
 }
 
 #[allow(dead_code)]
-pub fn delete_file(args: &DeleteArgs) -> Result<(), Box<dyn std::error::Error>> {
-    let file = &args.file_path;
+pub fn diff(args: &DiffArgs, out: &mut Output) -> Result<(), CommandError> {
+    let old = Png::from_file(&args.old_file)?;
+    let new = Png::from_file(&args.new_file)?;
+    let changes = diff::diff(&old, &new);
 
-    if file.exists() {
-        fs::remove_file(file).map_err(|_| CommandError::DeleteFileError)?;
-        println!("Deleted: {:?}", file);
-        Ok(())
+    if out.format() != OutputFormat::Text {
+        let value = Value::object(vec![
+            ("old_file", args.old_file.display().to_string().into()),
+            ("new_file", args.new_file.display().to_string().into()),
+            ("identical", changes.is_empty().into()),
+            ("changes", Value::Array(changes.iter().map(Value::from).collect())),
+        ]);
+        out.document(value);
+    } else if changes.is_empty() {
+        out.line("No differences");
     } else {
-        println!("No file at path: {:?}", file);
-        Err(CommandError::DeleteFileError)?
+        for change in &changes {
+            out.line(change);
+        }
+    }
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn set_dpi(file_path: &Path, args: &SetDpiArgs) -> Result<(), CommandError> {
+    let dpi = args.dpi.ok_or(CommandError::MissingArgument("resolution"))?;
+    set_ancillary(file_path, &Ancillary::PhysicalDimensions(dpi), &args.write)
+}
+
+#[allow(dead_code)]
+pub fn touch(file_path: &Path, args: &TouchArgs) -> Result<(), CommandError> {
+    set_ancillary(file_path, &Ancillary::Time(args.time), &args.write)
+}
+
+#[allow(dead_code)]
+pub fn text_list(file_path: &Path, out: &mut Output) -> Result<(), CommandError> {
+    let png = Png::from_file(file_path)?;
+
+    // A malformed chunk is reported and skipped so the other entries are still listed
+    let mut entries = Vec::new();
+    for (idx, chunk) in png.chunks().iter().enumerate().filter(|(_, c)| TextEntry::is_text_chunk(c)) {
+        match TextEntry::try_from(chunk) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => out.note(format!("warning: skipping chunk#{} ({}): {}", idx, chunk.chunk_type(), e)),
+        }
     }
 
+    if out.format() != OutputFormat::Text {
+        let value = Value::object(vec![
+            ("file", file_path.display().to_string().into()),
+            ("entries", Value::Array(entries.iter().map(Value::from).collect())),
+        ]);
+        out.document(value);
+        return Ok(());
+    }
+
+    for entry in entries {
+        out.line(entry);
+    }
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn text_get(file_path: &Path, args: &TextGetArgs, out: &mut Output) -> Result<(), CommandError> {
+    let keyword = args.keyword.as_deref().ok_or(CommandError::MissingArgument("keyword"))?;
+    let png = Png::from_file(file_path)?;
+
+    let entry = png.text(keyword)?.ok_or_else(|| CommandError::KeywordNotFound(keyword.to_string()))?;
+    if out.format() != OutputFormat::Text {
+        let value = Value::object(vec![
+            ("file", file_path.display().to_string().into()),
+            ("entry", (&entry).into()),
+        ]);
+        out.document(value);
+        return Ok(());
+    }
+
+    out.line(entry.text);
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn text_set(file_path: &Path, args: &TextSetArgs) -> Result<(), CommandError> {
+    let keyword = args.keyword.as_deref().ok_or(CommandError::MissingArgument("keyword"))?;
+    let text = args.text.as_deref().ok_or(CommandError::MissingArgument("text"))?;
+    let mut png = Png::from_file(file_path)?;
+
+    let entry = match args.kind {
+        TextChunkKind::Text => TextEntry::new(keyword, text),
+        TextChunkKind::Ztxt => TextEntry::compressed(keyword, text),
+        TextChunkKind::Itxt => TextEntry::international(
+            keyword,
+            text,
+            &args.language,
+            &args.translated_keyword,
+            args.compress,
+        ),
+    };
+    png.set_text(entry)?;
+
+    png.write_file_with(file_path, &args.write.options())?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn text_delete(file_path: &Path, args: &TextDeleteArgs) -> Result<(), CommandError> {
+    let keyword = args.keyword.as_deref().ok_or(CommandError::MissingArgument("keyword"))?;
+    let mut png = Png::from_file(file_path)?;
+
+    if png.remove_text(keyword) == 0 {
+        return Err(CommandError::KeywordNotFound(keyword.to_string()));
+    }
+
+    png.write_file_with(file_path, &args.write.options())?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn chunk_extract(file_path: &Path, args: &ChunkExtractArgs, out: &mut Output) -> Result<(), CommandError> {
+    let selector = args.chunk.as_ref().ok_or(CommandError::MissingArgument("chunk type or index"))?;
+    let png = Png::from_file(file_path)?;
+    let (index, chunk) = match selector {
+        ChunkSelector::Index(idx) => (*idx, png.chunks().get(*idx).ok_or(PngError::IndexOutOfBounds(*idx))?),
+        ChunkSelector::Type(chunk_type) => {
+            let chunk_type = ChunkType::from_str(chunk_type)?;
+            png.chunks()
+                .iter()
+                .enumerate()
+                .find(|(_, c)| *c.chunk_type() == chunk_type)
+                .ok_or_else(|| PngError::ChunkNotFound(chunk_type.to_string()))?
+        }
+    };
+
+    let bytes = if args.record { chunk.as_bytes() } else { chunk.data().to_vec() };
+    write_output(&args.output_file, &bytes, out).map_err(|e| CommandError::io(&args.output_file, e))?;
+
+    // Keep stdout clean when the chunk itself went there
+    if args.output_file.as_os_str() != "-" {
+        out.line(format!("Wrote {} bytes of chunk#{} ({}) to {}", bytes.len(), index, chunk.chunk_type(), args.output_file.display()));
+    }
+    Ok(())
+}
+
+// The chunk chunk inject adds, read once so that - (stdin) works for every file of a batch
+pub fn injected_chunk(args: &ChunkInjectArgs) -> Result<Chunk, CommandError> {
+    let bytes = read_input(&args.data_file).map_err(|e| CommandError::io(&args.data_file, e))?;
+
+    match &args.chunk_type {
+        Some(chunk_type) => Ok(Chunk::new(ChunkType::from_str(chunk_type)?, bytes)),
+        None => {
+            let chunk = Chunk::try_from(bytes.as_slice()).map_err(PngError::Chunk)?;
+            let record_length = chunk.data().len() + 12;
+            if bytes.len() > record_length {
+                return Err(CommandError::TrailingData(bytes.len() - record_length));
+            }
+            Ok(chunk)
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub fn chunk_inject(file_path: &Path, args: &ChunkInjectArgs, chunk: &Chunk, out: &mut Output) -> Result<(), CommandError> {
+    let mut png = Png::from_file(file_path)?;
+
+    let index = insertion_index(&png, args.at)?;
+    png.insert_chunk(index, chunk.clone())?;
+
+    png.write_file_with(file_path, &args.write.options())?;
+    out.line(format!("Inserted chunk#{} ({}, {} bytes)", index, chunk.chunk_type(), chunk.data().len()));
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn chunk_set(file_path: &Path, args: &ChunkSetArgs) -> Result<(), CommandError> {
+    let chunk_type = args.chunk_type.as_deref().ok_or(CommandError::MissingArgument("chunk type"))?;
+    let value = args.value.as_deref().ok_or(CommandError::MissingArgument("value"))?;
+    let value = Ancillary::parse(chunk_type, value)?;
+    set_ancillary(file_path, &value, &args.write)
+}
+
+#[allow(dead_code)]
+pub fn palette_list(file_path: &Path, out: &mut Output) -> Result<(), CommandError> {
+    let png = Png::from_file(file_path)?;
+    let ihdr = png.ihdr()?;
+    let palette = read_palette(&png)?;
+
+    if out.format() != OutputFormat::Text {
+        let value = Value::object(vec![
+            ("file", file_path.display().to_string().into()),
+            ("color_type", ihdr.color_type.value().into()),
+            ("bit_depth", ihdr.bit_depth.into()),
+            ("entries", Value::Array(palette.entries.iter().map(Value::from).collect())),
+        ]);
+        out.document(value);
+        return Ok(());
+    }
+
+    out.line(format!("File: {}, Palette entries: {}", file_path.display(), palette.entries.len()));
+    for (index, entry) in palette.entries.iter().enumerate() {
+        out.line(format!("  entry#{}{{ {}}}", index, entry));
+    }
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn palette_export(file_path: &Path, args: &PaletteExportArgs, out: &mut Output) -> Result<(), CommandError> {
+    let png = Png::from_file(file_path)?;
+    let palette = read_palette(&png)?;
+
+    let name = file_path.file_stem().map_or_else(|| "palette".into(), |stem| stem.to_string_lossy());
+    write_output(&args.output_file, palette.to_gpl(&name).as_bytes(), out).map_err(|e| CommandError::io(&args.output_file, e))?;
+
+    // Keep stdout clean when the palette itself went there
+    if args.output_file.as_os_str() != "-" {
+        out.line(format!("Wrote {} palette entries to {}", palette.entries.len(), args.output_file.display()));
+    }
+    Ok(())
+}
+
+// Only PLTE and tRNS are rewritten; the image data keeps pointing at the same indices
+#[allow(dead_code)]
+pub fn palette_recolor(file_path: &Path, args: &PaletteRecolorArgs, out: &mut Output) -> Result<(), CommandError> {
+    if args.mappings.is_empty() {
+        return Err(CommandError::MissingArgument("color mapping"));
+    }
+    let mut png = Png::from_file(file_path)?;
+    let mut palette = read_palette(&png)?;
+
+    let changed = palette.recolor(&args.mappings).map_err(PngError::from)?;
+    out.line(format!("Recolored {} of {} palette entries", changed, palette.entries.len()));
+    if changed == 0 {
+        return Ok(());
+    }
+
+    png.set_palette(&palette)?;
+    png.write_file_with(file_path, &args.write.options())?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn apng_list(file_path: &Path, out: &mut Output) -> Result<(), CommandError> {
+    let png = Png::from_file(file_path)?;
+    let control = png.animation_control()?.ok_or(PngError::Apng(ApngError::NotAnimated))?;
+    let frames = png.frames()?;
+
+    if out.format() != OutputFormat::Text {
+        let frames = frames
+            .iter()
+            .enumerate()
+            .map(|(index, frame)| {
+                Value::object(vec![
+                    ("index", index.into()),
+                    ("default_image", frame.is_default_image.into()),
+                    ("control", (&frame.control).into()),
+                ])
+            })
+            .collect();
+        let value = Value::object(vec![
+            ("file", file_path.display().to_string().into()),
+            ("animation", (&control).into()),
+            ("frames", Value::Array(frames)),
+        ]);
+        out.document(value);
+        return Ok(());
+    }
+
+    out.line(control);
+    for (index, frame) in frames.iter().enumerate() {
+        let default = if frame.is_default_image { " (default image)" } else { "" };
+        out.line(format!("  frame#{}{{ {}}}{}", index, frame.control, default));
+    }
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn apng_extract(file_path: &Path, args: &ApngExtractArgs, out: &mut Output) -> Result<(), CommandError> {
+    let output_dir = args.output_dir.as_deref().ok_or(CommandError::MissingArgument("output directory"))?;
+    let png = Png::from_file(file_path)?;
+    let frames = match args.frame {
+        Some(index) => vec![(index, png.frame_png(index)?)],
+        None => png.frame_pngs()?.into_iter().enumerate().collect(),
+    };
+
+    fs::create_dir_all(output_dir).map_err(|e| CommandError::io(output_dir, e))?;
+    for (index, frame) in frames {
+        let output_file = output_dir.join(format!("frame_{:03}.png", index));
+        frame.write_file(&output_file)?;
+        out.line(format!("Wrote frame {} to {}", index, output_file.display()));
+    }
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn apng_assemble(args: &ApngAssembleArgs, out: &mut Output) -> Result<(), CommandError> {
+    let options = FrameOptions {
+        delay_num: args.delay,
+        delay_den: 1000,
+        dispose_op: args.dispose,
+        blend_op: args.blend,
+        ..FrameOptions::default()
+    };
+
+    let frames = args
+        .frames
+        .iter()
+        .map(|path| Ok((Png::from_file(path)?, options)))
+        .collect::<Result<Vec<_>, PngError>>()?;
+
+    Png::from_frames(&frames, args.plays)?.write_file(&args.output_file)?;
+    out.line(format!("Assembled {} frames into {}", frames.len(), args.output_file.display()));
+    Ok(())
+}
+
+// The passphrase or key file contents are fed to the KDF, so key files can be any length
+fn read_secret(passphrase: &Option<String>, key_file: &Option<PathBuf>) -> Result<Option<Vec<u8>>, CommandError> {
+    if let Some(passphrase) = passphrase {
+        return Ok(Some(passphrase.as_bytes().to_vec()));
+    }
+
+    match key_file {
+        Some(path) => fs::read(path).map(Some).map_err(|e| CommandError::io(path, e)),
+        None => Ok(None),
+    }
+}
+
+fn parse_chunk_types(types: &[String]) -> Result<Vec<ChunkType>, CommandError> {
+    Ok(types
+        .iter()
+        .map(|t| ChunkType::from_str(t))
+        .collect::<Result<_, _>>()?)
+}
+
+// Index the first new chunk goes to; before IEND falls back to the end when there is no IEND
+fn insertion_index(png: &Png, position: ChunkPosition) -> Result<usize, PngError> {
+    match position {
+        ChunkPosition::BeforeIend => Ok(png.position_before(&["IEND"])),
+        ChunkPosition::End => Ok(png.chunks().len()),
+        ChunkPosition::AfterIhdr => png.position_of("IHDR").map(|idx| idx + 1).ok_or(PngError::ChunkNotFound("IHDR".to_string())),
+        ChunkPosition::Index(idx) => Ok(idx),
+    }
+}
+
+// Replaces the chunks of the value's type, or adds one where the spec places it, and writes the image back
+fn set_ancillary(file_path: &Path, value: &Ancillary, write: &WriteArgs) -> Result<(), CommandError> {
+    let mut png = Png::from_file(file_path)?;
+    png.set_ancillary(value)?;
+
+    png.write_file_with(file_path, &write.options())?;
+    Ok(())
+}
+
+// The image's palette, a missing PLTE being reported like any other missing chunk
+fn read_palette(png: &Png) -> Result<Palette, PngError> {
+    png.palette()?.ok_or_else(|| PngError::ChunkNotFound("PLTE".to_string()))
+}
+
+// Reassembles the first split message found in the chunks
+fn join_pieces(chunks: &[Chunk], out: &mut Output) -> Result<Vec<u8>, SplitError> {
+    let pieces = chunks
+        .iter()
+        .map(|c| Piece::try_from(c.data()))
+        .collect::<Result<Vec<Piece>, SplitError>>()?;
+
+    let message_id = pieces.first().ok_or(SplitError::NoPieces)?.message_id;
+    let (pieces, others): (Vec<Piece>, Vec<Piece>) = pieces.into_iter().partition(|p| p.message_id == message_id);
+
+    if !others.is_empty() {
+        out.note(format!("note: ignoring {} piece(s) of other split messages", others.len()));
+    }
+    if !split::in_order(&pieces) {
+        out.note("warning: pieces are out of order in the file, reassembling by index");
+    }
+
+    split::join(&pieces)
+}
+
+// A path of - means stdin
+fn read_input(path: &Path) -> Result<Vec<u8>, std::io::Error> {
+    if path.as_os_str() == "-" {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes)?;
+        Ok(bytes)
+    } else {
+        fs::read(path)
+    }
+}
+
+// A path of - means stdout
+fn write_output(path: &Path, bytes: &[u8], out: &mut Output) -> Result<(), std::io::Error> {
+    if path.as_os_str() == "-" {
+        out.bytes(bytes)
+    } else {
+        fs::write(path, bytes)
+    }
+}
+
+// The same key that encrypts the payload also seeds the pixel order
+fn lsb_options(args: &LsbArgs, key: Option<Vec<u8>>) -> LsbOptions {
+    LsbOptions {
+        bits_per_channel: args.bits,
+        channels: args.channels.clone(),
+        key,
+    }
+}
+
+fn print_summary(file_path: &Path, size: u64, headers: &[ChunkHeader], out: &mut Output) {
+    out.line(format!(
+        "File: {}, Size: {}",
+        file_path.display(),
+        size
+    ));
+
+    for header in headers {
+        out.line(format!(
+            "  chunk#{}{{ chunk_type: {}, data_length: {}}}",
+            header.index,
+            header.chunk_type,
+            header.length,
+        ));
+    }
+}
+
+// Streams a PNG from a file, or from stdin when the path is -
+fn open_png(file_path: &Path, lenient: bool) -> Result<PngReader<Box<dyn Read>>, PngError> {
+    let input: Box<dyn Read> = if file_path.as_os_str() == "-" {
+        Box::new(BufReader::new(std::io::stdin()))
+    } else {
+        Box::new(BufReader::new(File::open(file_path).map_err(|e| PngError::io(file_path, e))?))
+    };
+
+    if lenient {
+        PngReader::new_lenient(input)
+    } else {
+        PngReader::new(input)
+    }
+}
+
+// The chunk headers of a file, plus the parsed IHDR and the few chunk bodies that were kept
+struct Layout {
+    headers: Vec<ChunkHeader>,
+    chunks: Vec<Chunk>,
+    ihdr: Result<Ihdr, PngError>,
+}
+
+// Every chunk header, plus the parsed IHDR and the chunks with one of the given types; other bodies are skipped
+fn read_layout<R: Read>(reader: &mut PngReader<R>, keep: &[&str]) -> Result<Layout, PngError> {
+    let mut headers = Vec::new();
+    let mut chunks = Vec::new();
+    let mut ihdr = None;
+
+    while let Some(header) = reader.next_header()? {
+        if header.chunk_type == "IHDR" && ihdr.is_none() {
+            ihdr = Some(Ihdr::try_from(reader.read_body()?.data()).map_err(PngError::from));
+        } else if keep.contains(&header.chunk_type.as_str()) {
+            chunks.push(reader.read_body()?);
+        } else {
+            reader.skip_body()?;
+        }
+        headers.push(header);
+    }
+
+    let ihdr = ihdr.unwrap_or_else(|| Err(PngError::ChunkNotFound("IHDR".to_string())));
+    Ok(Layout { headers, chunks, ihdr })
+}
+
+// Every chunk in structured form, plus the parsed IHDR; one chunk body is held in memory at a time
+fn read_chunk_values<R: Read>(reader: &mut PngReader<R>) -> Result<(Vec<Value>, Result<Ihdr, PngError>), PngError> {
+    let mut chunks = Vec::new();
+    let mut ihdr = None;
+
+    while let Some(header) = reader.next_header()? {
+        let chunk = reader.read_body()?;
+        if header.chunk_type == "IHDR" && ihdr.is_none() {
+            ihdr = Some(Ihdr::try_from(chunk.data()).map_err(PngError::from));
+        }
+        chunks.push(output::chunk_value(&header, &chunk));
+    }
+
+    Ok((chunks, ihdr.unwrap_or_else(|| Err(PngError::ChunkNotFound("IHDR".to_string())))))
+}
+
+// The chunks with a type, with their index, and how many there are in all
+struct FoundChunks {
+    chunks: Vec<(usize, Chunk)>,
+    count: usize,
+}
+
+// Reads every chunk with the type, or with `all` unset only the first one; the other bodies are only checked against their CRC
+fn find_chunks<R: Read>(reader: &mut PngReader<R>, chunk_type: &str, all: bool) -> Result<FoundChunks, PngError> {
+    let mut found = FoundChunks { chunks: Vec::new(), count: 0 };
+    let mut idx = 0;
+
+    while let Some(header) = reader.next_header()? {
+        if header.chunk_type == chunk_type {
+            if all || found.chunks.is_empty() {
+                found.chunks.push((idx, reader.read_body()?));
+            } else {
+                reader.skip_body()?;
+            }
+            found.count += 1;
+        } else {
+            reader.skip_body()?;
+        }
+        idx += 1;
+    }
+
+    Ok(found)
+}
+
+fn find_chunk_at<R: Read>(reader: &mut PngReader<R>, index: usize, chunk_type: Option<&str>) -> Result<(usize, Chunk), CommandError> {
+    let mut idx = 0;
+
+    while let Some(header) = reader.next_header()? {
+        if idx == index {
+            check_chunk_type(index, &header.chunk_type, chunk_type)?;
+            return Ok((idx, reader.read_body()?));
+        }
+        reader.skip_body()?;
+        idx += 1;
+    }
+
+    Err(PngError::IndexOutOfBounds(index).into())
+}
+
+// An index picks the chunk, so a chunk type given along with it only guards against picking the wrong one
+fn check_chunk_type(index: usize, actual: &str, expected: Option<&str>) -> Result<(), CommandError> {
+    match expected {
+        Some(expected) if expected != actual => Err(CommandError::WrongChunkType {
+            index,
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[allow(dead_code)]
+pub fn delete_file(file_path: &Path, out: &mut Output) -> Result<(), CommandError> {
+    if file_path.exists() {
+        fs::remove_file(file_path).map_err(|e| CommandError::io(file_path, e))?;
+        out.line(format!("Deleted: {:?}", file_path));
+        Ok(())
+    } else {
+        Err(CommandError::FileNotFound(file_path.to_path_buf()))
+    }
 }
 
 #[allow(dead_code)]
-pub fn download_file(args: &DownloadFromInternetArgs) -> Result<(), Box<dyn std::error::Error>> {
+pub fn download_file(args: &DownloadFromInternetArgs, out: &mut Output) -> Result<(), CommandError> {
     let res = get(args.url.clone())
-        .map_err(|_| CommandError::DownloadError)?;
+        .map_err(CommandError::Download)?;
 
     if !res.status().is_success() {
-        Err(CommandError::FailedToFindURL)?;
+        return Err(CommandError::UrlNotFound(res.status()));
     }
 
     let images_dir = Path::new("images");
     let file_path = images_dir.join(&args.output_file_name);
 
     let mut output_file = File::create(&file_path)
-        .map_err(|_| CommandError::DownloadError)?;
+        .map_err(|e| CommandError::io(&file_path, e))?;
 
     let mut image = res;
     copy(&mut image, &mut output_file)
-        .map_err(|_| CommandError::DownloadError)?;
+        .map_err(|e| CommandError::io(&file_path, e))?;
 
-    println!("Download file to: {:?}", file_path);
+    out.line(format!("Download file to: {:?}", file_path));
     Ok(())
 }
 
 #[allow(dead_code)]
-pub fn convert_file(args: &ConvertArgs) -> Result<(), Box<dyn std::error::Error>> {
+pub fn convert_file(input_path: &Path, args: &ConvertArgs, out: &mut Output) -> Result<(), CommandError> {
     #[allow(deprecated)]
-    let img = ImageReader::open(&args.input_path)
-        .map_err(|_| CommandError::FailedToFindURL)?
-        .decode().map_err(|_| CommandError::ConversionError)?;
+    let img = ImageReader::open(input_path)
+        .map_err(|e| CommandError::io(input_path, e))?
+        .decode().map_err(CommandError::Conversion)?;
     
-    let mut output_path = args.input_path.clone();
+    let mut output_path = input_path.to_path_buf();
     
     let format: Option<image::ImageFormat> = if args.convert_to_jpg {
         output_path.set_extension("jpeg");
@@ -162,13 +1042,13 @@ pub fn convert_file(args: &ConvertArgs) -> Result<(), Box<dyn std::error::Error>
     match format {
         Some(fmt) => {
             img.save_with_format(&output_path, fmt)
-                .map_err(|_| CommandError::ConversionError)?;
-            println!("Image converted and saved to {:?}", output_path);
+                .map_err(CommandError::Conversion)?;
+            out.line(format!("Image converted and saved to {:?}", output_path));
             Ok(())
         }
         None => { 
-            println!("No conversion format selected");
-            Err(CommandError::ConversionError)?
+            out.line("No conversion format selected");
+            Err(CommandError::MissingArgument("conversion format"))
         }
     }
 }
@@ -0,0 +1,178 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::{fmt, io};
+
+use crate::png::ancillary::AncillaryError;
+use crate::png::chunk_type::ChunkTypeError;
+use crate::png::image::PngError;
+use crate::png::palette::PaletteError;
+use crate::stego::crypto::CryptoError;
+use crate::stego::lsb::LsbError;
+use crate::stego::split::SplitError;
+
+// Process exit codes, picked by the kind of error a command failed with
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_IO: i32 = 3;
+pub const EXIT_INVALID_DATA: i32 = 4;
+pub const EXIT_NOT_FOUND: i32 = 5;
+pub const EXIT_VALIDATION: i32 = 6;
+pub const EXIT_CRYPTO: i32 = 7;
+pub const EXIT_NETWORK: i32 = 8;
+pub const EXIT_BATCH: i32 = 9;
+
+#[derive(Debug)]
+pub enum CommandError {
+    Png(PngError),
+    ChunkType(ChunkTypeError),
+    Ancillary(AncillaryError),
+    Crypto(CryptoError),
+    Lsb(LsbError),
+    Split(SplitError),
+    Io { path: PathBuf, source: io::Error },
+    FileNotFound(PathBuf),
+    KeywordNotFound(String),
+    Download(reqwest::Error),
+    UrlNotFound(reqwest::StatusCode),
+    Conversion(image::ImageError),
+    ValidationFailed(usize),
+    MissingArgument(&'static str),
+    UnexpectedArgument(String),
+    InvalidPattern(glob::PatternError),
+    NoMatches(String),
+    SingleFileOnly(&'static str),
+    BatchFailed { failed: usize, total: usize },
+    TrailingData(usize),
+    WrongChunkType { index: usize, expected: String, actual: String },
+}
+
+/**
+ * The error every command returns, wrapping the library errors it can run into.
+ *
+ * Wrapped errors display as themselves, so `source` skips straight to their cause.
+ *
+ * @returns io - Wraps an I/O error together with the path it happened on.
+ * @returns exit_code - Returns the process exit code for this kind of error.
+ */
+impl CommandError {
+    pub fn io(path: &Path, source: io::Error) -> CommandError {
+        CommandError::Io { path: path.to_path_buf(), source }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CommandError::Png(e) => match e {
+                PngError::Io { .. } => EXIT_IO,
+                PngError::ChunkNotFound(_)
+                | PngError::IndexOutOfBounds(_)
+                | PngError::Palette(PaletteError::IndexOutOfRange { .. }) => EXIT_NOT_FOUND,
+                // Only ever parsed from the command line
+                PngError::Palette(
+                    PaletteError::InvalidColor(_) | PaletteError::InvalidSelector(_) | PaletteError::InvalidMapping(_),
+                ) => EXIT_USAGE,
+                _ => EXIT_INVALID_DATA,
+            },
+            CommandError::Lsb(LsbError::Crypto(_)) | CommandError::Crypto(_) => EXIT_CRYPTO,
+            CommandError::Lsb(_) | CommandError::Split(_) | CommandError::Conversion(_) | CommandError::TrailingData(_) => {
+                EXIT_INVALID_DATA
+            }
+            CommandError::Io { .. } => EXIT_IO,
+            CommandError::FileNotFound(_)
+            | CommandError::KeywordNotFound(_)
+            | CommandError::NoMatches(_) => EXIT_NOT_FOUND,
+            CommandError::Download(_) | CommandError::UrlNotFound(_) => EXIT_NETWORK,
+            CommandError::ValidationFailed(_) => EXIT_VALIDATION,
+            CommandError::ChunkType(_)
+            | CommandError::Ancillary(_)
+            | CommandError::WrongChunkType { .. }
+            | CommandError::MissingArgument(_)
+            | CommandError::UnexpectedArgument(_)
+            | CommandError::InvalidPattern(_)
+            | CommandError::SingleFileOnly(_) => EXIT_USAGE,
+            CommandError::BatchFailed { .. } => EXIT_BATCH,
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Png(e) => write!(f, "{}", e),
+            CommandError::ChunkType(e) => write!(f, "{}", e),
+            CommandError::Ancillary(e) => write!(f, "{}", e),
+            CommandError::Crypto(e) => write!(f, "{}", e),
+            CommandError::Lsb(e) => write!(f, "{}", e),
+            CommandError::Split(e) => write!(f, "{}", e),
+            CommandError::Io { path, .. } => write!(f, "I/O error on {}", path.display()),
+            CommandError::FileNotFound(path) => write!(f, "No file at path {}", path.display()),
+            CommandError::KeywordNotFound(keyword) => write!(f, "No text chunk with keyword '{}'", keyword),
+            CommandError::Download(_) => write!(f, "Failed to download file from the internet"),
+            CommandError::UrlNotFound(status) => write!(f, "Failed to find URL from the internet: {}", status),
+            CommandError::Conversion(_) => write!(f, "Failed to convert file"),
+            CommandError::ValidationFailed(count) => write!(f, "Validation failed with {} error(s)", count),
+            CommandError::MissingArgument(name) => write!(f, "Missing {}", name),
+            CommandError::UnexpectedArgument(arg) => write!(f, "Unexpected argument '{}'", arg),
+            CommandError::InvalidPattern(_) => write!(f, "Invalid glob pattern"),
+            CommandError::NoMatches(input) => write!(f, "No files found for {}", input),
+            CommandError::SingleFileOnly(option) => write!(f, "The {} can only be used with a single input file", option),
+            CommandError::BatchFailed { failed, total } => write!(f, "{} of {} file(s) failed", failed, total),
+            CommandError::TrailingData(len) => write!(f, "{} unexpected bytes after the chunk record", len),
+            CommandError::WrongChunkType { index, expected, actual } => {
+                write!(f, "chunk#{} is {}, not {}", index, actual, expected)
+            }
+        }
+    }
+}
+
+impl Error for CommandError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CommandError::Png(e) => e.source(),
+            CommandError::ChunkType(e) => e.source(),
+            CommandError::Ancillary(e) => e.source(),
+            CommandError::Crypto(e) => e.source(),
+            CommandError::Lsb(e) => e.source(),
+            CommandError::Split(e) => e.source(),
+            CommandError::Io { source, .. } => Some(source),
+            CommandError::Download(e) => Some(e),
+            CommandError::Conversion(e) => Some(e),
+            CommandError::InvalidPattern(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<PngError> for CommandError {
+    fn from(e: PngError) -> Self {
+        CommandError::Png(e)
+    }
+}
+
+impl From<ChunkTypeError> for CommandError {
+    fn from(e: ChunkTypeError) -> Self {
+        CommandError::ChunkType(e)
+    }
+}
+
+impl From<AncillaryError> for CommandError {
+    fn from(e: AncillaryError) -> Self {
+        CommandError::Ancillary(e)
+    }
+}
+
+impl From<CryptoError> for CommandError {
+    fn from(e: CryptoError) -> Self {
+        CommandError::Crypto(e)
+    }
+}
+
+impl From<LsbError> for CommandError {
+    fn from(e: LsbError) -> Self {
+        CommandError::Lsb(e)
+    }
+}
+
+impl From<SplitError> for CommandError {
+    fn from(e: SplitError) -> Self {
+        CommandError::Split(e)
+    }
+}
@@ -1,2 +1,5 @@
 pub mod args;
-pub mod commands;
\ No newline at end of file
+pub mod batch;
+pub mod commands;
+pub mod error;
+pub mod output;
@@ -0,0 +1,557 @@
+use std::fmt::{Display, Write as _};
+use std::io::{self, Write};
+
+use crate::img_cli::args::OutputFormat;
+use crate::png::ancillary::{Ancillary, Background, PhysicalUnit};
+use crate::png::apng::{AnimationControl, FrameControl};
+use crate::png::chunk::Chunk;
+use crate::png::diff::{ChunkChange, FieldChange};
+use crate::png::ihdr::Ihdr;
+use crate::png::image::CorruptedChunk;
+use crate::png::lint::Diagnostic;
+use crate::png::palette::PaletteEntry;
+use crate::png::reader::ChunkHeader;
+use crate::png::text::{TextEntry, TextKind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+/**
+ * A structured value for the machine readable output of `--format json` and `--format yaml`.
+ *
+ * Object keys keep the order they were added in. JSON is indented by two spaces; YAML uses block
+ * style with every string double quoted, so values never need guessing at their type.
+ *
+ * @returns object - Builds an object from key and value pairs.
+ * @returns to_json - Renders the value as JSON.
+ * @returns to_yaml - Renders the value as a YAML document, ending with a newline.
+ */
+impl Value {
+    pub fn object<K: Into<String>>(fields: Vec<(K, Value)>) -> Value {
+        Value::Object(fields.into_iter().map(|(key, value)| (key.into(), value)).collect())
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out, 0);
+        out
+    }
+
+    pub fn to_yaml(&self) -> String {
+        let mut out = String::new();
+        match self {
+            Value::Array(items) if !items.is_empty() => self.write_yaml(&mut out, 0),
+            Value::Object(fields) if !fields.is_empty() => self.write_yaml(&mut out, 0),
+            scalar => {
+                scalar.write_scalar(&mut out);
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    fn write_json(&self, out: &mut String, indent: usize) {
+        let pad = "  ".repeat(indent + 1);
+
+        match self {
+            Value::Array(items) if !items.is_empty() => {
+                out.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    out.push_str(&pad);
+                    item.write_json(out, indent + 1);
+                    out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&"  ".repeat(indent));
+                out.push(']');
+            }
+            Value::Object(fields) if !fields.is_empty() => {
+                out.push_str("{\n");
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    out.push_str(&pad);
+                    write_string(out, key);
+                    out.push_str(": ");
+                    value.write_json(out, indent + 1);
+                    out.push_str(if i + 1 < fields.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&"  ".repeat(indent));
+                out.push('}');
+            }
+            scalar => scalar.write_scalar(out),
+        }
+    }
+
+    // Non-empty arrays and objects only; everything else is written inline by the caller
+    fn write_yaml(&self, out: &mut String, indent: usize) {
+        let pad = "  ".repeat(indent);
+
+        match self {
+            Value::Array(items) => {
+                for item in items {
+                    if item.is_block() {
+                        // The item is written one level deeper, then its first indent becomes the dash
+                        let mut nested = String::new();
+                        item.write_yaml(&mut nested, indent + 1);
+                        out.push_str(&pad);
+                        out.push_str("- ");
+                        out.push_str(&nested[pad.len() + 2..]);
+                    } else {
+                        out.push_str(&pad);
+                        out.push_str("- ");
+                        item.write_scalar(out);
+                        out.push('\n');
+                    }
+                }
+            }
+            Value::Object(fields) => {
+                for (key, value) in fields {
+                    out.push_str(&pad);
+                    out.push_str(key);
+                    if value.is_block() {
+                        out.push_str(":\n");
+                        value.write_yaml(out, indent + 1);
+                    } else {
+                        out.push_str(": ");
+                        value.write_scalar(out);
+                        out.push('\n');
+                    }
+                }
+            }
+            scalar => scalar.write_scalar(out),
+        }
+    }
+
+    fn is_block(&self) -> bool {
+        match self {
+            Value::Array(items) => !items.is_empty(),
+            Value::Object(fields) => !fields.is_empty(),
+            _ => false,
+        }
+    }
+
+    // Scalars and empty containers look the same in JSON and YAML flow style
+    fn write_scalar(&self, out: &mut String) {
+        match self {
+            Value::Null => out.push_str("null"),
+            Value::Bool(value) => out.push_str(&value.to_string()),
+            Value::Number(value) => out.push_str(&value.to_string()),
+            Value::String(value) => write_string(out, value),
+            Value::Array(_) => out.push_str("[]"),
+            Value::Object(_) => out.push_str("{}"),
+        }
+    }
+}
+
+/**
+ * Prints a value in the given format.
+ *
+ * Commands print their own text output and only call this for json and yaml, through `Output`; text
+ * falls back to YAML, the closest of the two to plain text.
+ */
+pub fn print(value: &Value, format: OutputFormat) {
+    match format {
+        OutputFormat::Json => println!("{}", value.to_json()),
+        OutputFormat::Yaml | OutputFormat::Text => print!("{}", value.to_yaml()),
+    }
+}
+
+pub struct Output {
+    format: OutputFormat,
+    buffered: bool,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    document: Option<Value>,
+}
+
+/**
+ * Where a command prints what it has to say about one file.
+ *
+ * A direct output prints as it goes. A buffered one, used when several files run at once, holds the
+ * text back until the file is done so the output of different files never interleaves, and keeps the
+ * structured value apart so the batch can print the values of all its files as one document.
+ *
+ * @returns direct - Creates an output that prints straight away.
+ * @returns buffered - Creates an output that holds everything until `flush`.
+ * @returns format - Returns the format the command should print in.
+ * @returns line - Prints a line of text to stdout.
+ * @returns note - Prints a line of text to stderr.
+ * @returns bytes - Writes raw bytes to stdout, e.g. a payload written to `-`.
+ * @returns document - Prints the structured value of the file.
+ * @returns flush - Writes the held back text to stdout and stderr and returns the held back value.
+ */
+impl Output {
+    pub fn direct(format: OutputFormat) -> Output {
+        Output { format, buffered: false, stdout: Vec::new(), stderr: Vec::new(), document: None }
+    }
+
+    pub fn buffered(format: OutputFormat) -> Output {
+        Output { buffered: true, ..Output::direct(format) }
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    pub fn line(&mut self, line: impl Display) {
+        if self.buffered {
+            let _ = writeln!(self.stdout, "{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    pub fn note(&mut self, line: impl Display) {
+        if self.buffered {
+            let _ = writeln!(self.stderr, "{}", line);
+        } else {
+            eprintln!("{}", line);
+        }
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if self.buffered {
+            self.stdout.extend_from_slice(bytes);
+            return Ok(());
+        }
+
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(bytes)?;
+        stdout.flush()
+    }
+
+    pub fn document(&mut self, value: Value) {
+        if self.buffered {
+            self.document = Some(value);
+        } else {
+            print(&value, self.format);
+        }
+    }
+
+    // Both streams are locked for the whole write, so another thread's output cannot land in the middle
+    pub fn flush(&mut self) -> io::Result<Option<Value>> {
+        let mut stdout = io::stdout().lock();
+        let mut stderr = io::stderr().lock();
+        stdout.write_all(&std::mem::take(&mut self.stdout))?;
+        stdout.flush()?;
+        stderr.write_all(&std::mem::take(&mut self.stderr))?;
+        Ok(self.document.take())
+    }
+}
+
+// JSON escapes, which YAML double quoted strings also accept
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\u{7f}' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/**
+ * The structured form of a chunk, as printed by `print` and `info`.
+ *
+ * `fields` holds the decoded contents of IHDR, text, APNG control and standard ancillary chunks
+ * (gAMA, cHRM, sRGB, pHYs, tIME, bKGD, sBIT, hIST), and is null for other chunk types or chunks that
+ * fail to decode.
+ */
+pub fn chunk_value(header: &ChunkHeader, chunk: &Chunk) -> Value {
+    let chunk_type = chunk.chunk_type();
+
+    Value::object(vec![
+        ("index", header.index.into()),
+        ("chunk_type", header.chunk_type.as_str().into()),
+        ("offset", header.offset.into()),
+        ("length", header.length.into()),
+        ("crc", chunk.crc().into()),
+        ("crc_valid", chunk.is_crc_valid().into()),
+        ("critical", chunk_type.is_critical().into()),
+        ("private", chunk_type.is_private().into()),
+        ("reserved_bit_valid", chunk_type.is_reserved_bit_valid().into()),
+        ("safe_to_copy", chunk_type.is_safe_to_copy().into()),
+        ("fields", chunk_fields(chunk).unwrap_or(Value::Null)),
+    ])
+}
+
+fn chunk_fields(chunk: &Chunk) -> Option<Value> {
+    match chunk.chunk_type().to_string().as_str() {
+        "IHDR" => Ihdr::try_from(chunk.data()).ok().map(|ihdr| Value::from(&ihdr)),
+        "tEXt" | "zTXt" | "iTXt" => TextEntry::try_from(chunk).ok().map(|entry| Value::from(&entry)),
+        "acTL" => AnimationControl::from_chunk(chunk).ok().map(|control| Value::from(&control)),
+        "fcTL" => FrameControl::from_chunk(chunk).ok().map(|control| Value::from(&control)),
+        _ => Ancillary::from_chunk(chunk)?.ok().map(|value| Value::from(&value)),
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<u8> for Value {
+    fn from(value: u8) -> Self {
+        Value::Number(value.into())
+    }
+}
+
+impl From<u16> for Value {
+    fn from(value: u16) -> Self {
+        Value::Number(value.into())
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Value::Number(value.into())
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Value::Number(value as f64)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Value::Number(value as f64)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl From<&Ihdr> for Value {
+    fn from(ihdr: &Ihdr) -> Self {
+        Value::object(vec![
+            ("width", ihdr.width.into()),
+            ("height", ihdr.height.into()),
+            ("bit_depth", ihdr.bit_depth.into()),
+            ("color_type", ihdr.color_type.value().into()),
+            ("compression_method", ihdr.compression_method.into()),
+            ("filter_method", ihdr.filter_method.into()),
+            ("interlace_method", ihdr.interlace_method.into()),
+        ])
+    }
+}
+
+impl From<&TextEntry> for Value {
+    fn from(entry: &TextEntry) -> Self {
+        let (compressed, language_tag, translated_keyword) = match &entry.kind {
+            TextKind::Text => (false, None, None),
+            TextKind::Compressed => (true, None, None),
+            TextKind::International { compressed, language_tag, translated_keyword } => {
+                (*compressed, Some(language_tag.as_str()), Some(translated_keyword.as_str()))
+            }
+        };
+
+        Value::object(vec![
+            ("keyword", entry.keyword.as_str().into()),
+            ("text", entry.text.as_str().into()),
+            ("compressed", compressed.into()),
+            ("language_tag", language_tag.into()),
+            ("translated_keyword", translated_keyword.into()),
+        ])
+    }
+}
+
+impl From<&AnimationControl> for Value {
+    fn from(control: &AnimationControl) -> Self {
+        Value::object(vec![
+            ("num_frames", control.num_frames.into()),
+            ("num_plays", control.num_plays.into()),
+        ])
+    }
+}
+
+impl From<&FrameControl> for Value {
+    fn from(control: &FrameControl) -> Self {
+        Value::object(vec![
+            ("sequence_number", control.sequence_number.into()),
+            ("width", control.width.into()),
+            ("height", control.height.into()),
+            ("x_offset", control.x_offset.into()),
+            ("y_offset", control.y_offset.into()),
+            ("delay_num", control.delay_num.into()),
+            ("delay_den", control.delay_den.into()),
+            ("dispose_op", control.dispose_op.to_string().into()),
+            ("blend_op", control.blend_op.to_string().into()),
+        ])
+    }
+}
+
+// The stored values, plus the derived ones info shows: gamma as a float, DPI, an ISO timestamp
+impl From<&Ancillary> for Value {
+    fn from(value: &Ancillary) -> Self {
+        let fixed_point = |value: u32| Value::from(f64::from(value) / 100_000.0);
+        let numbers = |values: Vec<u16>| Value::Array(values.into_iter().map(Value::from).collect());
+
+        match value {
+            Ancillary::Gamma(gamma) => Value::object(vec![
+                ("gamma", gamma.value().into()),
+                ("gamma_raw", gamma.gamma.into()),
+            ]),
+            Ancillary::Chromaticities(chrm) => Value::object(vec![
+                ("white_x", fixed_point(chrm.white_x)),
+                ("white_y", fixed_point(chrm.white_y)),
+                ("red_x", fixed_point(chrm.red_x)),
+                ("red_y", fixed_point(chrm.red_y)),
+                ("green_x", fixed_point(chrm.green_x)),
+                ("green_y", fixed_point(chrm.green_y)),
+                ("blue_x", fixed_point(chrm.blue_x)),
+                ("blue_y", fixed_point(chrm.blue_y)),
+            ]),
+            Ancillary::Srgb(srgb) => Value::object(vec![
+                ("rendering_intent", srgb.rendering_intent.value().into()),
+            ]),
+            Ancillary::PhysicalDimensions(phys) => {
+                let dpi = phys.dpi();
+                Value::object(vec![
+                    ("pixels_per_unit_x", phys.pixels_per_unit_x.into()),
+                    ("pixels_per_unit_y", phys.pixels_per_unit_y.into()),
+                    ("unit", if phys.unit == PhysicalUnit::Meter { "meter" } else { "unknown" }.into()),
+                    ("dpi_x", dpi.map(|(x, _)| x).into()),
+                    ("dpi_y", dpi.map(|(_, y)| y).into()),
+                ])
+            }
+            Ancillary::Time(time) => Value::object(vec![
+                ("year", time.year.into()),
+                ("month", time.month.into()),
+                ("day", time.day.into()),
+                ("hour", time.hour.into()),
+                ("minute", time.minute.into()),
+                ("second", time.second.into()),
+                ("iso", time.to_string().into()),
+            ]),
+            Ancillary::Background(background) => match *background {
+                Background::Gray(gray) => Value::object(vec![("gray", gray.into())]),
+                Background::Rgb(r, g, b) => Value::object(vec![("rgb", numbers(vec![r, g, b]))]),
+                Background::PaletteIndex(index) => Value::object(vec![("palette_index", index.into())]),
+            },
+            Ancillary::SignificantBits(bits) => Value::object(vec![
+                ("bits", Value::Array(bits.bits().into_iter().map(Value::from).collect())),
+            ]),
+            Ancillary::Histogram(histogram) => Value::object(vec![
+                ("frequencies", numbers(histogram.frequencies.clone())),
+            ]),
+        }
+    }
+}
+
+impl From<&PaletteEntry> for Value {
+    fn from(entry: &PaletteEntry) -> Self {
+        Value::object(vec![
+            ("red", entry.red.into()),
+            ("green", entry.green.into()),
+            ("blue", entry.blue.into()),
+            ("alpha", entry.alpha.into()),
+            ("hex", entry.to_string().into()),
+        ])
+    }
+}
+
+impl From<&CorruptedChunk> for Value {
+    fn from(corrupted: &CorruptedChunk) -> Self {
+        Value::object(vec![
+            ("index", corrupted.index.into()),
+            ("chunk_type", corrupted.chunk_type.as_str().into()),
+            ("offset", corrupted.offset.into()),
+            ("expected_crc", corrupted.expected.into()),
+            ("actual_crc", corrupted.actual.into()),
+        ])
+    }
+}
+
+impl From<&Diagnostic> for Value {
+    fn from(diagnostic: &Diagnostic) -> Self {
+        Value::object(vec![
+            ("severity", diagnostic.severity.to_string().into()),
+            ("chunk_index", diagnostic.chunk_index.into()),
+            ("chunk_type", diagnostic.chunk_type.as_str().into()),
+            ("message", diagnostic.message.as_str().into()),
+        ])
+    }
+}
+
+impl From<&FieldChange> for Value {
+    fn from(change: &FieldChange) -> Self {
+        Value::object(vec![
+            ("field", change.field.as_str().into()),
+            ("old", change.old.as_str().into()),
+            ("new", change.new.as_str().into()),
+        ])
+    }
+}
+
+impl From<&ChunkChange> for Value {
+    fn from(change: &ChunkChange) -> Self {
+        match change {
+            ChunkChange::Added { new_index, chunk_type, length } => Value::object(vec![
+                ("change", "added".into()),
+                ("chunk_type", chunk_type.as_str().into()),
+                ("new_index", (*new_index).into()),
+                ("length", (*length).into()),
+            ]),
+            ChunkChange::Removed { old_index, chunk_type, length } => Value::object(vec![
+                ("change", "removed".into()),
+                ("chunk_type", chunk_type.as_str().into()),
+                ("old_index", (*old_index).into()),
+                ("length", (*length).into()),
+            ]),
+            ChunkChange::Moved { old_index, new_index, chunk_type } => Value::object(vec![
+                ("change", "moved".into()),
+                ("chunk_type", chunk_type.as_str().into()),
+                ("old_index", (*old_index).into()),
+                ("new_index", (*new_index).into()),
+            ]),
+            ChunkChange::Modified { old_index, new_index, chunk_type, bytes, fields } => Value::object(vec![
+                ("change", "modified".into()),
+                ("chunk_type", chunk_type.as_str().into()),
+                ("old_index", (*old_index).into()),
+                ("new_index", (*new_index).into()),
+                ("old_length", bytes.old_length.into()),
+                ("new_length", bytes.new_length.into()),
+                ("changed_bytes", bytes.changed_bytes.into()),
+                ("first_difference", bytes.first_difference.into()),
+                ("fields", Value::Array(fields.iter().map(Value::from).collect())),
+            ]),
+        }
+    }
+}
@@ -1,24 +1,96 @@
 pub mod png;
 pub mod img_cli;
+pub mod stego;
 
-use crate::img_cli::args::{CliArgs, Commands, ManageCommands};
-use crate::img_cli::commands::{decode, encode, remove, print_chunks, delete_file, download_file, convert_file};
+use std::path::PathBuf;
 
-pub type Error = Box<dyn std::error::Error>;
+use crate::img_cli::args::{ApngCommands, ChunkCommands, CliArgs, Commands, ManageCommands, PaletteCommands, TextCommands};
+use crate::img_cli::commands::{decode, encode, read_message, remove, print_chunks, info, lint_file, strip, diff, set_dpi, touch, delete_file, download_file, convert_file};
+use crate::img_cli::commands::{text_list, text_get, text_set, text_delete};
+use crate::img_cli::commands::{chunk_extract, chunk_inject, injected_chunk, chunk_set};
+use crate::img_cli::commands::{palette_list, palette_export, palette_recolor};
+use crate::img_cli::commands::{apng_list, apng_extract, apng_assemble};
+use crate::img_cli::batch::Batch;
+use crate::img_cli::error::CommandError;
+use crate::img_cli::output::Output;
+
+pub type Error = CommandError;
 pub type Result<T> = std::result::Result<T, Error>;
 
-pub fn run(cli: CliArgs) -> Result<()> {
-       match cli.command {
-        Commands::Encode(args) => encode(&args),
-        Commands::Decode(args) => decode(&args),
-        Commands::Remove(args) => remove(&args),
-        Commands::Print(args) => print_chunks(&args),
+pub fn run(mut cli: CliArgs) -> Result<()> {
+    cli.resolve_positionals()?;
+    let format = cli.format;
+    let batch = |inputs: &[PathBuf]| Batch::resolve(inputs, &cli.batch);
+
+    match cli.command {
+        Commands::Encode(args) => {
+            let files = batch(&args.file_paths)?.single_file_only("--output-file option", args.output_file.is_some())?;
+            let message = read_message(&args)?;
+            files.run(format, |file, out| encode(file, &args, &message, out))
+        }
+        Commands::Decode(args) => batch(&args.file_paths)?
+            .single_file_only("--output-file option", args.output_file.as_ref().is_some_and(|path| path.as_os_str() != "-"))?
+            .run(format, |file, out| decode(file, &args, out)),
+        Commands::Remove(args) => batch(&args.file_paths)?.run(format, |file, out| remove(file, &args, out)),
+        Commands::Print(args) => batch(&args.file_paths)?.run(format, |file, out| print_chunks(file, &args, out)),
+        Commands::Info(args) => batch(&args.file_paths)?.run(format, info),
+        Commands::Lint(args) => batch(&args.file_paths)?.run(format, lint_file),
+        Commands::Strip(args) => batch(&args.file_paths)?
+            .single_file_only("--output-file option", args.output_file.is_some())?
+            .run(format, |file, out| strip(file, &args, out)),
+        Commands::Diff(args) => diff(&args, &mut Output::direct(format)),
+        Commands::SetDpi(args) => batch(&args.file_paths)?.run(format, |file, _| set_dpi(file, &args)),
+        Commands::Touch(args) => batch(&args.file_paths)?.run(format, |file, _| touch(file, &args)),
+
+        Commands::Text(text_args) => {
+            match text_args.text_command {
+                TextCommands::List(args) => batch(&args.file_paths)?.run(format, text_list),
+                TextCommands::Get(args) => batch(&args.file_paths)?.run(format, |file, out| text_get(file, &args, out)),
+                TextCommands::Set(args) => batch(&args.file_paths)?.run(format, |file, _| text_set(file, &args)),
+                TextCommands::Delete(args) => batch(&args.file_paths)?.run(format, |file, _| text_delete(file, &args)),
+            }
+        }
+
+        Commands::Chunk(chunk_args) => {
+            match chunk_args.chunk_command {
+                ChunkCommands::Extract(args) => batch(&args.file_paths)?
+                    .single_file_only("--output-file option", args.output_file.as_os_str() != "-")?
+                    .run(format, |file, out| chunk_extract(file, &args, out)),
+                ChunkCommands::Inject(args) => {
+                    let files = batch(&args.file_paths)?;
+                    let chunk = injected_chunk(&args)?;
+                    files.run(format, |file, out| chunk_inject(file, &args, &chunk, out))
+                }
+                ChunkCommands::Set(args) => batch(&args.file_paths)?.run(format, |file, _| chunk_set(file, &args)),
+            }
+        }
+
+        Commands::Palette(palette_args) => {
+            match palette_args.palette_command {
+                PaletteCommands::List(args) => batch(&args.file_paths)?.run(format, palette_list),
+                PaletteCommands::Export(args) => batch(&args.file_paths)?
+                    .single_file_only("--output-file option", args.output_file.as_os_str() != "-")?
+                    .run(format, |file, out| palette_export(file, &args, out)),
+                PaletteCommands::Recolor(args) => batch(&args.file_paths)?.run(format, |file, out| palette_recolor(file, &args, out)),
+            }
+        }
+
+        Commands::Apng(apng_args) => {
+            match apng_args.apng_command {
+                ApngCommands::List(args) => batch(&args.file_paths)?.run(format, apng_list),
+                // Every input would write its frames to the same file names
+                ApngCommands::Extract(args) => batch(&args.file_paths)?
+                    .single_file_only("--output-dir option", true)?
+                    .run(format, |file, out| apng_extract(file, &args, out)),
+                ApngCommands::Assemble(args) => apng_assemble(&args, &mut Output::direct(format)),
+            }
+        }
 
         Commands::Manage(manage_args) => {
             match manage_args.manage_command {
-                ManageCommands::Delete(args) => delete_file(&args),
-                ManageCommands::Download(args) => download_file(&args),
-                ManageCommands::Convert(args) => convert_file(&args),
+                ManageCommands::Delete(args) => batch(&args.file_paths)?.run(format, delete_file),
+                ManageCommands::Download(args) => download_file(&args, &mut Output::direct(format)),
+                ManageCommands::Convert(args) => batch(&args.input_paths)?.run(format, |file, out| convert_file(file, &args, out)),
             }
         }
     }
@@ -0,0 +1,902 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::png::chunk::Chunk;
+use crate::png::chunk_type::ChunkType;
+use crate::png::ihdr::{ColorType, Ihdr};
+
+// Where a chunk has to go relative to the critical chunks, per the PNG spec
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    BeforePlte,
+    BeforeIdat,
+    Anywhere,
+}
+
+// gAMA: image gamma times 100000
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gamma {
+    pub gamma: u32,
+}
+
+// cHRM: CIE 1931 x,y chromaticities of the white point and primaries, each times 100000
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chromaticities {
+    pub white_x: u32,
+    pub white_y: u32,
+    pub red_x: u32,
+    pub red_y: u32,
+    pub green_x: u32,
+    pub green_y: u32,
+    pub blue_x: u32,
+    pub blue_y: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderingIntent {
+    Perceptual,
+    RelativeColorimetric,
+    Saturation,
+    AbsoluteColorimetric,
+}
+
+// sRGB: the image is in the sRGB color space, rendered with this intent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Srgb {
+    pub rendering_intent: RenderingIntent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhysicalUnit {
+    Unknown,
+    Meter,
+}
+
+// pHYs: pixel size; with an unknown unit it only gives the pixel aspect ratio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhysicalDimensions {
+    pub pixels_per_unit_x: u32,
+    pub pixels_per_unit_y: u32,
+    pub unit: PhysicalUnit,
+}
+
+// tIME: last modification time, in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Time {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+// bKGD: preferred background color; which form it takes follows the color type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Background {
+    Gray(u16),
+    Rgb(u16, u16, u16),
+    PaletteIndex(u8),
+}
+
+// sBIT: number of significant bits in each channel of the original image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignificantBits {
+    Gray(u8),
+    GrayAlpha(u8, u8),
+    Rgb(u8, u8, u8),
+    Rgba(u8, u8, u8, u8),
+}
+
+// hIST: approximate usage frequency of each palette entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    pub frequencies: Vec<u16>,
+}
+
+// Any of the chunks above, picked by chunk type
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ancillary {
+    Gamma(Gamma),
+    Chromaticities(Chromaticities),
+    Srgb(Srgb),
+    PhysicalDimensions(PhysicalDimensions),
+    Time(Time),
+    Background(Background),
+    SignificantBits(SignificantBits),
+    Histogram(Histogram),
+}
+
+#[derive(Debug)]
+pub enum AncillaryError {
+    InvalidLength { chunk_type: &'static str, length: usize },
+    InvalidRenderingIntent(u8),
+    InvalidUnit(u8),
+    InvalidTime(String),
+    InvalidValue { chunk_type: &'static str, value: String },
+    ColorTypeMismatch { chunk_type: &'static str, color_type: ColorType },
+    PaletteMismatch { chunk_type: &'static str, entries: Option<usize> },
+    UnknownChunkType(String),
+}
+
+/**
+ * A standard ancillary chunk with a fixed binary layout.
+ *
+ * Each implementation names the chunk type it is stored in and where the spec requires the chunk to
+ * be placed, so `Png::set_ancillary` can put a new one in the right spot.
+ *
+ * @returns from_data - Parses the chunk data.
+ * @returns to_data - Serializes the value into chunk data.
+ * @returns from_chunk - Parses a chunk, which must have this type.
+ * @returns to_chunk - Returns a chunk of this type holding the value.
+ */
+pub trait AncillaryChunk: Sized {
+    const CHUNK_TYPE: &'static str;
+    const PLACEMENT: Placement;
+
+    fn from_data(data: &[u8]) -> Result<Self, AncillaryError>;
+    fn to_data(&self) -> Vec<u8>;
+
+    fn from_chunk(chunk: &Chunk) -> Result<Self, AncillaryError> {
+        let chunk_type = chunk.chunk_type().to_string();
+        if chunk_type != Self::CHUNK_TYPE {
+            return Err(AncillaryError::UnknownChunkType(chunk_type));
+        }
+        Self::from_data(chunk.data())
+    }
+
+    fn to_chunk(&self) -> Chunk {
+        let chunk_type = ChunkType::from_str(Self::CHUNK_TYPE).expect("ancillary chunk types are valid");
+        Chunk::new(chunk_type, self.to_data())
+    }
+}
+
+// The chunk data as a fixed-size array, or an InvalidLength error for this chunk type
+fn fixed<'a, const N: usize>(chunk_type: &'static str, data: &'a [u8]) -> Result<&'a [u8; N], AncillaryError> {
+    data.try_into().map_err(|_| AncillaryError::InvalidLength { chunk_type, length: data.len() })
+}
+
+fn u32_at(bytes: &[u8], i: usize) -> u32 {
+    u32::from_be_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]])
+}
+
+fn u16_at(bytes: &[u8], i: usize) -> u16 {
+    u16::from_be_bytes([bytes[i], bytes[i + 1]])
+}
+
+// Values like gamma and chromaticities are stored as fixed point numbers times 100000
+fn to_fixed_point(chunk_type: &'static str, value: &str) -> Result<u32, AncillaryError> {
+    let invalid = || AncillaryError::InvalidValue { chunk_type, value: value.to_string() };
+    let parsed: f64 = value.trim().parse().map_err(|_| invalid())?;
+    let scaled = (parsed * 100_000.0).round();
+
+    if !(0.0..=u32::MAX as f64).contains(&scaled) {
+        return Err(invalid());
+    }
+    Ok(scaled as u32)
+}
+
+fn from_fixed_point(value: u32) -> f64 {
+    value as f64 / 100_000.0
+}
+
+// A comma separated list of numbers, e.g. "5,6,5"
+fn parse_list<T: FromStr>(chunk_type: &'static str, value: &str) -> Result<Vec<T>, AncillaryError> {
+    value
+        .split(',')
+        .map(|part| part.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| AncillaryError::InvalidValue { chunk_type, value: value.to_string() })
+}
+
+/**
+ * Provides methods for working with gAMA chunks.
+ *
+ * @returns value - Returns the gamma as a float, e.g. 0.45455 for sRGB-like images.
+ */
+impl Gamma {
+    pub fn value(&self) -> f64 {
+        from_fixed_point(self.gamma)
+    }
+}
+
+impl AncillaryChunk for Gamma {
+    const CHUNK_TYPE: &'static str = "gAMA";
+    const PLACEMENT: Placement = Placement::BeforePlte;
+
+    fn from_data(data: &[u8]) -> Result<Self, AncillaryError> {
+        let bytes = fixed::<4>(Self::CHUNK_TYPE, data)?;
+        Ok(Gamma { gamma: u32_at(bytes, 0) })
+    }
+
+    fn to_data(&self) -> Vec<u8> {
+        self.gamma.to_be_bytes().to_vec()
+    }
+}
+
+impl AncillaryChunk for Chromaticities {
+    const CHUNK_TYPE: &'static str = "cHRM";
+    const PLACEMENT: Placement = Placement::BeforePlte;
+
+    fn from_data(data: &[u8]) -> Result<Self, AncillaryError> {
+        let bytes = fixed::<32>(Self::CHUNK_TYPE, data)?;
+        Ok(Chromaticities {
+            white_x: u32_at(bytes, 0),
+            white_y: u32_at(bytes, 4),
+            red_x: u32_at(bytes, 8),
+            red_y: u32_at(bytes, 12),
+            green_x: u32_at(bytes, 16),
+            green_y: u32_at(bytes, 20),
+            blue_x: u32_at(bytes, 24),
+            blue_y: u32_at(bytes, 28),
+        })
+    }
+
+    fn to_data(&self) -> Vec<u8> {
+        [
+            self.white_x, self.white_y, self.red_x, self.red_y,
+            self.green_x, self.green_y, self.blue_x, self.blue_y,
+        ]
+        .iter()
+        .flat_map(|value| value.to_be_bytes())
+        .collect()
+    }
+}
+
+/**
+ * Provides methods for working with the rendering intent stored in sRGB.
+ *
+ * @returns value - Returns the intent code as stored in the file.
+ */
+impl RenderingIntent {
+    pub fn value(&self) -> u8 {
+        match self {
+            RenderingIntent::Perceptual => 0,
+            RenderingIntent::RelativeColorimetric => 1,
+            RenderingIntent::Saturation => 2,
+            RenderingIntent::AbsoluteColorimetric => 3,
+        }
+    }
+}
+
+impl AncillaryChunk for Srgb {
+    const CHUNK_TYPE: &'static str = "sRGB";
+    const PLACEMENT: Placement = Placement::BeforePlte;
+
+    fn from_data(data: &[u8]) -> Result<Self, AncillaryError> {
+        let bytes = fixed::<1>(Self::CHUNK_TYPE, data)?;
+        Ok(Srgb { rendering_intent: RenderingIntent::try_from(bytes[0])? })
+    }
+
+    fn to_data(&self) -> Vec<u8> {
+        vec![self.rendering_intent.value()]
+    }
+}
+
+/**
+ * Provides methods for working with pHYs chunks.
+ *
+ * @returns from_dpi - Builds a pHYs chunk for the given horizontal and vertical resolution in dots per inch.
+ * @returns dpi - Returns the horizontal and vertical resolution in dots per inch, or `None` if the unit is unknown.
+ */
+impl PhysicalDimensions {
+    pub const METERS_PER_INCH: f64 = 0.0254;
+
+    pub fn from_dpi(dpi_x: f64, dpi_y: f64) -> Result<PhysicalDimensions, AncillaryError> {
+        let per_meter = |dpi: f64| {
+            let ppm = (dpi / PhysicalDimensions::METERS_PER_INCH).round();
+            if dpi > 0.0 && ppm <= u32::MAX as f64 {
+                Ok(ppm as u32)
+            } else {
+                Err(AncillaryError::InvalidValue { chunk_type: Self::CHUNK_TYPE, value: dpi.to_string() })
+            }
+        };
+
+        Ok(PhysicalDimensions {
+            pixels_per_unit_x: per_meter(dpi_x)?,
+            pixels_per_unit_y: per_meter(dpi_y)?,
+            unit: PhysicalUnit::Meter,
+        })
+    }
+
+    pub fn dpi(&self) -> Option<(f64, f64)> {
+        match self.unit {
+            PhysicalUnit::Meter => Some((
+                self.pixels_per_unit_x as f64 * PhysicalDimensions::METERS_PER_INCH,
+                self.pixels_per_unit_y as f64 * PhysicalDimensions::METERS_PER_INCH,
+            )),
+            PhysicalUnit::Unknown => None,
+        }
+    }
+}
+
+impl AncillaryChunk for PhysicalDimensions {
+    const CHUNK_TYPE: &'static str = "pHYs";
+    const PLACEMENT: Placement = Placement::BeforeIdat;
+
+    fn from_data(data: &[u8]) -> Result<Self, AncillaryError> {
+        let bytes = fixed::<9>(Self::CHUNK_TYPE, data)?;
+        let unit = match bytes[8] {
+            0 => PhysicalUnit::Unknown,
+            1 => PhysicalUnit::Meter,
+            unit => return Err(AncillaryError::InvalidUnit(unit)),
+        };
+
+        Ok(PhysicalDimensions { pixels_per_unit_x: u32_at(bytes, 0), pixels_per_unit_y: u32_at(bytes, 4), unit })
+    }
+
+    fn to_data(&self) -> Vec<u8> {
+        let unit = match self.unit {
+            PhysicalUnit::Unknown => 0,
+            PhysicalUnit::Meter => 1,
+        };
+        [self.pixels_per_unit_x.to_be_bytes().as_ref(), &self.pixels_per_unit_y.to_be_bytes(), &[unit]].concat()
+    }
+}
+
+/**
+ * Provides methods for working with tIME chunks.
+ *
+ * @returns new - Builds a time and checks that it is a real date and time of day (a leap second is allowed).
+ * @returns now - Returns the current time in UTC.
+ * @returns from_unix - Converts seconds since the Unix epoch into a UTC date and time.
+ */
+impl Time {
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Result<Time, AncillaryError> {
+        let time = Time { year, month, day, hour, minute, second };
+
+        let valid = (1..=12).contains(&month)
+            && day >= 1
+            && day <= days_in_month(year, month)
+            && hour < 24
+            && minute < 60
+            && second <= 60;
+        if !valid {
+            return Err(AncillaryError::InvalidTime(time.to_string()));
+        }
+        Ok(time)
+    }
+
+    pub fn now() -> Time {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        Time::from_unix(seconds)
+    }
+
+    pub fn from_unix(seconds: u64) -> Time {
+        let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+        let time_of_day = seconds % 86_400;
+
+        Time {
+            year: year.clamp(0, u16::MAX as i64) as u16,
+            month,
+            day,
+            hour: (time_of_day / 3600) as u8,
+            minute: (time_of_day / 60 % 60) as u8,
+            second: (time_of_day % 60) as u8,
+        }
+    }
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    let leap = (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400);
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Howard Hinnant's days-to-civil conversion: days since 1970-01-01 to (year, month, day)
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+impl AncillaryChunk for Time {
+    const CHUNK_TYPE: &'static str = "tIME";
+    const PLACEMENT: Placement = Placement::Anywhere;
+
+    fn from_data(data: &[u8]) -> Result<Self, AncillaryError> {
+        let bytes = fixed::<7>(Self::CHUNK_TYPE, data)?;
+        Time::new(u16_at(bytes, 0), bytes[2], bytes[3], bytes[4], bytes[5], bytes[6])
+    }
+
+    fn to_data(&self) -> Vec<u8> {
+        [self.year.to_be_bytes().as_ref(), &[self.month, self.day, self.hour, self.minute, self.second]].concat()
+    }
+}
+
+/**
+ * Provides methods for working with bKGD chunks.
+ *
+ * @returns check - Returns an error if this form of background does not belong to the color type, or the index is past the palette.
+ */
+impl Background {
+    pub fn check(&self, ihdr: &Ihdr, palette_entries: Option<usize>) -> Result<(), AncillaryError> {
+        let matches = match self {
+            Background::Gray(_) => matches!(ihdr.color_type, ColorType::Grayscale | ColorType::GrayscaleAlpha),
+            Background::Rgb(..) => matches!(ihdr.color_type, ColorType::Rgb | ColorType::Rgba),
+            Background::PaletteIndex(_) => ihdr.color_type == ColorType::Indexed,
+        };
+        if !matches {
+            return Err(AncillaryError::ColorTypeMismatch { chunk_type: Self::CHUNK_TYPE, color_type: ihdr.color_type });
+        }
+
+        if let Background::PaletteIndex(index) = self
+            && palette_entries.is_none_or(|entries| usize::from(*index) >= entries)
+        {
+            return Err(AncillaryError::PaletteMismatch { chunk_type: Self::CHUNK_TYPE, entries: palette_entries });
+        }
+        Ok(())
+    }
+}
+
+impl AncillaryChunk for Background {
+    const CHUNK_TYPE: &'static str = "bKGD";
+    const PLACEMENT: Placement = Placement::BeforeIdat;
+
+    // The length tells the forms apart: 1 byte for a palette index, 2 for gray, 6 for RGB
+    fn from_data(data: &[u8]) -> Result<Self, AncillaryError> {
+        match data.len() {
+            1 => Ok(Background::PaletteIndex(data[0])),
+            2 => Ok(Background::Gray(u16_at(data, 0))),
+            6 => Ok(Background::Rgb(u16_at(data, 0), u16_at(data, 2), u16_at(data, 4))),
+            length => Err(AncillaryError::InvalidLength { chunk_type: Self::CHUNK_TYPE, length }),
+        }
+    }
+
+    fn to_data(&self) -> Vec<u8> {
+        match self {
+            Background::PaletteIndex(index) => vec![*index],
+            Background::Gray(gray) => gray.to_be_bytes().to_vec(),
+            Background::Rgb(r, g, b) => [r.to_be_bytes(), g.to_be_bytes(), b.to_be_bytes()].concat(),
+        }
+    }
+}
+
+/**
+ * Provides methods for working with sBIT chunks.
+ *
+ * @returns bits - Returns the significant bits of every channel in order.
+ * @returns check - Returns an error if the channels do not match the color type, or a count is 0 or above the bit depth.
+ */
+impl SignificantBits {
+    pub fn bits(&self) -> Vec<u8> {
+        match *self {
+            SignificantBits::Gray(gray) => vec![gray],
+            SignificantBits::GrayAlpha(gray, alpha) => vec![gray, alpha],
+            SignificantBits::Rgb(r, g, b) => vec![r, g, b],
+            SignificantBits::Rgba(r, g, b, a) => vec![r, g, b, a],
+        }
+    }
+
+    pub fn check(&self, ihdr: &Ihdr) -> Result<(), AncillaryError> {
+        let (matches, max) = match self {
+            SignificantBits::Gray(_) => (ihdr.color_type == ColorType::Grayscale, ihdr.bit_depth),
+            SignificantBits::GrayAlpha(..) => (ihdr.color_type == ColorType::GrayscaleAlpha, ihdr.bit_depth),
+            // Palette entries are always 8 bits per channel
+            SignificantBits::Rgb(..) if ihdr.color_type == ColorType::Indexed => (true, 8),
+            SignificantBits::Rgb(..) => (ihdr.color_type == ColorType::Rgb, ihdr.bit_depth),
+            SignificantBits::Rgba(..) => (ihdr.color_type == ColorType::Rgba, ihdr.bit_depth),
+        };
+        if !matches {
+            return Err(AncillaryError::ColorTypeMismatch { chunk_type: Self::CHUNK_TYPE, color_type: ihdr.color_type });
+        }
+
+        if let Some(bits) = self.bits().into_iter().find(|bits| *bits == 0 || *bits > max) {
+            return Err(AncillaryError::InvalidValue { chunk_type: Self::CHUNK_TYPE, value: bits.to_string() });
+        }
+        Ok(())
+    }
+}
+
+impl AncillaryChunk for SignificantBits {
+    const CHUNK_TYPE: &'static str = "sBIT";
+    const PLACEMENT: Placement = Placement::BeforePlte;
+
+    fn from_data(data: &[u8]) -> Result<Self, AncillaryError> {
+        match *data {
+            [gray] => Ok(SignificantBits::Gray(gray)),
+            [gray, alpha] => Ok(SignificantBits::GrayAlpha(gray, alpha)),
+            [r, g, b] => Ok(SignificantBits::Rgb(r, g, b)),
+            [r, g, b, a] => Ok(SignificantBits::Rgba(r, g, b, a)),
+            _ => Err(AncillaryError::InvalidLength { chunk_type: Self::CHUNK_TYPE, length: data.len() }),
+        }
+    }
+
+    fn to_data(&self) -> Vec<u8> {
+        self.bits()
+    }
+}
+
+/**
+ * Provides methods for working with hIST chunks.
+ *
+ * @returns check - Returns an error unless there is a palette with exactly one entry per frequency.
+ */
+impl Histogram {
+    pub fn check(&self, palette_entries: Option<usize>) -> Result<(), AncillaryError> {
+        if palette_entries != Some(self.frequencies.len()) {
+            return Err(AncillaryError::PaletteMismatch { chunk_type: Self::CHUNK_TYPE, entries: palette_entries });
+        }
+        Ok(())
+    }
+}
+
+impl AncillaryChunk for Histogram {
+    const CHUNK_TYPE: &'static str = "hIST";
+    const PLACEMENT: Placement = Placement::BeforeIdat;
+
+    // One entry per palette entry, of which there are at most 256
+    fn from_data(data: &[u8]) -> Result<Self, AncillaryError> {
+        if data.is_empty() || !data.len().is_multiple_of(2) || data.len() > 512 {
+            return Err(AncillaryError::InvalidLength { chunk_type: Self::CHUNK_TYPE, length: data.len() });
+        }
+        Ok(Histogram { frequencies: data.chunks_exact(2).map(|pair| u16_at(pair, 0)).collect() })
+    }
+
+    fn to_data(&self) -> Vec<u8> {
+        self.frequencies.iter().flat_map(|frequency| frequency.to_be_bytes()).collect()
+    }
+}
+
+/**
+ * Picks the typed form of a chunk by its type.
+ *
+ * @returns CHUNK_TYPES - Lists the chunk types that have a typed form.
+ * @returns from_chunk - Parses a chunk, or returns `None` if its type has no typed form.
+ * @returns parse - Parses a value given as text for a chunk type, as `set-chunk` takes it (see the `FromStr` impls).
+ * @returns chunk_type - Returns the chunk type the value is stored in.
+ * @returns placement - Returns where the chunk has to go.
+ * @returns check - Returns an error if the value does not fit the image (bKGD, sBIT and hIST depend on its color type or palette).
+ * @returns to_chunk - Returns the chunk holding the value.
+ */
+impl Ancillary {
+    pub const CHUNK_TYPES: [&'static str; 8] = ["gAMA", "cHRM", "sRGB", "pHYs", "tIME", "bKGD", "sBIT", "hIST"];
+
+    pub fn from_chunk(chunk: &Chunk) -> Option<Result<Ancillary, AncillaryError>> {
+        let data = chunk.data();
+        let parsed = match chunk.chunk_type().to_string().as_str() {
+            "gAMA" => Gamma::from_data(data).map(Ancillary::Gamma),
+            "cHRM" => Chromaticities::from_data(data).map(Ancillary::Chromaticities),
+            "sRGB" => Srgb::from_data(data).map(Ancillary::Srgb),
+            "pHYs" => PhysicalDimensions::from_data(data).map(Ancillary::PhysicalDimensions),
+            "tIME" => Time::from_data(data).map(Ancillary::Time),
+            "bKGD" => Background::from_data(data).map(Ancillary::Background),
+            "sBIT" => SignificantBits::from_data(data).map(Ancillary::SignificantBits),
+            "hIST" => Histogram::from_data(data).map(Ancillary::Histogram),
+            _ => return None,
+        };
+        Some(parsed)
+    }
+
+    pub fn parse(chunk_type: &str, value: &str) -> Result<Ancillary, AncillaryError> {
+        match chunk_type {
+            "gAMA" => value.parse().map(Ancillary::Gamma),
+            "cHRM" => value.parse().map(Ancillary::Chromaticities),
+            "sRGB" => value.parse().map(Ancillary::Srgb),
+            "pHYs" => value.parse().map(Ancillary::PhysicalDimensions),
+            "tIME" => value.parse().map(Ancillary::Time),
+            "bKGD" => value.parse().map(Ancillary::Background),
+            "sBIT" => value.parse().map(Ancillary::SignificantBits),
+            "hIST" => value.parse().map(Ancillary::Histogram),
+            _ => Err(AncillaryError::UnknownChunkType(chunk_type.to_string())),
+        }
+    }
+
+    pub fn chunk_type(&self) -> &'static str {
+        match self {
+            Ancillary::Gamma(_) => Gamma::CHUNK_TYPE,
+            Ancillary::Chromaticities(_) => Chromaticities::CHUNK_TYPE,
+            Ancillary::Srgb(_) => Srgb::CHUNK_TYPE,
+            Ancillary::PhysicalDimensions(_) => PhysicalDimensions::CHUNK_TYPE,
+            Ancillary::Time(_) => Time::CHUNK_TYPE,
+            Ancillary::Background(_) => Background::CHUNK_TYPE,
+            Ancillary::SignificantBits(_) => SignificantBits::CHUNK_TYPE,
+            Ancillary::Histogram(_) => Histogram::CHUNK_TYPE,
+        }
+    }
+
+    pub fn placement(&self) -> Placement {
+        match self {
+            Ancillary::Gamma(_) => Gamma::PLACEMENT,
+            Ancillary::Chromaticities(_) => Chromaticities::PLACEMENT,
+            Ancillary::Srgb(_) => Srgb::PLACEMENT,
+            Ancillary::PhysicalDimensions(_) => PhysicalDimensions::PLACEMENT,
+            Ancillary::Time(_) => Time::PLACEMENT,
+            Ancillary::Background(_) => Background::PLACEMENT,
+            Ancillary::SignificantBits(_) => SignificantBits::PLACEMENT,
+            Ancillary::Histogram(_) => Histogram::PLACEMENT,
+        }
+    }
+
+    pub fn check(&self, ihdr: &Ihdr, palette_entries: Option<usize>) -> Result<(), AncillaryError> {
+        match self {
+            Ancillary::Background(background) => background.check(ihdr, palette_entries),
+            Ancillary::SignificantBits(bits) => bits.check(ihdr),
+            Ancillary::Histogram(histogram) => histogram.check(palette_entries),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        match self {
+            Ancillary::Gamma(value) => value.to_chunk(),
+            Ancillary::Chromaticities(value) => value.to_chunk(),
+            Ancillary::Srgb(value) => value.to_chunk(),
+            Ancillary::PhysicalDimensions(value) => value.to_chunk(),
+            Ancillary::Time(value) => value.to_chunk(),
+            Ancillary::Background(value) => value.to_chunk(),
+            Ancillary::SignificantBits(value) => value.to_chunk(),
+            Ancillary::Histogram(value) => value.to_chunk(),
+        }
+    }
+}
+
+// Takes in a rendering intent byte and see if the spec defines it
+impl TryFrom<u8> for RenderingIntent {
+    type Error = AncillaryError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(RenderingIntent::Perceptual),
+            1 => Ok(RenderingIntent::RelativeColorimetric),
+            2 => Ok(RenderingIntent::Saturation),
+            3 => Ok(RenderingIntent::AbsoluteColorimetric),
+            _ => Err(AncillaryError::InvalidRenderingIntent(value)),
+        }
+    }
+}
+
+// A float, e.g. 0.45455
+impl FromStr for Gamma {
+    type Err = AncillaryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Gamma { gamma: to_fixed_point(Self::CHUNK_TYPE, s)? })
+    }
+}
+
+// Eight floats: white x,y then red, green and blue x,y
+impl FromStr for Chromaticities {
+    type Err = AncillaryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values: Vec<u32> = s.split(',').map(|part| to_fixed_point(Self::CHUNK_TYPE, part)).collect::<Result<_, _>>()?;
+        let [white_x, white_y, red_x, red_y, green_x, green_y, blue_x, blue_y] = values[..] else {
+            return Err(AncillaryError::InvalidValue { chunk_type: Self::CHUNK_TYPE, value: s.to_string() });
+        };
+        Ok(Chromaticities { white_x, white_y, red_x, red_y, green_x, green_y, blue_x, blue_y })
+    }
+}
+
+// perceptual, relative, saturation or absolute, or the intent code
+impl FromStr for Srgb {
+    type Err = AncillaryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rendering_intent = match s {
+            "perceptual" => RenderingIntent::Perceptual,
+            "relative" | "relative-colorimetric" => RenderingIntent::RelativeColorimetric,
+            "saturation" => RenderingIntent::Saturation,
+            "absolute" | "absolute-colorimetric" => RenderingIntent::AbsoluteColorimetric,
+            _ => {
+                let code = s.parse::<u8>().map_err(|_| AncillaryError::InvalidValue { chunk_type: Self::CHUNK_TYPE, value: s.to_string() })?;
+                RenderingIntent::try_from(code)?
+            }
+        };
+        Ok(Srgb { rendering_intent })
+    }
+}
+
+// A resolution in DPI, e.g. 300 or 300x600 for different horizontal and vertical resolutions
+impl FromStr for PhysicalDimensions {
+    type Err = AncillaryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || AncillaryError::InvalidValue { chunk_type: Self::CHUNK_TYPE, value: s.to_string() };
+        let dpi = s.strip_suffix("dpi").unwrap_or(s);
+        let (x, y) = dpi.split_once('x').unwrap_or((dpi, dpi));
+
+        PhysicalDimensions::from_dpi(x.trim().parse().map_err(|_| invalid())?, y.trim().parse().map_err(|_| invalid())?)
+    }
+}
+
+// An ISO 8601 UTC time: 2024-05-01T12:30:00 with an optional Z, or now
+impl FromStr for Time {
+    type Err = AncillaryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "now" {
+            return Ok(Time::now());
+        }
+
+        let invalid = || AncillaryError::InvalidTime(s.to_string());
+        let (date, time) = s.strip_suffix('Z').unwrap_or(s).split_once('T').ok_or_else(invalid)?;
+        let date: Vec<&str> = date.split('-').collect();
+        let time: Vec<&str> = time.split(':').collect();
+        let ([year, month, day], [hour, minute, second]) = (&date[..], &time[..]) else {
+            return Err(invalid());
+        };
+
+        let byte = |part: &str| part.parse::<u8>().map_err(|_| invalid());
+        Time::new(year.parse().map_err(|_| invalid())?, byte(month)?, byte(day)?, byte(hour)?, byte(minute)?, byte(second)?)
+    }
+}
+
+// gray:N, rgb:R,G,B or index:N
+impl FromStr for Background {
+    type Err = AncillaryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || AncillaryError::InvalidValue { chunk_type: Self::CHUNK_TYPE, value: s.to_string() };
+        let (kind, value) = s.split_once(':').ok_or_else(invalid)?;
+
+        match (kind, &parse_list::<u16>(Self::CHUNK_TYPE, value)?[..]) {
+            ("gray", &[gray]) => Ok(Background::Gray(gray)),
+            ("rgb", &[r, g, b]) => Ok(Background::Rgb(r, g, b)),
+            ("index", &[index]) => u8::try_from(index).map(Background::PaletteIndex).map_err(|_| invalid()),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// One to four bit counts, in the order of the image's channels, e.g. 5,6,5
+impl FromStr for SignificantBits {
+    type Err = AncillaryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SignificantBits::from_data(&parse_list::<u8>(Self::CHUNK_TYPE, s)?)
+            .map_err(|_| AncillaryError::InvalidValue { chunk_type: Self::CHUNK_TYPE, value: s.to_string() })
+    }
+}
+
+// One frequency per palette entry, e.g. 10,0,3
+impl FromStr for Histogram {
+    type Err = AncillaryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let frequencies = parse_list::<u16>(Self::CHUNK_TYPE, s)?;
+        if frequencies.len() > 256 {
+            return Err(AncillaryError::InvalidValue { chunk_type: Self::CHUNK_TYPE, value: s.to_string() });
+        }
+        Ok(Histogram { frequencies })
+    }
+}
+
+impl Display for Gamma {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "gamma {}", self.value())
+    }
+}
+
+impl Display for Chromaticities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let point = |x: u32, y: u32| format!("({}, {})", from_fixed_point(x), from_fixed_point(y));
+        write!(
+            f,
+            "white {}, red {}, green {}, blue {}",
+            point(self.white_x, self.white_y),
+            point(self.red_x, self.red_y),
+            point(self.green_x, self.green_y),
+            point(self.blue_x, self.blue_y)
+        )
+    }
+}
+
+impl Display for RenderingIntent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RenderingIntent::Perceptual => "perceptual",
+            RenderingIntent::RelativeColorimetric => "relative colorimetric",
+            RenderingIntent::Saturation => "saturation",
+            RenderingIntent::AbsoluteColorimetric => "absolute colorimetric",
+        };
+        write!(f, "{} ({})", name, self.value())
+    }
+}
+
+impl Display for Srgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rendering intent {}", self.rendering_intent)
+    }
+}
+
+impl Display for PhysicalDimensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (x, y) = (self.pixels_per_unit_x, self.pixels_per_unit_y);
+        match self.dpi() {
+            Some((dpi, _)) if x == y => write!(f, "{:.0} DPI ({} pixels per meter)", dpi, x),
+            Some((dpi_x, dpi_y)) => write!(f, "{:.0} x {:.0} DPI ({} x {} pixels per meter)", dpi_x, dpi_y, x, y),
+            None => write!(f, "aspect ratio {}:{} (no physical unit)", x, y),
+        }
+    }
+}
+
+// ISO 8601, always UTC
+impl Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+impl Display for Background {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Background::Gray(gray) => write!(f, "gray {}", gray),
+            Background::Rgb(r, g, b) => write!(f, "rgb({}, {}, {})", r, g, b),
+            Background::PaletteIndex(index) => write!(f, "palette index {}", index),
+        }
+    }
+}
+
+impl Display for SignificantBits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: &[&str] = match self {
+            SignificantBits::Gray(_) => &["gray"],
+            SignificantBits::GrayAlpha(..) => &["gray", "alpha"],
+            SignificantBits::Rgb(..) => &["red", "green", "blue"],
+            SignificantBits::Rgba(..) => &["red", "green", "blue", "alpha"],
+        };
+        let channels: Vec<String> = names.iter().zip(self.bits()).map(|(name, bits)| format!("{} {}", name, bits)).collect();
+        write!(f, "significant bits: {}", channels.join(", "))
+    }
+}
+
+impl Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let used = self.frequencies.iter().filter(|frequency| **frequency > 0).count();
+        write!(f, "{} palette entries, {} in use", self.frequencies.len(), used)
+    }
+}
+
+impl Display for Ancillary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ancillary::Gamma(value) => write!(f, "{}", value),
+            Ancillary::Chromaticities(value) => write!(f, "{}", value),
+            Ancillary::Srgb(value) => write!(f, "{}", value),
+            Ancillary::PhysicalDimensions(value) => write!(f, "{}", value),
+            Ancillary::Time(value) => write!(f, "{}", value),
+            Ancillary::Background(value) => write!(f, "{}", value),
+            Ancillary::SignificantBits(value) => write!(f, "{}", value),
+            Ancillary::Histogram(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl fmt::Display for AncillaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AncillaryError::InvalidLength { chunk_type, length } => write!(f, "{} chunk has invalid length {}", chunk_type, length),
+            AncillaryError::InvalidRenderingIntent(intent) => write!(f, "Invalid rendering intent {}", intent),
+            AncillaryError::InvalidUnit(unit) => write!(f, "Invalid pHYs unit {}", unit),
+            AncillaryError::InvalidTime(time) => write!(f, "Invalid time '{}', expected a UTC time like 2024-05-01T12:30:00Z", time),
+            AncillaryError::InvalidValue { chunk_type, value } => write!(f, "Invalid {} value '{}'", chunk_type, value),
+            AncillaryError::ColorTypeMismatch { chunk_type, color_type } => {
+                write!(f, "This {} chunk does not fit {} images", chunk_type, color_type)
+            }
+            AncillaryError::PaletteMismatch { chunk_type, entries: Some(entries) } => {
+                write!(f, "This {} chunk does not fit a palette of {} entries", chunk_type, entries)
+            }
+            AncillaryError::PaletteMismatch { chunk_type, entries: None } => write!(f, "A {} chunk needs a palette", chunk_type),
+            AncillaryError::UnknownChunkType(chunk_type) => write!(f, "{} has no typed form", chunk_type),
+        }
+    }
+}
+
+impl std::error::Error for AncillaryError {}
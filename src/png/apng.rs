@@ -0,0 +1,399 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use crate::png::chunk::Chunk;
+use crate::png::chunk_type::ChunkType;
+use crate::png::ihdr::Ihdr;
+
+// acTL: how many frames the animation has and how many times it plays (0 means forever)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationControl {
+    pub num_frames: u32,
+    pub num_plays: u32,
+}
+
+// fcTL: where a frame goes on the canvas, how long it shows and how it is composited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameControl {
+    pub sequence_number: u32,
+    pub width: u32,
+    pub height: u32,
+    pub x_offset: u32,
+    pub y_offset: u32,
+    pub delay_num: u16,
+    pub delay_den: u16,
+    pub dispose_op: DisposeOp,
+    pub blend_op: BlendOp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisposeOp {
+    None,
+    Background,
+    Previous,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendOp {
+    Source,
+    Over,
+}
+
+// One frame of an animation. `data` holds the zlib stream pieces in file order, taken from IDAT
+// chunks for the default image and from fdAT chunks (without their sequence number) otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub control: FrameControl,
+    pub data: Vec<Vec<u8>>,
+    pub is_default_image: bool,
+}
+
+// Per-frame settings used when assembling an APNG; the frame size comes from the frame's own IHDR
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameOptions {
+    pub x_offset: u32,
+    pub y_offset: u32,
+    pub delay_num: u16,
+    pub delay_den: u16,
+    pub dispose_op: DisposeOp,
+    pub blend_op: BlendOp,
+}
+
+#[derive(Debug)]
+pub enum ApngError {
+    NotAnimated,
+    NoFrames,
+    InvalidLength { chunk_type: &'static str, length: usize },
+    InvalidDisposeOp(u8),
+    InvalidBlendOp(u8),
+    InvalidSequence { expected: u32, found: u32 },
+    FrameCountMismatch { expected: u32, actual: usize },
+    FrameOutOfBounds(u32),
+    InvalidDefaultFrame,
+    MissingFrameData(u32),
+    DataBeforeFrameControl,
+    FrameMismatch(usize),
+}
+
+/**
+ * Parses and builds the animation chunks of an APNG: acTL, fcTL and fdAT.
+ *
+ * @returns from_chunk - Parses an acTL chunk.
+ * @returns to_chunk - Serializes the animation control into an acTL chunk.
+ */
+impl AnimationControl {
+    pub const LENGTH: usize = 8;
+
+    pub fn from_chunk(chunk: &Chunk) -> Result<AnimationControl, ApngError> {
+        let bytes: &[u8; AnimationControl::LENGTH] = chunk
+            .data()
+            .try_into()
+            .map_err(|_| ApngError::InvalidLength { chunk_type: "acTL", length: chunk.data().len() })?;
+
+        Ok(AnimationControl {
+            num_frames: u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            num_plays: u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+        })
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let data = [self.num_frames.to_be_bytes(), self.num_plays.to_be_bytes()].concat();
+        Chunk::new(ChunkType::from_str("acTL").expect("acTL is a valid chunk type"), data)
+    }
+}
+
+/**
+ * Provides methods for working with fcTL frame control chunks.
+ *
+ * @returns from_chunk - Parses an fcTL chunk, checking the dispose and blend operations.
+ * @returns to_chunk - Serializes the frame control into an fcTL chunk.
+ * @returns delay_seconds - Returns the frame delay in seconds; a denominator of 0 means hundredths.
+ */
+impl FrameControl {
+    pub const LENGTH: usize = 26;
+
+    pub fn from_chunk(chunk: &Chunk) -> Result<FrameControl, ApngError> {
+        let bytes: &[u8; FrameControl::LENGTH] = chunk
+            .data()
+            .try_into()
+            .map_err(|_| ApngError::InvalidLength { chunk_type: "fcTL", length: chunk.data().len() })?;
+        let u32_at = |i: usize| u32::from_be_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+
+        Ok(FrameControl {
+            sequence_number: u32_at(0),
+            width: u32_at(4),
+            height: u32_at(8),
+            x_offset: u32_at(12),
+            y_offset: u32_at(16),
+            delay_num: u16::from_be_bytes([bytes[20], bytes[21]]),
+            delay_den: u16::from_be_bytes([bytes[22], bytes[23]]),
+            dispose_op: DisposeOp::try_from(bytes[24])?,
+            blend_op: BlendOp::try_from(bytes[25])?,
+        })
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let data = [
+            self.sequence_number.to_be_bytes().as_ref(),
+            &self.width.to_be_bytes(),
+            &self.height.to_be_bytes(),
+            &self.x_offset.to_be_bytes(),
+            &self.y_offset.to_be_bytes(),
+            &self.delay_num.to_be_bytes(),
+            &self.delay_den.to_be_bytes(),
+            &[self.dispose_op.value(), self.blend_op.value()],
+        ]
+        .concat();
+        Chunk::new(ChunkType::from_str("fcTL").expect("fcTL is a valid chunk type"), data)
+    }
+
+    pub fn delay_seconds(&self) -> f64 {
+        let den = if self.delay_den == 0 { 100 } else { self.delay_den };
+        self.delay_num as f64 / den as f64
+    }
+}
+
+// Builds an fdAT chunk carrying one piece of a frame's image data
+pub fn frame_data_chunk(sequence_number: u32, data: &[u8]) -> Chunk {
+    let data = [&sequence_number.to_be_bytes(), data].concat();
+    Chunk::new(ChunkType::from_str("fdAT").expect("fdAT is a valid chunk type"), data)
+}
+
+/**
+ * Collects the frames of an APNG, validating it along the way.
+ *
+ * Sequence numbers across fcTL and fdAT chunks must run 0, 1, 2, ... in file order, the number of
+ * fcTL chunks must match acTL, and every frame must fit on the canvas and have image data. An fcTL
+ * before the first IDAT makes the default image the first frame, in which case it must cover the
+ * whole canvas.
+ *
+ * @returns frames - Returns every frame in order.
+ */
+pub fn frames(chunks: &[Chunk], ihdr: &Ihdr) -> Result<Vec<Frame>, ApngError> {
+    let actl = chunks
+        .iter()
+        .find(|c| c.chunk_type().to_string() == "acTL")
+        .ok_or(ApngError::NotAnimated)?;
+    let control = AnimationControl::from_chunk(actl)?;
+
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut expected_sequence = 0u32;
+    let mut seen_idat = false;
+    let mut check_sequence = |found: u32| {
+        if found != expected_sequence {
+            return Err(ApngError::InvalidSequence { expected: expected_sequence, found });
+        }
+        expected_sequence = expected_sequence.wrapping_add(1);
+        Ok(())
+    };
+
+    for chunk in chunks {
+        match chunk.chunk_type().to_string().as_str() {
+            "fcTL" => {
+                let control = FrameControl::from_chunk(chunk)?;
+                check_sequence(control.sequence_number)?;
+                frames.push(Frame { control, data: Vec::new(), is_default_image: !seen_idat });
+            }
+            "IDAT" => {
+                seen_idat = true;
+                if let Some(frame) = frames.last_mut().filter(|f| f.is_default_image) {
+                    frame.data.push(chunk.data().to_vec());
+                }
+            }
+            "fdAT" => {
+                let (sequence, data) = chunk
+                    .data()
+                    .split_first_chunk::<4>()
+                    .ok_or(ApngError::InvalidLength { chunk_type: "fdAT", length: chunk.data().len() })?;
+                check_sequence(u32::from_be_bytes(*sequence))?;
+
+                match frames.last_mut() {
+                    Some(frame) if !frame.is_default_image => frame.data.push(data.to_vec()),
+                    _ => return Err(ApngError::DataBeforeFrameControl),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if frames.len() != control.num_frames as usize {
+        return Err(ApngError::FrameCountMismatch { expected: control.num_frames, actual: frames.len() });
+    }
+
+    for frame in &frames {
+        let c = &frame.control;
+        let fits = |offset: u32, size: u32, canvas: u32| offset.checked_add(size).is_some_and(|end| end <= canvas);
+        if c.width == 0 || c.height == 0 || !fits(c.x_offset, c.width, ihdr.width) || !fits(c.y_offset, c.height, ihdr.height) {
+            return Err(ApngError::FrameOutOfBounds(c.sequence_number));
+        }
+        if frame.is_default_image && (c.x_offset, c.y_offset, c.width, c.height) != (0, 0, ihdr.width, ihdr.height) {
+            return Err(ApngError::InvalidDefaultFrame);
+        }
+        if frame.data.iter().all(|d| d.is_empty()) {
+            return Err(ApngError::MissingFrameData(c.sequence_number));
+        }
+    }
+
+    Ok(frames)
+}
+
+impl Default for FrameOptions {
+    // Shown for a tenth of a second at the top left corner, replacing what was there
+    fn default() -> Self {
+        FrameOptions {
+            x_offset: 0,
+            y_offset: 0,
+            delay_num: 1,
+            delay_den: 10,
+            dispose_op: DisposeOp::None,
+            blend_op: BlendOp::Source,
+        }
+    }
+}
+
+impl DisposeOp {
+    pub fn value(&self) -> u8 {
+        match self {
+            DisposeOp::None => 0,
+            DisposeOp::Background => 1,
+            DisposeOp::Previous => 2,
+        }
+    }
+}
+
+impl BlendOp {
+    pub fn value(&self) -> u8 {
+        match self {
+            BlendOp::Source => 0,
+            BlendOp::Over => 1,
+        }
+    }
+}
+
+// Takes in a dispose_op byte and see if the spec defines it
+impl TryFrom<u8> for DisposeOp {
+    type Error = ApngError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(DisposeOp::None),
+            1 => Ok(DisposeOp::Background),
+            2 => Ok(DisposeOp::Previous),
+            _ => Err(ApngError::InvalidDisposeOp(value)),
+        }
+    }
+}
+
+// Takes in a blend_op byte and see if the spec defines it
+impl TryFrom<u8> for BlendOp {
+    type Error = ApngError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(BlendOp::Source),
+            1 => Ok(BlendOp::Over),
+            _ => Err(ApngError::InvalidBlendOp(value)),
+        }
+    }
+}
+
+// Takes in a --dispose value and see if it names a dispose operation
+impl FromStr for DisposeOp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(DisposeOp::None),
+            "background" => Ok(DisposeOp::Background),
+            "previous" => Ok(DisposeOp::Previous),
+            _ => Err(format!("invalid dispose op '{}', expected none, background or previous", s)),
+        }
+    }
+}
+
+// Takes in a --blend value and see if it names a blend operation
+impl FromStr for BlendOp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "source" => Ok(BlendOp::Source),
+            "over" => Ok(BlendOp::Over),
+            _ => Err(format!("invalid blend op '{}', expected source or over", s)),
+        }
+    }
+}
+
+impl Display for DisposeOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisposeOp::None => write!(f, "none"),
+            DisposeOp::Background => write!(f, "background"),
+            DisposeOp::Previous => write!(f, "previous"),
+        }
+    }
+}
+
+impl Display for BlendOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlendOp::Source => write!(f, "source"),
+            BlendOp::Over => write!(f, "over"),
+        }
+    }
+}
+
+impl Display for AnimationControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "frames: {}, plays: ", self.num_frames)?;
+        match self.num_plays {
+            0 => write!(f, "forever"),
+            plays => write!(f, "{}", plays),
+        }
+    }
+}
+
+impl Display for FrameControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sequence: {}, size: {}x{}, offset: ({}, {}), delay: {}/{} ({:.3}s), dispose: {}, blend: {}",
+            self.sequence_number,
+            self.width,
+            self.height,
+            self.x_offset,
+            self.y_offset,
+            self.delay_num,
+            self.delay_den,
+            self.delay_seconds(),
+            self.dispose_op,
+            self.blend_op,
+        )
+    }
+}
+
+impl fmt::Display for ApngError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApngError::NotAnimated => write!(f, "Image has no acTL chunk, it is not animated"),
+            ApngError::NoFrames => write!(f, "An animation needs at least one frame"),
+            ApngError::InvalidLength { chunk_type, length } => write!(f, "{} chunk has invalid length {}", chunk_type, length),
+            ApngError::InvalidDisposeOp(op) => write!(f, "Invalid dispose op {}", op),
+            ApngError::InvalidBlendOp(op) => write!(f, "Invalid blend op {}", op),
+            ApngError::InvalidSequence { expected, found } => {
+                write!(f, "Expected sequence number {}, found {}", expected, found)
+            }
+            ApngError::FrameCountMismatch { expected, actual } => {
+                write!(f, "acTL announces {} frames but there are {}", expected, actual)
+            }
+            ApngError::FrameOutOfBounds(sequence) => write!(f, "Frame with sequence number {} does not fit the canvas", sequence),
+            ApngError::InvalidDefaultFrame => write!(f, "A frame made of the default image must cover the whole canvas"),
+            ApngError::MissingFrameData(sequence) => write!(f, "Frame with sequence number {} has no image data", sequence),
+            ApngError::DataBeforeFrameControl => write!(f, "fdAT chunk without a preceding fcTL chunk"),
+            ApngError::FrameMismatch(index) => {
+                write!(f, "Frame {} does not share the first frame's bit depth, color type, interlacing and palette", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ApngError {}
@@ -0,0 +1,140 @@
+use std::ffi::OsString;
+use std::fs::{self, File, FileTimes, OpenOptions};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::png::image::PngError;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    // Keep the file being replaced at its path plus this suffix
+    pub backup: Option<String>,
+    pub preserve_mtime: bool,
+}
+
+// Keeps the temporary names of concurrent writes in one process apart
+static NEXT_TEMP: AtomicUsize = AtomicUsize::new(0);
+
+/**
+ * Replaces a file without ever leaving a partly written one in its place.
+ *
+ * The contents go to a temporary file in the same directory, which is flushed to disk and then
+ * renamed over the destination, so a crash or a full disk leaves either the old file or the new one.
+ * The temporary file is removed again if anything fails. When the destination already exists, the
+ * new file gets its permissions, and with `preserve_mtime` its access and modification times. A
+ * symlink is followed, and the file it points to is replaced.
+ *
+ * @returns write_atomic - Writes the file through `write`, backing up the file it replaces if asked to.
+ * @returns backup_path - Returns the path a backup of `path` is written to.
+ */
+pub fn write_atomic<F>(path: &Path, options: &WriteOptions, write: F) -> Result<(), PngError>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), PngError>,
+{
+    // Renaming over a symlink would replace the link itself, so write to the file it points to
+    let resolved;
+    let path = if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+        resolved = fs::canonicalize(path).map_err(|e| PngError::io(path, e))?;
+        resolved.as_path()
+    } else {
+        path
+    };
+
+    let original = match fs::metadata(path) {
+        Ok(metadata) => Some(metadata),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(PngError::io(path, e)),
+    };
+
+    let (temp_path, file) = create_temp(path)?;
+    let result = write_temp(file, original.as_ref(), options, write).map_err(|e| e.with_path(&temp_path));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    if original.is_some()
+        && let Some(suffix) = &options.backup
+    {
+        let backup = backup_path(path, suffix);
+        if let Err(e) = back_up(path, &backup) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(PngError::io(&backup, e));
+        }
+    }
+
+    if let Err(e) = fs::rename(&temp_path, path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(PngError::io(path, e));
+    }
+    sync_dir(path);
+    Ok(())
+}
+
+pub fn backup_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut backup = path.as_os_str().to_os_string();
+    backup.push(suffix);
+    PathBuf::from(backup)
+}
+
+// A hard link keeps the original file itself, times and all; copying is the fallback where links are not supported
+fn back_up(path: &Path, backup: &Path) -> io::Result<()> {
+    match fs::remove_file(backup) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    if fs::hard_link(path, backup).is_err() {
+        fs::copy(path, backup)?;
+    }
+    Ok(())
+}
+
+fn write_temp<F>(file: File, original: Option<&fs::Metadata>, options: &WriteOptions, write: F) -> Result<(), PngError>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), PngError>,
+{
+    let mut writer = BufWriter::new(file);
+    write(&mut writer)?;
+    let file = writer.into_inner().map_err(|e| e.into_error())?;
+
+    if let Some(original) = original {
+        file.set_permissions(original.permissions())?;
+        if options.preserve_mtime {
+            file.set_times(FileTimes::new().set_accessed(original.accessed()?).set_modified(original.modified()?))?;
+        }
+    }
+    file.sync_all()?;
+    Ok(())
+}
+
+// Hidden, and named after the destination and this process so a leftover one is easy to trace
+fn create_temp(path: &Path) -> Result<(PathBuf, File), PngError> {
+    let file_name = path.file_name().ok_or_else(|| PngError::io(path, io::ErrorKind::InvalidInput.into()))?;
+
+    loop {
+        let mut name = OsString::from(".");
+        name.push(file_name);
+        name.push(format!(".{}.{}.tmp", process::id(), NEXT_TEMP.fetch_add(1, Ordering::Relaxed)));
+        let temp_path = path.with_file_name(name);
+
+        match OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(PngError::io(&temp_path, e)),
+        }
+    }
+}
+
+// Makes the rename itself durable; best effort, as not every platform can open a directory
+fn sync_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
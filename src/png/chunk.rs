@@ -1,8 +1,9 @@
 use std::{fmt::{self, Display}, str::{from_utf8, Utf8Error}};
 use std::str::FromStr;
-use crate::png::chunk_type::ChunkType;
+use crate::png::chunk_type::{ChunkType, ChunkTypeError};
 use crc::{Crc, CRC_32_ISO_HDLC};
 
+#[derive(Clone)]
 pub struct Chunk {
     chunk_length: u32,
     chunk_type: ChunkType,
@@ -12,11 +13,19 @@ pub struct Chunk {
 
 #[derive(Debug)]
 pub enum ChunkError {
-    ConversionError,
+    InvalidChunkType([u8; 4]),
+    ChunkType(ChunkTypeError),
+    Truncated { expected: usize, actual: usize },
+    CrcMismatch { expected: u32, actual: u32 },
 }
 
-pub fn u8_4_from_slice(bytes: &[u8]) -> [u8; 4] {
-    bytes.try_into().expect("error converting")
+// CRC-32 over the chunk type and data, as defined by the PNG spec
+fn compute_crc(chunk_type: &ChunkType, chunk_data: &[u8]) -> u32 {
+    let crc_algorithm = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+    let mut digest = crc_algorithm.digest();
+    digest.update(&chunk_type.bytes());
+    digest.update(chunk_data);
+    digest.finalize()
 }
 
 /**
@@ -30,22 +39,16 @@ pub fn u8_4_from_slice(bytes: &[u8]) -> [u8; 4] {
  * @returns is_reserved_bit_valid - Returns `true` if the reserved bit (5th bit of the third byte) is `0` (must always be `0` for validity).
  * @returns is_safe_to_copy - Returns `true` if the chunk is safe to copy, meaning the 5th bit of the fourth byte is `1`.
  */
-
 #[allow(dead_code)]
 impl Chunk {
     pub fn new(chunk_type: ChunkType, chunk_data: Vec<u8>) -> Chunk {
-
-        let crc_algorithm = Crc::<u32>::new(&CRC_32_ISO_HDLC);
-        let mut digest = crc_algorithm.digest();
-        digest.update(&chunk_type.bytes());
-        digest.update(&chunk_data);
-        let crc = digest.finalize();
+        let crc = compute_crc(&chunk_type, &chunk_data);
 
         Chunk { 
             chunk_length: chunk_data.len() as u32, 
-            chunk_type: chunk_type, 
-            chunk_data: chunk_data, 
-            crc: crc, 
+            chunk_type, 
+            chunk_data, 
+            crc, 
         }
     }
 
@@ -84,8 +87,39 @@ impl Chunk {
         self.crc
     }
 
+    pub fn computed_crc(&self) -> u32 {
+        compute_crc(&self.chunk_type, &self.chunk_data)
+    }
+
+    pub fn is_crc_valid(&self) -> bool {
+        self.crc == self.computed_crc()
+    }
+
+    // Parses a chunk without checking its stored CRC against its contents.
+    // The length field is untrusted, so every slice is bounds-checked instead of indexed.
+    pub fn from_bytes_unchecked(bytes: &[u8]) -> Result<Chunk, ChunkError> {
+        let truncated = |expected: usize| ChunkError::Truncated { expected, actual: bytes.len() };
+
+        let (length_bytes, rest) = bytes.split_first_chunk::<4>().ok_or(truncated(12))?;
+        let (type_bytes, rest) = rest.split_first_chunk::<4>().ok_or(truncated(12))?;
+        let chunk_length = u32::from_be_bytes(*length_bytes);
+        let chunk_type = ChunkType::try_from(*type_bytes)
+            .map_err(|_| ChunkError::InvalidChunkType(*type_bytes))?;
+
+        let expected = (chunk_length as usize).saturating_add(12);
+        let (chunk_data, rest) = rest.split_at_checked(chunk_length as usize).ok_or(truncated(expected))?;
+        let (crc_bytes, _) = rest.split_first_chunk::<4>().ok_or(truncated(expected))?;
+
+        Ok(Chunk { 
+            chunk_length,
+            chunk_type,
+            chunk_data: chunk_data.to_vec(),
+            crc: u32::from_be_bytes(*crc_bytes),
+        })
+    }
+
     pub fn from_strings(chunk_type: &str, data: &str) -> Result<Chunk, ChunkError> {
-        let chunk_type = ChunkType::from_str(chunk_type).map_err(|_| ChunkError::ConversionError)?;
+        let chunk_type = ChunkType::from_str(chunk_type).map_err(ChunkError::ChunkType)?;
         let data: Vec<u8> = data.bytes().collect();
 
         Ok(Chunk::new(chunk_type, data))
@@ -97,27 +131,16 @@ impl TryFrom<&[u8]> for Chunk {
     type Error = ChunkError;
 
     fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
-        if bytes.len() < 12 {
-            return Err(ChunkError::ConversionError)
-        }
+        let chunk = Chunk::from_bytes_unchecked(bytes)?;
 
-        let chunk_length = u32::from_be_bytes(u8_4_from_slice(&bytes[0..4])) as usize;
-        let chunk_type = ChunkType::try_from(u8_4_from_slice(&bytes[4..8]))
-            .map_err(|_| ChunkError::ConversionError)?;
-        let chunk_data = bytes[8..8 + chunk_length].to_vec();
-        
-        let crc_start = 8 + chunk_length;
-        let crc_end = crc_start + 4;
-
-        let crc = u32::from_be_bytes(u8_4_from_slice(&bytes[crc_start..crc_end]));
-
-        Ok(Chunk { 
-            chunk_length: chunk_length as u32,
-            chunk_type: chunk_type, 
-            chunk_data: chunk_data,
-            crc: crc,
-        })
+        if !chunk.is_crc_valid() {
+            return Err(ChunkError::CrcMismatch {
+                expected: chunk.crc,
+                actual: chunk.computed_crc(),
+            })
+        }
 
+        Ok(chunk)
     }
 }
 
@@ -134,8 +157,24 @@ impl Display for Chunk {
 
 impl fmt::Display for ChunkError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            ChunkError::InvalidChunkType(bytes) => write!(f, "Invalid chunk type bytes {:02x?}", bytes),
+            ChunkError::ChunkType(_) => write!(f, "Invalid chunk type"),
+            ChunkError::Truncated { expected, actual } => {
+                write!(f, "Chunk is truncated: expected {} bytes, found {}", expected, actual)
+            }
+            ChunkError::CrcMismatch { expected, actual } => {
+                write!(f, "CRC mismatch: expected {:#010x}, actual {:#010x}", expected, actual)
+            }
+        }
     }
 }
 
-impl std::error::Error for ChunkError {}
\ No newline at end of file
+impl std::error::Error for ChunkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ChunkError::ChunkType(e) => Some(e),
+            _ => None,
+        }
+    }
+}
\ No newline at end of file
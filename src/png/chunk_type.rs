@@ -1,14 +1,14 @@
-use std::{fmt::Display, str::FromStr};
+use std::{fmt::{self, Display}, str::FromStr};
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct ChunkType(
     [u8; 4]
 );
 
 #[derive(Debug)]
 pub enum ChunkTypeError {
-    InvalidConversion,
-    InvalidString,
+    InvalidConversion([u8; 4]),
+    InvalidString(String),
 }
 
 /**
@@ -22,9 +22,11 @@ pub enum ChunkTypeError {
  * @returns is_reserved_bit_valid - Returns `true` if the reserved bit (5th bit of the third byte) is `0` (must always be `0` for validity).
  * @returns is_safe_to_copy - Returns `true` if the chunk is safe to copy, meaning the 5th bit of the fourth byte is `1`.
  */
-
 #[allow(dead_code)]
 impl ChunkType {
+    // Bit 5 of each byte carries the chunk's property flag (lowercase letter = bit set)
+    const PROPERTY_BIT: u8 = 1 << 5;
+
     pub fn bytes(&self) -> [u8; 4] {
         self.0
     }
@@ -34,31 +36,19 @@ impl ChunkType {
      }
 
     pub fn is_critical(&self) -> bool { 
-        let byte_1 = self.0[0];
-        let bit_5 = (byte_1 >> 5) + 1;
-
-        bit_5 == 0
+        self.0[0] & ChunkType::PROPERTY_BIT == 0
     }
 
     pub fn is_private(&self) -> bool {
-        let byte_2 = self.0[1];
-        let bit_5 = (byte_2 >> 5) + 1;
-
-        bit_5 == 1
+        self.0[1] & ChunkType::PROPERTY_BIT != 0
     }
 
     pub fn is_reserved_bit_valid(&self) -> bool {
-        let byte_3 = self.0[2];
-        let bit_5 = (byte_3 >> 5) + 1;
-
-        bit_5 == 0
+        self.0[2] & ChunkType::PROPERTY_BIT == 0
     }
 
     pub fn is_safe_to_copy(&self) -> bool {
-        let byte_4 = self.0[3];
-        let bit_5 = (byte_4 >> 5) + 1;
-
-        bit_5 == 1
+        self.0[3] & ChunkType::PROPERTY_BIT != 0
     }
 }
 
@@ -70,7 +60,7 @@ impl TryFrom<[u8; 4]> for ChunkType {
         if bytes.iter().all(|byte| (*byte as char).is_ascii_alphabetic()) {
             Ok(ChunkType(bytes))
         } else {
-            Err(ChunkTypeError::InvalidConversion)
+            Err(ChunkTypeError::InvalidConversion(bytes))
         }
     }
 }
@@ -82,9 +72,9 @@ impl FromStr for ChunkType {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let bytes: [u8; 4] = s.as_bytes()
             .try_into()
-            .map_err(|_| ChunkTypeError::InvalidString)?;
+            .map_err(|_| ChunkTypeError::InvalidString(s.to_string()))?;
 
-        Ok(ChunkType::try_from(bytes)?)
+        ChunkType::try_from(bytes)
     }
 }
 
@@ -93,4 +83,17 @@ impl Display for ChunkType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", String::from_utf8_lossy(&self.bytes()))
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for ChunkTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkTypeError::InvalidConversion(bytes) => {
+                write!(f, "Chunk type bytes {:02x?} are not all ASCII letters", bytes)
+            }
+            ChunkTypeError::InvalidString(s) => write!(f, "Chunk type '{}' must be four ASCII letters", s),
+        }
+    }
+}
+
+impl std::error::Error for ChunkTypeError {}
\ No newline at end of file
@@ -0,0 +1,299 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use crate::png::chunk::Chunk;
+use crate::png::ihdr::Ihdr;
+use crate::png::image::Png;
+use crate::png::text::{TextEntry, TextKind};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkChange {
+    Added {
+        new_index: usize,
+        chunk_type: String,
+        length: usize,
+    },
+    Removed {
+        old_index: usize,
+        chunk_type: String,
+        length: usize,
+    },
+    Moved {
+        old_index: usize,
+        new_index: usize,
+        chunk_type: String,
+    },
+    Modified {
+        old_index: usize,
+        new_index: usize,
+        chunk_type: String,
+        bytes: ByteSummary,
+        fields: Vec<FieldChange>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteSummary {
+    pub old_length: usize,
+    pub new_length: usize,
+    pub changed_bytes: usize,
+    pub first_difference: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+// What a chunk is matched on: its type, or the keyword for text chunks
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ChunkKey {
+    Type(String),
+    Text(String),
+}
+
+/**
+ * Compares the chunk lists of two PNGs.
+ *
+ * Chunks are paired up by type (text chunks by keyword) in order of appearance, so the second tEXt
+ * chunk with a keyword is compared against the second one in the other image. Paired chunks whose
+ * data differs are modified. Of the paired chunks, the largest by size that keep their relative order
+ * stay in place and the others are moved, so a small chunk hopping over IDAT shows up as moved.
+ *
+ * @returns diff - Returns every change from `old` to `new`: removed chunks first, then the rest in the new order.
+ */
+pub fn diff(old: &Png, new: &Png) -> Vec<ChunkChange> {
+    let old_keys = keys(old.chunks());
+    let new_keys = keys(new.chunks());
+
+    let mut unmatched: HashMap<&(ChunkKey, usize), usize> = new_keys.iter().enumerate().map(|(idx, key)| (key, idx)).collect();
+    let mut pairs = Vec::new();
+    let mut changes = Vec::new();
+
+    for (old_index, key) in old_keys.iter().enumerate() {
+        match unmatched.remove(key) {
+            Some(new_index) => pairs.push((old_index, new_index)),
+            None => {
+                let chunk = &old.chunks()[old_index];
+                changes.push(ChunkChange::Removed {
+                    old_index,
+                    chunk_type: chunk.chunk_type().to_string(),
+                    length: chunk.data().len(),
+                });
+            }
+        }
+    }
+
+    let sizes: Vec<usize> = pairs.iter().map(|&(_, new_index)| new.chunks()[new_index].data().len() + 12).collect();
+    let in_order = in_order(&pairs, &sizes);
+    let mut by_new_index: Vec<(usize, ChunkChange)> = Vec::new();
+
+    for (i, &(old_index, new_index)) in pairs.iter().enumerate() {
+        let (old_chunk, new_chunk) = (&old.chunks()[old_index], &new.chunks()[new_index]);
+        let chunk_type = new_chunk.chunk_type().to_string();
+
+        if !in_order[i] {
+            by_new_index.push((new_index, ChunkChange::Moved { old_index, new_index, chunk_type: chunk_type.clone() }));
+        }
+
+        if old_chunk.chunk_type() != new_chunk.chunk_type() || old_chunk.data() != new_chunk.data() {
+            by_new_index.push((
+                new_index,
+                ChunkChange::Modified {
+                    old_index,
+                    new_index,
+                    chunk_type,
+                    bytes: ByteSummary::new(old_chunk.data(), new_chunk.data()),
+                    fields: field_changes(old_chunk, new_chunk),
+                },
+            ));
+        }
+    }
+
+    for new_index in unmatched.into_values() {
+        let chunk = &new.chunks()[new_index];
+        by_new_index.push((
+            new_index,
+            ChunkChange::Added {
+                new_index,
+                chunk_type: chunk.chunk_type().to_string(),
+                length: chunk.data().len(),
+            },
+        ));
+    }
+
+    // Stable, so a chunk that moved and changed lists the move first
+    by_new_index.sort_by_key(|(idx, _)| *idx);
+    changes.extend(by_new_index.into_iter().map(|(_, change)| change));
+    changes
+}
+
+// Pairs each chunk's key with the number of chunks before it that share the key
+fn keys(chunks: &[Chunk]) -> Vec<(ChunkKey, usize)> {
+    let mut seen: HashMap<ChunkKey, usize> = HashMap::new();
+
+    chunks
+        .iter()
+        .map(|chunk| {
+            let key = match TextEntry::keyword_of(chunk) {
+                Ok(keyword) => ChunkKey::Text(keyword),
+                Err(_) => ChunkKey::Type(chunk.chunk_type().to_string()),
+            };
+            let count = seen.entry(key.clone()).or_insert(0);
+            *count += 1;
+            (key, *count - 1)
+        })
+        .collect()
+}
+
+// Marks the pairs that stay in order on both sides, keeping as many bytes in place as possible; the rest were moved.
+// This is a heaviest increasing run of new indices, found in O(n log n) with a Fenwick tree over the new indices
+// so that images split into thousands of IDAT chunks stay fast
+fn in_order(pairs: &[(usize, usize)], sizes: &[usize]) -> Vec<bool> {
+    let len = pairs.iter().map(|&(_, new_index)| new_index + 1).max().unwrap_or(0);
+    // The heaviest run ending at a new index below each position, as (bytes, pair); ties go to the earlier pair
+    let mut tree: Vec<Option<(usize, Reverse<usize>)>> = vec![None; len + 1];
+    let mut weights = vec![0; pairs.len()];
+    let mut previous = vec![None; pairs.len()];
+
+    for (i, &(_, new_index)) in pairs.iter().enumerate() {
+        let mut best = None;
+        let mut pos = new_index;
+        while pos > 0 {
+            best = best.max(tree[pos]);
+            pos &= pos - 1;
+        }
+
+        weights[i] = best.map_or(0, |(weight, _)| weight) + sizes[i];
+        previous[i] = best.map(|(_, Reverse(j))| j);
+
+        let mut pos = new_index + 1;
+        while pos <= len {
+            tree[pos] = tree[pos].max(Some((weights[i], Reverse(i))));
+            pos += pos & pos.wrapping_neg();
+        }
+    }
+
+    let mut in_order = vec![false; pairs.len()];
+    let mut current = (0..pairs.len()).max_by_key(|&i| (weights[i], Reverse(i)));
+    while let Some(i) = current {
+        in_order[i] = true;
+        current = previous[i];
+    }
+    in_order
+}
+
+fn field_changes(old: &Chunk, new: &Chunk) -> Vec<FieldChange> {
+    let old_fields = typed_fields(old);
+    let new_fields = typed_fields(new);
+    if old_fields.is_empty() || new_fields.is_empty() {
+        return Vec::new();
+    }
+
+    old_fields
+        .into_iter()
+        .zip(new_fields)
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((field, old), (_, new))| FieldChange { field: field.to_string(), old, new })
+        .collect()
+}
+
+// The fields of the chunk types we know how to parse, or nothing if the chunk does not parse
+fn typed_fields(chunk: &Chunk) -> Vec<(&'static str, String)> {
+    if chunk.chunk_type().to_string() == "IHDR" {
+        let Ok(ihdr) = Ihdr::try_from(chunk.data()) else {
+            return Vec::new();
+        };
+        return vec![
+            ("width", ihdr.width.to_string()),
+            ("height", ihdr.height.to_string()),
+            ("bit_depth", ihdr.bit_depth.to_string()),
+            ("color_type", ihdr.color_type.to_string()),
+            ("compression_method", ihdr.compression_method.to_string()),
+            ("filter_method", ihdr.filter_method.to_string()),
+            ("interlace_method", ihdr.interlace_method.to_string()),
+        ];
+    }
+
+    let Ok(entry) = TextEntry::try_from(chunk) else {
+        return Vec::new();
+    };
+    let (compressed, language_tag, translated_keyword) = match &entry.kind {
+        TextKind::Text => (false, "", ""),
+        TextKind::Compressed => (true, "", ""),
+        TextKind::International { compressed, language_tag, translated_keyword } => {
+            (*compressed, language_tag.as_str(), translated_keyword.as_str())
+        }
+    };
+    vec![
+        ("chunk_type", entry.chunk_type().to_string()),
+        ("text", entry.text.clone()),
+        ("compressed", compressed.to_string()),
+        ("language_tag", language_tag.to_string()),
+        ("translated_keyword", translated_keyword.to_string()),
+    ]
+}
+
+/**
+ * Summarizes how the data of a modified chunk changed.
+ *
+ * @returns new - Counts the bytes that differ, counting every byte past the end of the shorter data as changed.
+ */
+impl ByteSummary {
+    pub fn new(old: &[u8], new: &[u8]) -> ByteSummary {
+        let common = old.len().min(new.len());
+        let differing = old.iter().zip(new).filter(|(a, b)| a != b).count();
+
+        ByteSummary {
+            old_length: old.len(),
+            new_length: new.len(),
+            changed_bytes: differing + old.len().abs_diff(new.len()),
+            first_difference: old.iter().zip(new).position(|(a, b)| a != b).unwrap_or(common),
+        }
+    }
+}
+
+impl Display for ChunkChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkChange::Added { new_index, chunk_type, length } => {
+                write!(f, "added: chunk#{} ({}) {} bytes", new_index, chunk_type, length)
+            }
+            ChunkChange::Removed { old_index, chunk_type, length } => {
+                write!(f, "removed: chunk#{} ({}) {} bytes", old_index, chunk_type, length)
+            }
+            ChunkChange::Moved { old_index, new_index, chunk_type } => {
+                write!(f, "moved: chunk#{} -> chunk#{} ({})", old_index, new_index, chunk_type)
+            }
+            ChunkChange::Modified { old_index, new_index, chunk_type, bytes, fields } => {
+                write!(f, "modified: chunk#{}", old_index)?;
+                if old_index != new_index {
+                    write!(f, " -> chunk#{}", new_index)?;
+                }
+                write!(f, " ({}) {}", chunk_type, bytes)?;
+                for field in fields {
+                    write!(f, "\n  {}", field)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Display for ByteSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} bytes, {} changed from offset {}",
+            self.old_length, self.new_length, self.changed_bytes, self.first_difference
+        )
+    }
+}
+
+impl Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:?} -> {:?}", self.field, self.old, self.new)
+    }
+}
@@ -0,0 +1,147 @@
+use std::io::Write;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use crate::png::chunk::Chunk;
+use crate::png::chunk_type::ChunkType;
+use crate::png::filter::FilterType;
+use crate::png::ihdr::Ihdr;
+use crate::png::pixels::{bytes_per_pixel, pack_samples, pass_layout, pass_sizes, row_bytes, PixelBuffer, PixelError};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterStrategy {
+    // Same filter on every scanline
+    Fixed(FilterType),
+    // Per scanline, the filter whose output has the smallest sum of absolute values (as signed bytes)
+    MinSum,
+    // Per scanline, the filter whose output compresses smallest
+    BruteForce,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncoderOptions {
+    pub filter: FilterStrategy,
+    pub compression_level: u32,
+    pub idat_chunk_size: usize,
+    pub interlaced: bool,
+}
+
+// Largest data length a chunk's length field may hold
+pub const MAX_CHUNK_SIZE: usize = i32::MAX as usize;
+
+impl Default for EncoderOptions {
+    fn default() -> Self {
+        EncoderOptions {
+            filter: FilterStrategy::MinSum,
+            compression_level: 6,
+            idat_chunk_size: 8192,
+            interlaced: false,
+        }
+    }
+}
+
+/**
+ * Encodes a pixel buffer into IHDR, IDAT and IEND chunks.
+ *
+ * @returns encode - Returns the IHDR chunk, the filtered and deflated image data split into IDAT
+ *                   chunks of at most `idat_chunk_size` bytes, and IEND.
+ * @returns image_data - Returns the zlib stream of filtered scanlines, before it is split into IDAT chunks.
+ */
+pub fn encode(pixels: &PixelBuffer, options: &EncoderOptions) -> Result<Vec<Chunk>, PixelError> {
+    let ihdr = header(pixels, options)?;
+    let data = image_data(pixels, options)?;
+    let chunk_size = options.idat_chunk_size.clamp(1, MAX_CHUNK_SIZE);
+
+    let mut chunks = vec![ihdr.to_chunk()];
+    chunks.extend(data.chunks(chunk_size).map(|part| {
+        Chunk::new(ChunkType::from_str("IDAT").expect("IDAT is a valid chunk type"), part.to_vec())
+    }));
+    chunks.push(Chunk::new(ChunkType::from_str("IEND").expect("IEND is a valid chunk type"), vec![]));
+
+    Ok(chunks)
+}
+
+pub fn image_data(pixels: &PixelBuffer, options: &EncoderOptions) -> Result<Vec<u8>, PixelError> {
+    if options.compression_level > 9 {
+        return Err(PixelError::InvalidCompressionLevel(options.compression_level));
+    }
+    let compression = Compression::new(options.compression_level);
+
+    let ihdr = header(pixels, options)?;
+    let channels = pixels.channels();
+    let bpp = bytes_per_pixel(pixels.color_type(), pixels.bit_depth());
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), compression);
+    let mut row_samples = Vec::new();
+    let mut row = Vec::new();
+    let mut filtered = Vec::new();
+
+    for (&(x0, y0, dx, dy), &(pass_width, pass_height)) in pass_layout(options.interlaced).iter().zip(pass_sizes(&ihdr).iter()) {
+        if pass_width == 0 || pass_height == 0 {
+            continue;
+        }
+
+        let stride = row_bytes(pass_width, pixels.color_type(), pixels.bit_depth()).ok_or(PixelError::ImageTooLarge)?;
+        let mut prev = vec![0u8; stride];
+
+        for row_idx in 0..pass_height {
+            let y = (y0 + row_idx * dy) as u32;
+            row_samples.clear();
+            for col in 0..pass_width {
+                let x = (x0 + col * dx) as u32;
+                row_samples.extend((0..channels).map(|c| pixels.sample(x, y, c)));
+            }
+
+            row.clear();
+            pack_samples(&row_samples, pixels.bit_depth(), &mut row);
+
+            filtered.clear();
+            filter_row(options.filter, &row, &prev, bpp, compression, &mut filtered);
+            encoder.write_all(&filtered).map_err(PixelError::Compress)?;
+
+            std::mem::swap(&mut prev, &mut row);
+        }
+    }
+
+    encoder.finish().map_err(PixelError::Compress)
+}
+
+fn header(pixels: &PixelBuffer, options: &EncoderOptions) -> Result<Ihdr, PixelError> {
+    Ihdr::new(pixels.width(), pixels.height(), pixels.bit_depth(), pixels.color_type(), options.interlaced)
+        .map_err(PixelError::InvalidHeader)
+}
+
+// Writes the filter type byte followed by the filtered scanline
+fn filter_row(strategy: FilterStrategy, row: &[u8], prev: &[u8], bpp: usize, compression: Compression, out: &mut Vec<u8>) {
+    let filter = match strategy {
+        FilterStrategy::Fixed(filter) => filter,
+        FilterStrategy::MinSum => pick_filter(row, prev, bpp, |candidate| {
+            candidate.iter().map(|&b| (b as i8).unsigned_abs() as usize).sum()
+        }),
+        FilterStrategy::BruteForce => pick_filter(row, prev, bpp, |candidate| {
+            let mut encoder = ZlibEncoder::new(Vec::new(), compression);
+            encoder
+                .write_all(candidate)
+                .and_then(|_| encoder.finish())
+                .map(|compressed| compressed.len())
+                .unwrap_or(usize::MAX)
+        }),
+    };
+
+    out.push(filter.value());
+    filter.apply(row, prev, bpp, out);
+}
+
+fn pick_filter(row: &[u8], prev: &[u8], bpp: usize, cost: impl Fn(&[u8]) -> usize) -> FilterType {
+    let mut candidate = Vec::with_capacity(row.len());
+
+    FilterType::ALL
+        .iter()
+        .copied()
+        .min_by_key(|filter| {
+            candidate.clear();
+            filter.apply(row, prev, bpp, &mut candidate);
+            cost(&candidate)
+        })
+        .unwrap_or(FilterType::None)
+}
@@ -0,0 +1,111 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterType {
+    None,
+    Sub,
+    Up,
+    Average,
+    Paeth,
+}
+
+/**
+ * Provides methods for working with the five PNG scanline filters.
+ *
+ * @returns value - Returns the filter type byte that prefixes each scanline.
+ * @returns apply - Filters a scanline against the previous (unfiltered) scanline, appending the result to `out`.
+ * @returns unfilter - Reverses the filter in place on a scanline, given the previous (already unfiltered) scanline.
+ */
+impl FilterType {
+    pub const ALL: [FilterType; 5] = [
+        FilterType::None,
+        FilterType::Sub,
+        FilterType::Up,
+        FilterType::Average,
+        FilterType::Paeth,
+    ];
+
+    pub fn value(&self) -> u8 {
+        match self {
+            FilterType::None => 0,
+            FilterType::Sub => 1,
+            FilterType::Up => 2,
+            FilterType::Average => 3,
+            FilterType::Paeth => 4,
+        }
+    }
+
+    pub fn apply(&self, row: &[u8], prev: &[u8], bpp: usize, out: &mut Vec<u8>) {
+        for i in 0..row.len() {
+            let a = if i >= bpp { row[i - bpp] } else { 0 };
+            let c = if i >= bpp { prev[i - bpp] } else { 0 };
+            let predicted = match self {
+                FilterType::None => 0,
+                FilterType::Sub => a,
+                FilterType::Up => prev[i],
+                FilterType::Average => ((a as u16 + prev[i] as u16) / 2) as u8,
+                FilterType::Paeth => paeth_predictor(a, prev[i], c),
+            };
+            out.push(row[i].wrapping_sub(predicted));
+        }
+    }
+
+    // `bpp` is the number of bytes per complete pixel, rounded up to one for sub-byte depths.
+    // `prev` is all zeroes for the first scanline of an image or interlace pass.
+    pub fn unfilter(&self, row: &mut [u8], prev: &[u8], bpp: usize) {
+        match self {
+            FilterType::None => {}
+            FilterType::Sub => {
+                for i in bpp..row.len() {
+                    row[i] = row[i].wrapping_add(row[i - bpp]);
+                }
+            }
+            FilterType::Up => {
+                for (x, b) in row.iter_mut().zip(prev) {
+                    *x = x.wrapping_add(*b);
+                }
+            }
+            FilterType::Average => {
+                for i in 0..row.len() {
+                    let a = if i >= bpp { row[i - bpp] } else { 0 };
+                    row[i] = row[i].wrapping_add(((a as u16 + prev[i] as u16) / 2) as u8);
+                }
+            }
+            FilterType::Paeth => {
+                for i in 0..row.len() {
+                    let (a, c) = if i >= bpp { (row[i - bpp], prev[i - bpp]) } else { (0, 0) };
+                    row[i] = row[i].wrapping_add(paeth_predictor(a, prev[i], c));
+                }
+            }
+        }
+    }
+}
+
+pub fn paeth_predictor(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// Takes in a scanline's filter type byte and see if it is one the spec defines
+impl TryFrom<u8> for FilterType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(FilterType::None),
+            1 => Ok(FilterType::Sub),
+            2 => Ok(FilterType::Up),
+            3 => Ok(FilterType::Average),
+            4 => Ok(FilterType::Paeth),
+            _ => Err(value),
+        }
+    }
+}
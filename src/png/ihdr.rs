@@ -0,0 +1,240 @@
+use std::fmt::{self, Display};
+use crate::png::chunk::Chunk;
+use crate::png::chunk_type::ChunkType;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorType {
+    Grayscale,
+    Rgb,
+    Indexed,
+    GrayscaleAlpha,
+    Rgba,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ihdr {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: ColorType,
+    pub compression_method: u8,
+    pub filter_method: u8,
+    pub interlace_method: u8,
+}
+
+#[derive(Debug)]
+pub enum IhdrError {
+    InvalidLength(usize),
+    InvalidDimensions,
+    InvalidColorType(u8),
+    InvalidBitDepth { color_type: ColorType, bit_depth: u8 },
+    InvalidCompressionMethod(u8),
+    InvalidFilterMethod(u8),
+    InvalidInterlaceMethod(u8),
+}
+
+/**
+ * Provides methods for working with the PNG color type stored in IHDR.
+ *
+ * @returns value - Returns the color type code as stored in the file.
+ * @returns channels - Returns the number of samples per pixel.
+ * @returns allowed_bit_depths - Returns the bit depths the spec allows for this color type.
+ */
+impl ColorType {
+    pub fn value(&self) -> u8 {
+        match self {
+            ColorType::Grayscale => 0,
+            ColorType::Rgb => 2,
+            ColorType::Indexed => 3,
+            ColorType::GrayscaleAlpha => 4,
+            ColorType::Rgba => 6,
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        match self {
+            ColorType::Grayscale | ColorType::Indexed => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+        }
+    }
+
+    pub fn allowed_bit_depths(&self) -> &'static [u8] {
+        match self {
+            ColorType::Grayscale => &[1, 2, 4, 8, 16],
+            ColorType::Indexed => &[1, 2, 4, 8],
+            ColorType::Rgb | ColorType::GrayscaleAlpha | ColorType::Rgba => &[8, 16],
+        }
+    }
+}
+
+/**
+ * Provides methods for working with the typed IHDR (image header) chunk.
+ *
+ * @returns new - Builds an IHDR and checks that its values are legal per the PNG spec.
+ * @returns validate - Returns an error if any field holds a value the spec does not allow.
+ * @returns is_interlaced - Returns `true` if the image uses Adam7 interlacing.
+ * @returns as_bytes - Returns the 13-byte IHDR chunk data.
+ * @returns to_chunk - Returns an `IHDR` chunk holding this header.
+ */
+impl Ihdr {
+    pub const LENGTH: usize = 13;
+    pub const MAX_DIMENSION: u32 = i32::MAX as u32;
+
+    pub fn new(width: u32, height: u32, bit_depth: u8, color_type: ColorType, interlaced: bool) -> Result<Ihdr, IhdrError> {
+        let ihdr = Ihdr {
+            width,
+            height,
+            bit_depth,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: interlaced as u8,
+        };
+        ihdr.validate()?;
+
+        Ok(ihdr)
+    }
+
+    pub fn validate(&self) -> Result<(), IhdrError> {
+        if self.width == 0 || self.height == 0
+            || self.width > Ihdr::MAX_DIMENSION || self.height > Ihdr::MAX_DIMENSION {
+            return Err(IhdrError::InvalidDimensions);
+        }
+
+        if !self.color_type.allowed_bit_depths().contains(&self.bit_depth) {
+            return Err(IhdrError::InvalidBitDepth {
+                color_type: self.color_type,
+                bit_depth: self.bit_depth,
+            });
+        }
+
+        if self.compression_method != 0 {
+            return Err(IhdrError::InvalidCompressionMethod(self.compression_method));
+        }
+
+        if self.filter_method != 0 {
+            return Err(IhdrError::InvalidFilterMethod(self.filter_method));
+        }
+
+        if self.interlace_method > 1 {
+            return Err(IhdrError::InvalidInterlaceMethod(self.interlace_method));
+        }
+
+        Ok(())
+    }
+
+    pub fn is_interlaced(&self) -> bool {
+        self.interlace_method == 1
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        [
+            self.width.to_be_bytes().as_ref(),
+            self.height.to_be_bytes().as_ref(),
+            &[
+                self.bit_depth,
+                self.color_type.value(),
+                self.compression_method,
+                self.filter_method,
+                self.interlace_method,
+            ],
+        ]
+        .concat()
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let chunk_type = ChunkType::from_str("IHDR").expect("IHDR is a valid chunk type");
+        Chunk::new(chunk_type, self.as_bytes())
+    }
+}
+
+// Takes in a color type code and see if it is one the spec defines
+impl TryFrom<u8> for ColorType {
+    type Error = IhdrError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ColorType::Grayscale),
+            2 => Ok(ColorType::Rgb),
+            3 => Ok(ColorType::Indexed),
+            4 => Ok(ColorType::GrayscaleAlpha),
+            6 => Ok(ColorType::Rgba),
+            _ => Err(IhdrError::InvalidColorType(value)),
+        }
+    }
+}
+
+// Takes in the IHDR chunk data and builds a validated header out of it
+impl TryFrom<&[u8]> for Ihdr {
+    type Error = IhdrError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let bytes: &[u8; Ihdr::LENGTH] = bytes
+            .try_into()
+            .map_err(|_| IhdrError::InvalidLength(bytes.len()))?;
+
+        let ihdr = Ihdr {
+            width: u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            height: u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+            bit_depth: bytes[8],
+            color_type: ColorType::try_from(bytes[9])?,
+            compression_method: bytes[10],
+            filter_method: bytes[11],
+            interlace_method: bytes[12],
+        };
+        ihdr.validate()?;
+
+        Ok(ihdr)
+    }
+}
+
+impl Display for ColorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColorType::Grayscale => "Grayscale",
+            ColorType::Rgb => "RGB",
+            ColorType::Indexed => "Indexed",
+            ColorType::GrayscaleAlpha => "Grayscale + Alpha",
+            ColorType::Rgba => "RGBA",
+        };
+        write!(f, "{} ({})", name, self.value())
+    }
+}
+
+impl Display for Ihdr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "width: {}", self.width)?;
+        writeln!(f, "height: {}", self.height)?;
+        writeln!(f, "bit_depth: {}", self.bit_depth)?;
+        writeln!(f, "color_type: {}", self.color_type)?;
+        writeln!(f, "compression_method: {}", self.compression_method)?;
+        writeln!(f, "filter_method: {}", self.filter_method)?;
+        write!(
+            f,
+            "interlace_method: {} ({})",
+            self.interlace_method,
+            if self.is_interlaced() { "Adam7" } else { "none" }
+        )
+    }
+}
+
+impl fmt::Display for IhdrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IhdrError::InvalidLength(length) => write!(f, "IHDR must be {} bytes long, found {}", Ihdr::LENGTH, length),
+            IhdrError::InvalidDimensions => write!(f, "Image width and height must be between 1 and {}", Ihdr::MAX_DIMENSION),
+            IhdrError::InvalidColorType(value) => write!(f, "Invalid color type {}", value),
+            IhdrError::InvalidBitDepth { color_type, bit_depth } => {
+                write!(f, "Bit depth {} is not allowed for {} images", bit_depth, color_type)
+            }
+            IhdrError::InvalidCompressionMethod(method) => write!(f, "Invalid compression method {}", method),
+            IhdrError::InvalidFilterMethod(method) => write!(f, "Invalid filter method {}", method),
+            IhdrError::InvalidInterlaceMethod(method) => write!(f, "Invalid interlace method {}", method),
+        }
+    }
+}
+
+impl std::error::Error for IhdrError {}
@@ -1,18 +1,56 @@
+use crate::png::ancillary::{Ancillary, AncillaryChunk, AncillaryError, Placement};
+use crate::png::atomic::{self, WriteOptions};
+use crate::png::apng::{self, AnimationControl, ApngError, Frame, FrameControl, FrameOptions};
 use crate::png::chunk::{Chunk, ChunkError};
+use crate::png::chunk_type::ChunkType;
+use crate::png::ihdr::{ColorType, Ihdr, IhdrError};
+use crate::png::encoder::{self, EncoderOptions};
+use crate::png::palette::{Palette, PaletteError};
+use crate::png::pixels::{PixelBuffer, PixelError};
+use crate::png::text::{TextEntry, TextError};
 use std::fmt::{self, Display};
-use std::fs;
-use std::path::Path;
+use std::str::FromStr;
+use crate::png::reader::PngReader;
+use crate::png::writer::PngWriter;
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
 pub struct Png {
     header: [u8; 8],
-    chunks: Vec<Chunk>
+    chunks: Vec<Chunk>,
+    corrupted_chunks: Vec<CorruptedChunk>,
 }
 
+// A chunk whose stored CRC does not match the CRC computed over its type and data.
+// `expected` is the CRC stored in the file, `actual` is the one computed while parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptedChunk {
+    pub index: usize,
+    pub chunk_type: String,
+    pub offset: usize,
+    pub expected: u32,
+    pub actual: u32,
+}
+
+// `InvalidSignature` holds the bytes found where the signature should be (fewer than 8 if the input is shorter).
+// `UnexpectedEOF` holds the offset at which the input ended.
 #[derive(Debug)]
 pub enum PngError {
-    InvalidSignature,
-    InvalidChunk,
-    UnexpectedEOF,
-    Chunk(ChunkError)
+    InvalidSignature(Vec<u8>),
+    ChunkNotFound(String),
+    UnexpectedEOF { offset: u64 },
+    IndexOutOfBounds(usize),
+    CorruptedChunk(CorruptedChunk),
+    ChunkOrder(String),
+    NoPendingChunk,
+    Chunk(ChunkError),
+    Ihdr(IhdrError),
+    Text(TextError),
+    Pixel(PixelError),
+    Apng(ApngError),
+    Ancillary(AncillaryError),
+    Palette(PaletteError),
+    Io { path: Option<PathBuf>, source: std::io::Error },
 }
 
 /**
@@ -23,12 +61,46 @@ pub enum PngError {
  * @returns as_bytes - Returns a `Vec<u8>` representing the complete PNG file (header concatenated with all chunks).
  * @returns from_chunks - Constructs a PNG image from a vector of chunks.
  * @returns append_chunk - Appends a new chunk to the PNG image.
+ * @returns insert_chunk - Inserts a chunk at the given index, shifting later chunks back.
+ * @returns insert_before - Inserts a chunk right before the first chunk with the given type.
+ * @returns insert_after - Inserts a chunk right after the first chunk with the given type.
  * @returns remove_first_chunk - Removes and returns the first chunk with the specified type.
+ * @returns remove_chunk_at - Removes and returns the chunk at the given index.
+ * @returns remove_all - Removes every chunk with the given type and returns them in order; none is not an error.
+ * @returns retain - Keeps only the chunks the predicate returns `true` for and returns the removed ones in order.
  * @returns chunk_by_type - Returns a reference to the first chunk with the given type.
+ * @returns chunks_by_type - Iterates over every chunk with the given type, in file order.
+ * @returns chunk_at - Returns the chunk at the given index.
+ * @returns position_of - Returns the index of the first chunk with the given type.
+ * @returns position_before - Returns the index of the first chunk with one of the given types, or the number of chunks if there is none.
+ * @returns ihdr - Returns the typed image header parsed from the IHDR chunk.
+ * @returns image_data - Returns the concatenated data of every IDAT chunk (the zlib stream).
+ * @returns pixels - Decodes the image data into a typed pixel buffer.
+ * @returns from_pixels - Builds a PNG (IHDR, IDAT and IEND) from a pixel buffer.
+ * @returns replace_pixels - Re-encodes the image data from a pixel buffer, keeping every other chunk in place.
+ * @returns text_entries - Returns every tEXt, zTXt and iTXt entry in chunk order, skipping chunks that do not decode.
+ * @returns text - Returns the first text entry with the given keyword.
+ * @returns set_text - Replaces all text entries with the entry's keyword by the entry, placed before IEND.
+ * @returns remove_text - Removes all text entries with the given keyword and returns how many were removed; only keywords are read.
+ * @returns ancillary - Returns the first chunk of a standard ancillary type (gAMA, pHYs, tIME, ...) in typed form.
+ * @returns ancillary_chunks - Returns every chunk with a typed form, with its index, in chunk order.
+ * @returns set_ancillary - Checks the value against the image, then replaces the chunks of its type or inserts it where the spec places it.
+ * @returns palette - Returns the PLTE entries with their tRNS alpha for indexed images, or `None` if there is no PLTE.
+ * @returns set_palette - Checks the palette against IHDR, then replaces PLTE and tRNS in place, adding or dropping tRNS as needed.
+ * @returns animation_control - Returns the parsed acTL chunk, or `None` if the image is not animated.
+ * @returns frames - Returns every animation frame, checking sequence numbers, frame count and frame bounds.
+ * @returns frame_png - Builds a standalone PNG out of one frame's region, without compositing earlier frames.
+ * @returns frame_pngs - Same as `frame_png` for every frame, reading the frames only once.
+ * @returns from_frames - Assembles an APNG from standalone PNGs, the first one setting the canvas size and default image.
+ * @returns corrupted_chunks - Returns the chunks that failed CRC verification (only populated in lenient mode).
+ * @returns from_bytes_lenient - Parses a PNG, recording CRC failures instead of rejecting the file.
  * @returns from_file returns the file as a bytes
- * @returns write_file writes bytes into the file
+ * @returns from_file_lenient - Same as `from_file` but records CRC failures instead of rejecting the file.
+ * @returns from_reader - Collects every chunk from a streaming reader into an in-memory PNG.
+ * @returns write_file writes bytes into the file, replacing it atomically so a failed write leaves the old file intact
+ * @returns write_file_with - Same as `write_file`, with a backup of the replaced file or its times kept as `options` ask.
+ * @returns write_to - Writes the signature and chunks in their current order to any writer.
  */
-
 #[allow(dead_code)]
 impl Png {
     pub const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
@@ -36,7 +108,8 @@ impl Png {
     pub fn from_chunks(chunks: Vec<Chunk>) -> Png {
         Png {
             header: Png::STANDARD_HEADER,
-            chunks: chunks
+            chunks,
+            corrupted_chunks: Vec::new(),
         }
     }
     
@@ -44,16 +117,57 @@ impl Png {
         self.chunks.push(chunk);
     }
 
-    pub fn remove_chunk(&mut self, chunk_type: &str) -> Result<Chunk, PngError> {
-        if let Some(idx) = self.chunks
+    pub fn insert_chunk(&mut self, index: usize, chunk: Chunk) -> Result<(), PngError> {
+        if index > self.chunks.len() {
+            return Err(PngError::IndexOutOfBounds(index));
+        }
+
+        self.chunks.insert(index, chunk);
+        Ok(())
+    }
+
+    pub fn insert_before(&mut self, chunk_type: &str, chunk: Chunk) -> Result<(), PngError> {
+        let idx = self.position_of(chunk_type).ok_or_else(|| PngError::ChunkNotFound(chunk_type.to_string()))?;
+        self.insert_chunk(idx, chunk)
+    }
+
+    pub fn insert_after(&mut self, chunk_type: &str, chunk: Chunk) -> Result<(), PngError> {
+        let idx = self.position_of(chunk_type).ok_or_else(|| PngError::ChunkNotFound(chunk_type.to_string()))?;
+        self.insert_chunk(idx + 1, chunk)
+    }
+
+    pub fn position_of(&self, chunk_type: &str) -> Option<usize> {
+        self.chunks
             .iter()
-            .position(|c| c.chunk_type().to_string() == chunk_type) {
+            .position(|c| c.chunk_type().to_string() == chunk_type)
+    }
+
+    pub fn remove_chunk(&mut self, chunk_type: &str) -> Result<Chunk, PngError> {
+        if let Some(idx) = self.position_of(chunk_type) {
             Ok(self.chunks.remove(idx))
         } else {
-            Err(PngError::InvalidChunk)
+            Err(PngError::ChunkNotFound(chunk_type.to_string()))
         }
     } 
 
+    pub fn remove_chunk_at(&mut self, index: usize) -> Result<Chunk, PngError> {
+        if index >= self.chunks.len() {
+            return Err(PngError::IndexOutOfBounds(index));
+        }
+
+        Ok(self.chunks.remove(index))
+    }
+
+    pub fn remove_all(&mut self, chunk_type: &str) -> Vec<Chunk> {
+        self.retain(|c| c.chunk_type().to_string() != chunk_type)
+    }
+
+    pub fn retain<F: FnMut(&Chunk) -> bool>(&mut self, mut keep: F) -> Vec<Chunk> {
+        let (kept, removed) = std::mem::take(&mut self.chunks).into_iter().partition(|c| keep(c));
+        self.chunks = kept;
+        removed
+    }
+
     pub fn header(&self) -> &[u8; 8] {
         &self.header
     }
@@ -62,83 +176,350 @@ impl Png {
         &self.chunks
     }
 
+    pub fn corrupted_chunks(&self) -> &[CorruptedChunk] {
+        &self.corrupted_chunks
+    }
+
     pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
         self.chunks.iter().find(|c| c.chunk_type().to_string() == chunk_type)
     }
 
-    pub fn as_bytes(&self) -> Vec<u8> {
-        [
-            Png::STANDARD_HEADER.to_vec(),
-            self.chunks()
-                .iter()
-                .flat_map(|chunk| chunk.as_bytes())
-                .collect(),
-        ]
-        .concat()
+    pub fn chunks_by_type<'a>(&'a self, chunk_type: &'a str) -> impl Iterator<Item = &'a Chunk> + 'a {
+        self.chunks.iter().filter(move |c| c.chunk_type().to_string() == chunk_type)
     }
 
-    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Png, PngError> {
-        let file = fs::read(path)
-            .map_err(|_| PngError::InvalidChunk)?;
+    pub fn chunk_at(&self, index: usize) -> Result<&Chunk, PngError> {
+        self.chunks.get(index).ok_or(PngError::IndexOutOfBounds(index))
+    }
 
-        Ok(file.as_slice().try_into()?)
+    pub fn ihdr(&self) -> Result<Ihdr, PngError> {
+        let chunk = self.chunk_by_type("IHDR").ok_or_else(|| PngError::ChunkNotFound("IHDR".to_string()))?;
+        Ok(Ihdr::try_from(chunk.data())?)
     }
 
-    pub fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<(), PngError> {
-        let _ = fs::write(path, self.as_bytes()).map_err(|_| PngError::InvalidChunk)?;
+    pub fn image_data(&self) -> Vec<u8> {
+        self.chunks
+            .iter()
+            .filter(|c| c.chunk_type().to_string() == "IDAT")
+            .flat_map(|c| c.data().iter().copied())
+            .collect()
+    }
+
+    pub fn pixels(&self) -> Result<PixelBuffer, PngError> {
+        let ihdr = self.ihdr()?;
+        Ok(PixelBuffer::decode(&ihdr, &self.image_data())?)
+    }
+
+    pub fn from_pixels(pixels: &PixelBuffer, options: &EncoderOptions) -> Result<Png, PngError> {
+        Ok(Png::from_chunks(encoder::encode(pixels, options)?))
+    }
+
+    // New IDAT chunks go where the first old one was; IHDR is replaced in place
+    pub fn replace_pixels(&mut self, pixels: &PixelBuffer, options: &EncoderOptions) -> Result<(), PngError> {
+        let mut encoded = encoder::encode(pixels, options)?;
+        encoded.pop();
+        let ihdr = encoded.remove(0);
+
+        let first_idat = self.position_of("IDAT").ok_or(PixelError::NoImageData)?;
+        let ihdr_idx = self.position_of("IHDR").ok_or_else(|| PngError::ChunkNotFound("IHDR".to_string()))?;
+
+        self.chunks[ihdr_idx] = ihdr;
+        self.chunks.retain(|c| c.chunk_type().to_string() != "IDAT");
+        self.chunks.splice(first_idat..first_idat, encoded);
+
         Ok(())
     }
 
-    
-}
+    pub fn text_entries(&self) -> Vec<TextEntry> {
+        self.chunks
+            .iter()
+            .filter(|c| TextEntry::is_text_chunk(c))
+            .filter_map(|c| TextEntry::try_from(c).ok())
+            .collect()
+    }
 
-impl TryFrom<&[u8]> for Png {
-    type Error = PngError;
+    // Only the chunk with the keyword is decoded, so malformed chunks with other keywords do not matter
+    pub fn text(&self, keyword: &str) -> Result<Option<TextEntry>, PngError> {
+        self.chunks
+            .iter()
+            .find(|c| TextEntry::keyword_of(c).is_ok_and(|k| k == keyword))
+            .map(|c| TextEntry::try_from(c).map_err(PngError::from))
+            .transpose()
+    }
 
-    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
-        
-        if bytes.len() < 8  || !bytes.starts_with(&Png::STANDARD_HEADER) {
-            return Err(PngError::InvalidSignature);
+    pub fn set_text(&mut self, entry: TextEntry) -> Result<(), PngError> {
+        let chunk = entry.to_chunk()?;
+        self.remove_text(&entry.keyword);
+
+        match self.position_of("IEND") {
+            Some(idx) => self.insert_chunk(idx, chunk),
+            None => {
+                self.append_chunk(chunk);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn remove_text(&mut self, keyword: &str) -> usize {
+        let before = self.chunks.len();
+        self.chunks.retain(|c| !TextEntry::keyword_of(c).is_ok_and(|k| k == keyword));
+        before - self.chunks.len()
+    }
+
+    pub fn ancillary<T: AncillaryChunk>(&self) -> Result<Option<T>, PngError> {
+        match self.chunk_by_type(T::CHUNK_TYPE) {
+            Some(chunk) => Ok(Some(T::from_chunk(chunk)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn ancillary_chunks(&self) -> Result<Vec<(usize, Ancillary)>, PngError> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, chunk)| Some(Ancillary::from_chunk(chunk)?.map(|value| (idx, value))))
+            .collect::<Result<_, _>>()
+            .map_err(PngError::from)
+    }
+
+    pub fn set_ancillary(&mut self, value: &Ancillary) -> Result<(), PngError> {
+        let palette_entries = self.chunk_by_type("PLTE").map(|plte| plte.data().len() / 3);
+        value.check(&self.ihdr()?, palette_entries)?;
+
+        // An existing chunk of the type is already in a valid spot, so the new one takes its place
+        if let Some(idx) = self.position_of(value.chunk_type()) {
+            self.remove_all(value.chunk_type());
+            return self.insert_chunk(idx, value.to_chunk());
         }
 
-        let mut chunks: Vec<Chunk> = Vec::new();
-        let mut cursor = 8;
+        let idx = match value.placement() {
+            Placement::BeforePlte => self.position_before(&["PLTE", "fcTL", "IDAT", "IEND"]),
+            Placement::BeforeIdat => self.position_before(&["fcTL", "IDAT", "IEND"]),
+            Placement::Anywhere => self.position_before(&["IEND"]),
+        };
+        self.insert_chunk(idx, value.to_chunk())
+    }
 
-        while cursor < bytes.len() {
+    pub fn palette(&self) -> Result<Option<Palette>, PngError> {
+        let Some(plte) = self.chunk_by_type("PLTE") else {
+            return Ok(None);
+        };
+        let trns = self.chunk_by_type("tRNS").map(|c| c.data());
+        Ok(Some(Palette::from_data(plte.data(), trns, &self.ihdr()?)?))
+    }
+
+    pub fn set_palette(&mut self, palette: &Palette) -> Result<(), PngError> {
+        let ihdr = self.ihdr()?;
+        palette.check(&ihdr)?;
+
+        let plte_idx = match self.position_of("PLTE") {
+            Some(idx) => {
+                self.chunks[idx] = palette.to_plte();
+                idx
+            }
+            None => {
+                let idx = self.position_before(&["bKGD", "hIST", "tRNS", "fcTL", "IDAT", "IEND"]);
+                self.insert_chunk(idx, palette.to_plte())?;
+                idx
+            }
+        };
 
-            if cursor + 4 > bytes.len() {
-                return Err(PngError::UnexpectedEOF);
+        // tRNS means something else outside indexed images, so it is left alone there
+        if ihdr.color_type != ColorType::Indexed {
+            return Ok(());
+        }
+        match (self.position_of("tRNS"), palette.to_trns()) {
+            (Some(idx), Some(trns)) => self.chunks[idx] = trns,
+            (Some(idx), None) => {
+                self.chunks.remove(idx);
             }
+            (None, Some(trns)) => self.insert_chunk(plte_idx + 1, trns)?,
+            (None, None) => {}
+        }
+        Ok(())
+    }
+
+    pub fn position_before(&self, chunk_types: &[&str]) -> usize {
+        self.chunks
+            .iter()
+            .position(|c| chunk_types.contains(&c.chunk_type().to_string().as_str()))
+            .unwrap_or(self.chunks.len())
+    }
+
+    pub fn animation_control(&self) -> Result<Option<AnimationControl>, PngError> {
+        match self.chunk_by_type("acTL") {
+            Some(chunk) => Ok(Some(AnimationControl::from_chunk(chunk)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn frames(&self) -> Result<Vec<Frame>, PngError> {
+        Ok(apng::frames(&self.chunks, &self.ihdr()?)?)
+    }
+
+    pub fn frame_png(&self, index: usize) -> Result<Png, PngError> {
+        let ihdr = self.ihdr()?;
+        let frames = self.frames()?;
+        let frame = frames.get(index).ok_or(PngError::IndexOutOfBounds(index))?;
+        Ok(self.png_of_frame(&ihdr, frame))
+    }
+
+    pub fn frame_pngs(&self) -> Result<Vec<Png>, PngError> {
+        let ihdr = self.ihdr()?;
+        Ok(self.frames()?.iter().map(|frame| self.png_of_frame(&ihdr, frame)).collect())
+    }
+
+    // Palette, transparency, color space and other chunks ahead of the image data apply to every frame
+    fn png_of_frame(&self, ihdr: &Ihdr, frame: &Frame) -> Png {
+        let header = Ihdr { width: frame.control.width, height: frame.control.height, ..*ihdr };
+        let mut chunks = vec![header.to_chunk()];
+        chunks.extend(self.chunks_before_image_data());
+        chunks.extend(frame.data.iter().map(|data| Png::new_chunk("IDAT", data.clone())));
+        chunks.push(Png::new_chunk("IEND", Vec::new()));
 
-            let length = u32::from_be_bytes(bytes[cursor..cursor + 4].try_into()?)
-                as usize;
+        Png::from_chunks(chunks)
+    }
+
+    pub fn from_frames(frames: &[(Png, FrameOptions)], num_plays: u32) -> Result<Png, PngError> {
+        let (first, _) = frames.first().ok_or(ApngError::NoFrames)?;
+        let canvas = first.ihdr()?;
+        let palette = |png: &Png| png.chunk_by_type("PLTE").map(|c| c.data().to_vec());
 
-            let total_chunk_len = 4 + 4 + length + 4;
+        let control = AnimationControl { num_frames: frames.len() as u32, num_plays };
+        let mut chunks = vec![canvas.to_chunk(), control.to_chunk()];
+        chunks.extend(first.chunks_before_image_data());
 
-            if cursor + total_chunk_len > bytes.len() {
-                return Err(PngError::UnexpectedEOF);
+        let mut sequence_number = 0;
+        for (index, (png, options)) in frames.iter().enumerate() {
+            let ihdr = png.ihdr()?;
+            let matches = (ihdr.bit_depth, ihdr.color_type, ihdr.interlace_method)
+                == (canvas.bit_depth, canvas.color_type, canvas.interlace_method);
+            if !matches || palette(png) != palette(first) {
+                return Err(ApngError::FrameMismatch(index).into());
             }
 
-            let chunk_bytes = &bytes[cursor..cursor + total_chunk_len];
-            let chunk = Chunk::try_from(chunk_bytes)?;
-            chunks.push(chunk);
+            chunks.push(FrameControl {
+                sequence_number,
+                width: ihdr.width,
+                height: ihdr.height,
+                x_offset: options.x_offset,
+                y_offset: options.y_offset,
+                delay_num: options.delay_num,
+                delay_den: options.delay_den,
+                dispose_op: options.dispose_op,
+                blend_op: options.blend_op,
+            }.to_chunk());
+            sequence_number += 1;
 
-            cursor += total_chunk_len;
+            // The first frame doubles as the default image, so its data stays in IDAT chunks
+            for idat in png.chunks.iter().filter(|c| c.chunk_type().to_string() == "IDAT") {
+                if index == 0 {
+                    chunks.push(idat.clone());
+                } else {
+                    chunks.push(apng::frame_data_chunk(sequence_number, idat.data()));
+                    sequence_number += 1;
+                }
+            }
         }
+        chunks.push(Png::new_chunk("IEND", Vec::new()));
+
+        let png = Png::from_chunks(chunks);
+        png.frames()?;
+        Ok(png)
+    }
 
-        Ok(Png{
+    fn chunks_before_image_data(&self) -> impl Iterator<Item = Chunk> + '_ {
+        self.chunks
+            .iter()
+            .take_while(|c| c.chunk_type().to_string() != "IDAT")
+            .filter(|c| !["IHDR", "acTL", "fcTL"].contains(&c.chunk_type().to_string().as_str()))
+            .cloned()
+    }
+
+    fn new_chunk(chunk_type: &str, data: Vec<u8>) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).expect("chunk type is valid"), data)
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        [
+            Png::STANDARD_HEADER.to_vec(),
+            self.chunks()
+                .iter()
+                .flat_map(|chunk| chunk.as_bytes())
+                .collect(),
+        ]
+        .concat()
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Png, PngError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| PngError::io(path, e))?;
+
+        PngReader::new(BufReader::new(file))
+            .and_then(Png::from_reader)
+            .map_err(|e| e.with_path(path))
+    }
+
+    pub fn from_file_lenient<P: AsRef<Path>>(path: P) -> Result<Png, PngError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| PngError::io(path, e))?;
+
+        PngReader::new_lenient(BufReader::new(file))
+            .and_then(Png::from_reader)
+            .map_err(|e| e.with_path(path))
+    }
+
+    pub fn from_bytes_lenient(bytes: &[u8]) -> Result<Png, PngError> {
+        Png::from_reader(PngReader::new_lenient(bytes)?)
+    }
+
+    pub fn from_reader<R: Read>(mut reader: PngReader<R>) -> Result<Png, PngError> {
+        let mut chunks = Vec::new();
+        while let Some(chunk) = reader.next_chunk()? {
+            chunks.push(chunk);
+        }
+
+        Ok(Png {
             header: Png::STANDARD_HEADER,
             chunks,
+            corrupted_chunks: reader.corrupted_chunks().to_vec(),
         })
     }
+
+    pub fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<(), PngError> {
+        self.write_file_with(path, &WriteOptions::default())
+    }
+
+    pub fn write_file_with<P: AsRef<Path>>(&self, path: P, options: &WriteOptions) -> Result<(), PngError> {
+        atomic::write_atomic(path.as_ref(), options, |writer| self.write_to(writer))
+    }
+
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), PngError> {
+        let mut writer = PngWriter::new_unchecked(writer)?;
+        for chunk in &self.chunks {
+            writer.write_chunk(chunk)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+
+    
+}
+
+impl TryFrom<&[u8]> for Png {
+    type Error = PngError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Png::from_reader(PngReader::new(bytes)?)
+    }
 }
 
 impl Display for Png {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "header: {:?}\nchunks:\n", Png::STANDARD_HEADER)?;
-        write!(f, "[\n")?;
+        writeln!(f, "[")?;
         for chunk in self.chunks.iter() {
-            write!(f, "{}\n", chunk)?;
+            writeln!(f, "{}", chunk)?;
         }
         write!(f, "]")?;
 
@@ -146,9 +527,23 @@ impl Display for Png {
     }
 }
 
-impl From<std::array::TryFromSliceError> for PngError {
-    fn from(_: std::array::TryFromSliceError) -> Self {
-        PngError::UnexpectedEOF
+impl PngError {
+    pub fn io(path: &Path, source: std::io::Error) -> PngError {
+        PngError::Io { path: Some(path.to_path_buf()), source }
+    }
+
+    // Fills in the path of an I/O error raised somewhere the path was not known
+    pub fn with_path(self, path: &Path) -> PngError {
+        match self {
+            PngError::Io { path: None, source } => PngError::io(path, source),
+            e => e,
+        }
+    }
+}
+
+impl From<std::io::Error> for PngError {
+    fn from(source: std::io::Error) -> Self {
+        PngError::Io { path: None, source }
     }
 }
 
@@ -158,10 +553,87 @@ impl From<ChunkError> for PngError {
     }
 }
 
+impl From<IhdrError> for PngError {
+    fn from(e: IhdrError) -> Self {
+        PngError::Ihdr(e)
+    }
+}
+
+impl From<ApngError> for PngError {
+    fn from(e: ApngError) -> Self {
+        PngError::Apng(e)
+    }
+}
+
+impl From<AncillaryError> for PngError {
+    fn from(e: AncillaryError) -> Self {
+        PngError::Ancillary(e)
+    }
+}
+
+impl From<PaletteError> for PngError {
+    fn from(e: PaletteError) -> Self {
+        PngError::Palette(e)
+    }
+}
+
+impl From<TextError> for PngError {
+    fn from(e: TextError) -> Self {
+        PngError::Text(e)
+    }
+}
+
+impl From<PixelError> for PngError {
+    fn from(e: PixelError) -> Self {
+        PngError::Pixel(e)
+    }
+}
+
+impl fmt::Display for CorruptedChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "chunk #{} ({}) at offset {}: CRC mismatch, expected {:#010x}, actual {:#010x}",
+            self.index, self.chunk_type, self.offset, self.expected, self.actual
+        )
+    }
+}
+
 impl fmt::Display for PngError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            PngError::InvalidSignature(found) => write!(f, "Not a PNG file: signature is {:02x?}", found),
+            PngError::ChunkNotFound(chunk_type) => write!(f, "No {} chunk found", chunk_type),
+            PngError::UnexpectedEOF { offset } => write!(f, "File is truncated at offset {}", offset),
+            PngError::IndexOutOfBounds(idx) => write!(f, "Chunk index {} is out of bounds", idx),
+            PngError::CorruptedChunk(corrupted) => write!(f, "Corrupted {}", corrupted),
+            PngError::ChunkOrder(message) => write!(f, "Invalid chunk order: {}", message),
+            PngError::NoPendingChunk => write!(f, "No chunk header has been read"),
+            PngError::Chunk(_) => write!(f, "Invalid chunk"),
+            PngError::Ihdr(_) => write!(f, "Invalid IHDR chunk"),
+            PngError::Text(_) => write!(f, "Invalid text chunk"),
+            PngError::Pixel(_) => write!(f, "Invalid image data"),
+            PngError::Apng(_) => write!(f, "Invalid animation"),
+            PngError::Ancillary(_) => write!(f, "Invalid ancillary chunk"),
+            PngError::Palette(_) => write!(f, "Invalid palette"),
+            PngError::Io { path: Some(path), .. } => write!(f, "I/O error on {}", path.display()),
+            PngError::Io { path: None, .. } => write!(f, "I/O error"),
+        }
     }
 }
 
-impl std::error::Error for PngError {}
\ No newline at end of file
+impl std::error::Error for PngError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PngError::Chunk(e) => Some(e),
+            PngError::Ihdr(e) => Some(e),
+            PngError::Text(e) => Some(e),
+            PngError::Pixel(e) => Some(e),
+            PngError::Apng(e) => Some(e),
+            PngError::Ancillary(e) => Some(e),
+            PngError::Palette(e) => Some(e),
+            PngError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,199 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use crate::png::chunk_type::ChunkType;
+use crate::png::ihdr::{ColorType, Ihdr};
+use crate::png::image::{Png, PngError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub chunk_index: Option<usize>,
+    pub chunk_type: String,
+    pub message: String,
+}
+
+pub const KNOWN_CRITICAL_CHUNKS: [&str; 4] = ["IHDR", "PLTE", "IDAT", "IEND"];
+
+// Chunks the spec allows at most once per image
+pub const SINGLE_INSTANCE_CHUNKS: [&str; 11] = [
+    "IHDR", "PLTE", "IEND", "cHRM", "gAMA", "iCCP", "sBIT", "sRGB", "bKGD", "hIST", "tRNS",
+];
+
+// Chunks the spec requires to appear before PLTE and IDAT
+const BEFORE_PLTE_CHUNKS: [&str; 5] = ["cHRM", "gAMA", "iCCP", "sBIT", "sRGB"];
+
+// Chunks the spec requires to appear after PLTE and before IDAT
+const AFTER_PLTE_CHUNKS: [&str; 3] = ["bKGD", "hIST", "tRNS"];
+
+/**
+ * Checks a PNG against the chunk ordering rules of the PNG spec.
+ *
+ * @returns lint - Returns every diagnostic found, in chunk order, errors and warnings mixed.
+ * @returns lint_chunks - Same as `lint`, from the chunk types and parsed IHDR alone, so streamed files need not be held in memory.
+ * @returns has_errors - Returns `true` if any diagnostic has `Severity::Error`.
+ */
+pub fn lint(png: &Png) -> Vec<Diagnostic> {
+    let types: Vec<String> = png
+        .chunks()
+        .iter()
+        .map(|chunk| chunk.chunk_type().to_string())
+        .collect();
+
+    lint_chunks(&types, png.ihdr())
+}
+
+// `ihdr` is the result of parsing the first IHDR chunk
+pub fn lint_chunks(types: &[String], ihdr: Result<Ihdr, PngError>) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let first_of = |name: &str| types.iter().position(|t| t == name);
+    let last_of = |name: &str| types.iter().rposition(|t| t == name);
+
+    match first_of("IHDR") {
+        Some(0) => {
+            if let Err(e) = &ihdr {
+                diagnostics.push(Diagnostic::error(Some(0), "IHDR", format!("invalid IHDR: {}", e)));
+            }
+        }
+        Some(idx) => diagnostics.push(Diagnostic::error(Some(idx), "IHDR", "IHDR must be the first chunk")),
+        None => diagnostics.push(Diagnostic::error(None, "IHDR", "missing IHDR chunk")),
+    }
+
+    match first_of("IEND") {
+        Some(idx) if idx + 1 != types.len() => {
+            diagnostics.push(Diagnostic::error(Some(idx), "IEND", "IEND must be the last chunk"))
+        }
+        Some(_) => {}
+        None => diagnostics.push(Diagnostic::error(None, "IEND", "missing IEND chunk")),
+    }
+
+    let first_idat = first_of("IDAT");
+    match first_idat {
+        Some(first) => {
+            let last = last_of("IDAT").unwrap_or(first);
+            for (idx, chunk_type) in types.iter().enumerate().take(last).skip(first) {
+                if chunk_type != "IDAT" {
+                    diagnostics.push(Diagnostic::error(
+                        Some(idx),
+                        chunk_type,
+                        "IDAT chunks must be consecutive",
+                    ));
+                }
+            }
+        }
+        None => diagnostics.push(Diagnostic::error(None, "IDAT", "missing IDAT chunk")),
+    }
+
+    let plte = first_of("PLTE");
+    if let (Some(plte), Some(idat)) = (plte, first_idat) && plte > idat {
+        diagnostics.push(Diagnostic::error(Some(plte), "PLTE", "PLTE must come before the first IDAT"));
+    }
+
+    if let Ok(ihdr) = &ihdr {
+        match (ihdr.color_type, plte) {
+            (ColorType::Indexed, None) => {
+                diagnostics.push(Diagnostic::error(None, "PLTE", "indexed-color images require a PLTE chunk"))
+            }
+            (ColorType::Grayscale | ColorType::GrayscaleAlpha, Some(idx)) => {
+                diagnostics.push(Diagnostic::error(Some(idx), "PLTE", "PLTE must not appear in grayscale images"))
+            }
+            _ => {}
+        }
+    }
+
+    for (idx, chunk_type) in types.iter().enumerate() {
+        let limit = plte.or(first_idat);
+        if BEFORE_PLTE_CHUNKS.contains(&chunk_type.as_str()) && limit.is_some_and(|limit| idx > limit) {
+            diagnostics.push(Diagnostic::error(
+                Some(idx),
+                chunk_type,
+                format!("{} must come before PLTE and IDAT", chunk_type),
+            ));
+        }
+
+        let after_plte = plte.is_none_or(|plte| idx > plte);
+        let before_idat = first_idat.is_none_or(|idat| idx < idat);
+        if AFTER_PLTE_CHUNKS.contains(&chunk_type.as_str()) && !(after_plte && before_idat) {
+            diagnostics.push(Diagnostic::error(
+                Some(idx),
+                chunk_type,
+                format!("{} must come after PLTE and before IDAT", chunk_type),
+            ));
+        }
+    }
+
+    for name in SINGLE_INSTANCE_CHUNKS {
+        for (idx, _) in types.iter().enumerate().filter(|(_, t)| *t == name).skip(1) {
+            diagnostics.push(Diagnostic::error(Some(idx), name, format!("duplicate {} chunk", name)));
+        }
+    }
+
+    if let (Some(_), Some(idx)) = (first_of("sRGB"), first_of("iCCP")) {
+        diagnostics.push(Diagnostic::warning(Some(idx), "iCCP", "iCCP and sRGB should not both be present"));
+    }
+
+    for (idx, name) in types.iter().enumerate() {
+        let Ok(chunk_type) = ChunkType::from_str(name) else {
+            continue;
+        };
+
+        if !chunk_type.is_reserved_bit_valid() {
+            diagnostics.push(Diagnostic::error(Some(idx), name, "reserved bit is set in chunk type"));
+        }
+
+        if chunk_type.is_critical() && !KNOWN_CRITICAL_CHUNKS.contains(&name.as_str()) {
+            diagnostics.push(Diagnostic::error(Some(idx), name, format!("unknown critical chunk {}", name)));
+        }
+    }
+
+    diagnostics.sort_by_key(|d| d.chunk_index.unwrap_or(usize::MAX));
+    diagnostics
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == Severity::Error)
+}
+
+impl Diagnostic {
+    pub fn error(chunk_index: Option<usize>, chunk_type: &str, message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Error, chunk_index, chunk_type, message)
+    }
+
+    pub fn warning(chunk_index: Option<usize>, chunk_type: &str, message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Warning, chunk_index, chunk_type, message)
+    }
+
+    fn new(severity: Severity, chunk_index: Option<usize>, chunk_type: &str, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity,
+            chunk_index,
+            chunk_type: chunk_type.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.severity)?;
+        if let Some(idx) = self.chunk_index {
+            write!(f, "chunk#{} ", idx)?;
+        }
+        write!(f, "({}) {}", self.chunk_type, self.message)
+    }
+}
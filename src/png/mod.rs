@@ -1,3 +1,16 @@
 pub mod chunk;
 pub mod chunk_type;
-pub mod image;
\ No newline at end of file
+pub mod image;
+pub mod ihdr;
+pub mod lint;
+pub mod diff;
+pub mod text;
+pub mod filter;
+pub mod pixels;
+pub mod reader;
+pub mod writer;
+pub mod encoder;
+pub mod apng;
+pub mod atomic;
+pub mod ancillary;
+pub mod palette;
\ No newline at end of file
@@ -0,0 +1,289 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use crate::png::chunk::Chunk;
+use crate::png::chunk_type::ChunkType;
+use crate::png::ihdr::{ColorType, Ihdr};
+
+// One palette entry: its PLTE color and its tRNS alpha (255 for entries past the end of tRNS)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaletteEntry {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+// PLTE and tRNS together, as one list of RGBA entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    pub entries: Vec<PaletteEntry>,
+}
+
+// A color given on the command line, e.g. #ff8800 or #ff880080; without an alpha it matches any
+// alpha and, as a replacement, keeps the alpha of the entry it replaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: Option<u8>,
+}
+
+#[derive(Debug)]
+pub enum PaletteError {
+    InvalidLength(usize),
+    TooManyEntries { entries: usize, max: usize },
+    TransparencyTooLong { alpha_entries: usize, entries: usize },
+    NotAllowed(ColorType),
+    TransparencyNotAllowed(ColorType),
+    IndexOutOfRange { index: usize, entries: usize },
+    InvalidColor(String),
+    InvalidSelector(String),
+    InvalidMapping(String),
+}
+
+/**
+ * Provides methods for working with the palette of an image.
+ *
+ * PLTE holds 1 to 256 RGB triples. In indexed images tRNS holds one alpha value per entry, and may
+ * stop early, in which case the remaining entries are opaque. Other color types may carry a PLTE as
+ * a suggested palette for viewers with fewer colors; their tRNS holds a single transparent color
+ * instead, so it is not part of the palette.
+ *
+ * @returns from_data - Builds a palette from the PLTE data and, for indexed images, the tRNS data, checked against the header.
+ * @returns check - Returns an error if the palette cannot be stored in an image with this header.
+ * @returns plte_data - Returns the PLTE data.
+ * @returns trns_data - Returns the tRNS data, up to the last entry that is not opaque, or `None` if every entry is opaque.
+ * @returns to_plte - Returns the PLTE chunk.
+ * @returns to_trns - Returns the tRNS chunk, or `None` if every entry is opaque.
+ * @returns recolor - Gives each entry the color of the first mapping that picks it, matching against the colors before the call, and returns how many changed.
+ * @returns to_gpl - Renders the palette as a GIMP palette file (GPL); alpha is noted in the entry names since GPL has none.
+ */
+impl Palette {
+    pub fn from_data(plte: &[u8], trns: Option<&[u8]>, ihdr: &Ihdr) -> Result<Palette, PaletteError> {
+        if plte.is_empty() || !plte.len().is_multiple_of(3) {
+            return Err(PaletteError::InvalidLength(plte.len()));
+        }
+
+        let trns = if ihdr.color_type == ColorType::Indexed { trns.unwrap_or(&[]) } else { &[] };
+        let entries = plte.len() / 3;
+        if trns.len() > entries {
+            return Err(PaletteError::TransparencyTooLong { alpha_entries: trns.len(), entries });
+        }
+
+        let palette = Palette {
+            entries: plte
+                .chunks_exact(3)
+                .enumerate()
+                .map(|(idx, rgb)| PaletteEntry { red: rgb[0], green: rgb[1], blue: rgb[2], alpha: trns.get(idx).copied().unwrap_or(255) })
+                .collect(),
+        };
+        palette.check(ihdr)?;
+        Ok(palette)
+    }
+
+    // An indexed image can only address 2^bit_depth entries
+    pub fn check(&self, ihdr: &Ihdr) -> Result<(), PaletteError> {
+        let max = match ihdr.color_type {
+            ColorType::Indexed => 1 << ihdr.bit_depth.min(8),
+            ColorType::Rgb | ColorType::Rgba => 256,
+            color_type => return Err(PaletteError::NotAllowed(color_type)),
+        };
+
+        if self.entries.is_empty() {
+            return Err(PaletteError::InvalidLength(0));
+        }
+        if self.entries.len() > max {
+            return Err(PaletteError::TooManyEntries { entries: self.entries.len(), max });
+        }
+        if ihdr.color_type != ColorType::Indexed && self.entries.iter().any(|entry| entry.alpha != 255) {
+            return Err(PaletteError::TransparencyNotAllowed(ihdr.color_type));
+        }
+        Ok(())
+    }
+
+    pub fn plte_data(&self) -> Vec<u8> {
+        self.entries.iter().flat_map(|entry| [entry.red, entry.green, entry.blue]).collect()
+    }
+
+    pub fn trns_data(&self) -> Option<Vec<u8>> {
+        let len = self.entries.iter().rposition(|entry| entry.alpha != 255)? + 1;
+        Some(self.entries[..len].iter().map(|entry| entry.alpha).collect())
+    }
+
+    pub fn to_plte(&self) -> Chunk {
+        Chunk::new(ChunkType::from_str("PLTE").expect("PLTE is a valid chunk type"), self.plte_data())
+    }
+
+    pub fn to_trns(&self) -> Option<Chunk> {
+        let data = self.trns_data()?;
+        Some(Chunk::new(ChunkType::from_str("tRNS").expect("tRNS is a valid chunk type"), data))
+    }
+
+    pub fn recolor(&mut self, mappings: &[PaletteMapping]) -> Result<usize, PaletteError> {
+        let entries = self.entries.len();
+        for mapping in mappings {
+            if let PaletteSelector::Index(index) = mapping.from
+                && index >= entries
+            {
+                return Err(PaletteError::IndexOutOfRange { index, entries });
+            }
+        }
+
+        let original = self.entries.clone();
+        let mut changed = 0;
+        for (index, (entry, old)) in self.entries.iter_mut().zip(original).enumerate() {
+            let Some(mapping) = mappings.iter().find(|mapping| mapping.from.matches(index, &old)) else {
+                continue;
+            };
+
+            *entry = mapping.to.apply(&old);
+            if *entry != old {
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+
+    pub fn to_gpl(&self, name: &str) -> String {
+        let mut gpl = format!("GIMP Palette\nName: {}\nColumns: 16\n#\n", name);
+        for (index, entry) in self.entries.iter().enumerate() {
+            let alpha = if entry.alpha == 255 { String::new() } else { format!(" (alpha {})", entry.alpha) };
+            gpl.push_str(&format!("{:3} {:3} {:3}\tIndex {}{}\n", entry.red, entry.green, entry.blue, index, alpha));
+        }
+        gpl
+    }
+}
+
+// Which palette entries a recolor applies to: one index, or every entry of a color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteSelector {
+    Index(usize),
+    Color(Color),
+}
+
+// FROM=TO: the entries FROM picks get the color TO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaletteMapping {
+    pub from: PaletteSelector,
+    pub to: Color,
+}
+
+/**
+ * Provides methods for picking palette entries.
+ *
+ * @returns matches - Returns whether the entry at this index is picked.
+ */
+impl PaletteSelector {
+    pub fn matches(&self, index: usize, entry: &PaletteEntry) -> bool {
+        match self {
+            PaletteSelector::Index(selected) => *selected == index,
+            PaletteSelector::Color(color) => color.matches(entry),
+        }
+    }
+}
+
+/**
+ * Provides methods for matching and replacing palette entries by color.
+ *
+ * @returns matches - Returns whether the entry has this color, and this alpha if one was given.
+ * @returns apply - Returns the entry with this color, keeping its alpha unless one was given.
+ */
+impl Color {
+    pub fn matches(&self, entry: &PaletteEntry) -> bool {
+        (self.red, self.green, self.blue) == (entry.red, entry.green, entry.blue)
+            && self.alpha.is_none_or(|alpha| alpha == entry.alpha)
+    }
+
+    pub fn apply(&self, entry: &PaletteEntry) -> PaletteEntry {
+        PaletteEntry { red: self.red, green: self.green, blue: self.blue, alpha: self.alpha.unwrap_or(entry.alpha) }
+    }
+}
+
+// #rrggbb or #rrggbbaa; the # is required, so a color like 000000 is never taken for palette index 0
+impl FromStr for Color {
+    type Err = PaletteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').ok_or_else(|| PaletteError::InvalidColor(s.to_string()))?;
+        // from_str_radix alone would also take a sign, as in +f
+        if !matches!(hex.len(), 6 | 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(PaletteError::InvalidColor(s.to_string()));
+        }
+
+        let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| PaletteError::InvalidColor(s.to_string()));
+        Ok(Color {
+            red: byte(0)?,
+            green: byte(2)?,
+            blue: byte(4)?,
+            alpha: if hex.len() == 8 { Some(byte(6)?) } else { None },
+        })
+    }
+}
+
+// A #color to replace wherever it appears, or a palette index. Indices are written plainly and have at
+// most three digits (a palette has at most 256 entries), so a color missing its #, like 000000 or 112233,
+// is an error instead of an index
+impl FromStr for PaletteSelector {
+    type Err = PaletteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with('#') {
+            return s.parse().map(PaletteSelector::Color);
+        }
+
+        match s.parse::<usize>() {
+            Ok(index) if s.len() <= 3 && index.to_string() == s => Ok(PaletteSelector::Index(index)),
+            _ => Err(PaletteError::InvalidSelector(s.to_string())),
+        }
+    }
+}
+
+// e.g. 3=#ff0000 or #ff0000=#00ff0080
+impl FromStr for PaletteMapping {
+    type Err = PaletteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s.split_once('=').ok_or_else(|| PaletteError::InvalidMapping(s.to_string()))?;
+        Ok(PaletteMapping { from: from.parse()?, to: to.parse()? })
+    }
+}
+
+// #rrggbb, with the alpha appended for entries that are not opaque
+impl Display for PaletteEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)?;
+        if self.alpha != 255 {
+            write!(f, "{:02x}", self.alpha)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteError::InvalidLength(length) => write!(f, "PLTE chunk has invalid length {}", length),
+            PaletteError::TooManyEntries { entries, max } => {
+                write!(f, "Palette has {} entries, but the image can use at most {}", entries, max)
+            }
+            PaletteError::TransparencyTooLong { alpha_entries, entries } => {
+                write!(f, "tRNS has {} alpha values for a palette of {} entries", alpha_entries, entries)
+            }
+            PaletteError::NotAllowed(color_type) => write!(f, "{} images cannot have a palette", color_type),
+            PaletteError::TransparencyNotAllowed(color_type) => {
+                write!(f, "The palette of {} images cannot have transparent entries", color_type)
+            }
+            PaletteError::IndexOutOfRange { index, entries } => {
+                write!(f, "Palette index {} is out of range for a palette of {} entries", index, entries)
+            }
+            PaletteError::InvalidColor(color) => write!(f, "Invalid color '{}', expected #rrggbb or #rrggbbaa", color),
+            PaletteError::InvalidSelector(selector) => {
+                write!(f, "Invalid palette entry '{}', expected an index or #rrggbb or #rrggbbaa", selector)
+            }
+            PaletteError::InvalidMapping(mapping) => write!(f, "Invalid mapping '{}', expected FROM=TO", mapping),
+        }
+    }
+}
+
+impl std::error::Error for PaletteError {}
@@ -0,0 +1,322 @@
+use std::fmt;
+use std::io::Read;
+use flate2::read::ZlibDecoder;
+use crate::png::filter::FilterType;
+use crate::png::ihdr::{ColorType, Ihdr, IhdrError};
+
+// Samples are stored one per element, channels interleaved, rows top to bottom.
+// Bit depths below 8 are unpacked to one `u8` per sample; 16-bit samples use `u16`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Samples {
+    Eight(Vec<u8>),
+    Sixteen(Vec<u16>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PixelBuffer {
+    width: u32,
+    height: u32,
+    color_type: ColorType,
+    bit_depth: u8,
+    samples: Samples,
+}
+
+#[derive(Debug)]
+pub enum PixelError {
+    NoImageData,
+    Decompress(std::io::Error),
+    Compress(std::io::Error),
+    InvalidCompressionLevel(u32),
+    TruncatedImageData { expected: usize, actual: usize },
+    InvalidFilterType(u8),
+    ImageTooLarge,
+    InvalidBitDepth(u8),
+    InvalidHeader(IhdrError),
+    SampleCountMismatch { expected: usize, actual: usize },
+}
+
+// (x start, y start, x step, y step) for each of the seven Adam7 passes
+pub const ADAM7_PASSES: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+const NO_INTERLACE: [(usize, usize, usize, usize); 1] = [(0, 0, 1, 1)];
+
+/**
+ * Provides methods for working with decoded PNG pixels.
+ *
+ * @returns new - Builds a buffer from samples, checking the sample count and container match the bit depth.
+ * @returns decode - Inflates the concatenated IDAT data, undoes scanline filters and Adam7 interlacing.
+ * @returns channels - Returns the number of samples per pixel (1 for indexed images, which hold palette indices).
+ * @returns sample - Returns one sample widened to `u16`.
+ * @returns set_sample - Overwrites one sample; values are truncated to the buffer's bit depth.
+ * @returns pixel - Returns every sample of one pixel widened to `u16`.
+ */
+impl PixelBuffer {
+    pub fn new(width: u32, height: u32, color_type: ColorType, bit_depth: u8, samples: Samples) -> Result<PixelBuffer, PixelError> {
+        if !color_type.allowed_bit_depths().contains(&bit_depth) {
+            return Err(PixelError::InvalidBitDepth(bit_depth));
+        }
+
+        let expected = sample_count(width, height, color_type)?;
+        let actual = match (&samples, bit_depth) {
+            (Samples::Eight(s), 1..=8) => s.len(),
+            (Samples::Sixteen(s), 16) => s.len(),
+            _ => return Err(PixelError::InvalidBitDepth(bit_depth)),
+        };
+
+        if expected != actual {
+            return Err(PixelError::SampleCountMismatch { expected, actual });
+        }
+
+        Ok(PixelBuffer { width, height, color_type, bit_depth, samples })
+    }
+
+    pub fn decode(ihdr: &Ihdr, image_data: &[u8]) -> Result<PixelBuffer, PixelError> {
+        if image_data.is_empty() {
+            return Err(PixelError::NoImageData);
+        }
+
+        let passes = pass_sizes(ihdr);
+        let expected = passes
+            .iter()
+            .filter(|(w, h)| *w > 0 && *h > 0)
+            .try_fold(0usize, |acc, (w, h)| {
+                let stride = 1 + row_bytes(*w, ihdr.color_type, ihdr.bit_depth)?;
+                acc.checked_add(stride.checked_mul(*h)?)
+            })
+            .ok_or(PixelError::ImageTooLarge)?;
+
+        let mut raw = Vec::new();
+        ZlibDecoder::new(image_data)
+            .take(expected as u64)
+            .read_to_end(&mut raw)
+            .map_err(PixelError::Decompress)?;
+
+        if raw.len() != expected {
+            return Err(PixelError::TruncatedImageData { expected, actual: raw.len() });
+        }
+
+        let channels = ihdr.color_type.channels();
+        let total = sample_count(ihdr.width, ihdr.height, ihdr.color_type)?;
+        let mut samples = if ihdr.bit_depth == 16 {
+            Samples::Sixteen(vec![0; total])
+        } else {
+            Samples::Eight(vec![0; total])
+        };
+
+        let bpp = bytes_per_pixel(ihdr.color_type, ihdr.bit_depth);
+        let layout = pass_layout(ihdr.is_interlaced());
+        let mut cursor = 0;
+
+        for (&(x0, y0, dx, dy), &(pass_width, pass_height)) in layout.iter().zip(passes.iter()) {
+            if pass_width == 0 || pass_height == 0 {
+                continue;
+            }
+
+            let stride = row_bytes(pass_width, ihdr.color_type, ihdr.bit_depth).ok_or(PixelError::ImageTooLarge)?;
+            let mut prev = vec![0u8; stride];
+
+            for row_idx in 0..pass_height {
+                let filter = FilterType::try_from(raw[cursor]).map_err(PixelError::InvalidFilterType)?;
+                let row = &mut raw[cursor + 1..cursor + 1 + stride];
+                filter.unfilter(row, &prev, bpp);
+                prev.copy_from_slice(row);
+                cursor += 1 + stride;
+
+                let y = y0 + row_idx * dy;
+                for col in 0..pass_width {
+                    let x = x0 + col * dx;
+                    let dst = (y * ihdr.width as usize + x) * channels;
+                    for c in 0..channels {
+                        let idx = col * channels + c;
+                        match &mut samples {
+                            Samples::Eight(s) => s[dst + c] = unpack_sample(&prev, idx, ihdr.bit_depth),
+                            Samples::Sixteen(s) => s[dst + c] = u16::from_be_bytes([prev[idx * 2], prev[idx * 2 + 1]]),
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(PixelBuffer {
+            width: ihdr.width,
+            height: ihdr.height,
+            color_type: ihdr.color_type,
+            bit_depth: ihdr.bit_depth,
+            samples,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn color_type(&self) -> ColorType {
+        self.color_type
+    }
+
+    pub fn bit_depth(&self) -> u8 {
+        self.bit_depth
+    }
+
+    pub fn channels(&self) -> usize {
+        self.color_type.channels()
+    }
+
+    pub fn samples(&self) -> &Samples {
+        &self.samples
+    }
+
+    pub fn samples_mut(&mut self) -> &mut Samples {
+        &mut self.samples
+    }
+
+    pub fn sample(&self, x: u32, y: u32, channel: usize) -> u16 {
+        let idx = self.index(x, y, channel);
+        match &self.samples {
+            Samples::Eight(s) => s[idx] as u16,
+            Samples::Sixteen(s) => s[idx],
+        }
+    }
+
+    pub fn set_sample(&mut self, x: u32, y: u32, channel: usize, value: u16) {
+        let idx = self.index(x, y, channel);
+        let mask = max_sample_value(self.bit_depth);
+        match &mut self.samples {
+            Samples::Eight(s) => s[idx] = (value & mask) as u8,
+            Samples::Sixteen(s) => s[idx] = value,
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Vec<u16> {
+        (0..self.channels()).map(|c| self.sample(x, y, c)).collect()
+    }
+
+    fn index(&self, x: u32, y: u32, channel: usize) -> usize {
+        (y as usize * self.width as usize + x as usize) * self.channels() + channel
+    }
+}
+
+impl Samples {
+    pub fn len(&self) -> usize {
+        match self {
+            Samples::Eight(s) => s.len(),
+            Samples::Sixteen(s) => s.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+pub fn max_sample_value(bit_depth: u8) -> u16 {
+    ((1u32 << bit_depth) - 1) as u16
+}
+
+// Filters operate on whole bytes, so sub-byte pixels count as one byte
+pub fn bytes_per_pixel(color_type: ColorType, bit_depth: u8) -> usize {
+    (color_type.channels() * bit_depth as usize).div_ceil(8)
+}
+
+// Number of data bytes in one scanline of `width` pixels, not counting the filter type byte
+pub fn row_bytes(width: usize, color_type: ColorType, bit_depth: u8) -> Option<usize> {
+    width
+        .checked_mul(color_type.channels() * bit_depth as usize)
+        .map(|bits| bits.div_ceil(8))
+}
+
+pub fn pass_layout(interlaced: bool) -> &'static [(usize, usize, usize, usize)] {
+    if interlaced { &ADAM7_PASSES } else { &NO_INTERLACE }
+}
+
+// Width and height of each pass; a single pass covering the image when not interlaced
+pub fn pass_sizes(ihdr: &Ihdr) -> Vec<(usize, usize)> {
+    let (width, height) = (ihdr.width as usize, ihdr.height as usize);
+
+    pass_layout(ihdr.is_interlaced())
+        .iter()
+        .map(|&(x0, y0, dx, dy)| {
+            let w = if width > x0 { (width - x0).div_ceil(dx) } else { 0 };
+            let h = if height > y0 { (height - y0).div_ceil(dy) } else { 0 };
+            (w, h)
+        })
+        .collect()
+}
+
+fn sample_count(width: u32, height: u32, color_type: ColorType) -> Result<usize, PixelError> {
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(color_type.channels()))
+        .ok_or(PixelError::ImageTooLarge)
+}
+
+// Packs one scanline worth of samples into bytes, big-endian and most significant bits first
+pub fn pack_samples(samples: &[u16], bit_depth: u8, out: &mut Vec<u8>) {
+    match bit_depth {
+        16 => out.extend(samples.iter().flat_map(|s| s.to_be_bytes())),
+        8 => out.extend(samples.iter().map(|&s| s as u8)),
+        _ => {
+            let depth = bit_depth as usize;
+            let mask = max_sample_value(bit_depth) as u8;
+            let start = out.len();
+            out.resize(start + (samples.len() * depth).div_ceil(8), 0);
+            for (i, &s) in samples.iter().enumerate() {
+                let bit = i * depth;
+                out[start + bit / 8] |= (s as u8 & mask) << (8 - depth - bit % 8);
+            }
+        }
+    }
+}
+
+fn unpack_sample(row: &[u8], idx: usize, bit_depth: u8) -> u8 {
+    if bit_depth == 8 {
+        return row[idx];
+    }
+
+    let bit = idx * bit_depth as usize;
+    let shift = 8 - bit_depth as usize - bit % 8;
+    (row[bit / 8] >> shift) & max_sample_value(bit_depth) as u8
+}
+
+impl fmt::Display for PixelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PixelError::NoImageData => write!(f, "Image has no IDAT data"),
+            PixelError::Decompress(_) => write!(f, "Failed to decompress image data"),
+            PixelError::Compress(_) => write!(f, "Failed to compress image data"),
+            PixelError::InvalidCompressionLevel(level) => write!(f, "Invalid compression level {}, expected 0 to 9", level),
+            PixelError::TruncatedImageData { expected, actual } => {
+                write!(f, "Image data is truncated: expected {} bytes, found {}", expected, actual)
+            }
+            PixelError::InvalidFilterType(filter) => write!(f, "Invalid scanline filter type {}", filter),
+            PixelError::ImageTooLarge => write!(f, "Image is too large to decode"),
+            PixelError::InvalidBitDepth(bit_depth) => write!(f, "Invalid bit depth {}", bit_depth),
+            PixelError::InvalidHeader(_) => write!(f, "Invalid image header"),
+            PixelError::SampleCountMismatch { expected, actual } => {
+                write!(f, "Expected {} samples, found {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PixelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PixelError::Decompress(e) | PixelError::Compress(e) => Some(e),
+            PixelError::InvalidHeader(e) => Some(e),
+            _ => None,
+        }
+    }
+}
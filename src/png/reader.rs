@@ -0,0 +1,231 @@
+use std::io::{self, Read, Seek, SeekFrom};
+use crc::{Crc, CRC_32_ISO_HDLC};
+use crate::png::chunk::{Chunk, ChunkError};
+use crate::png::chunk_type::ChunkType;
+use crate::png::image::{CorruptedChunk, Png, PngError};
+
+// Length and type of a chunk whose body has not been read yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkHeader {
+    pub index: usize,
+    pub offset: u64,
+    pub length: u32,
+    pub chunk_type: String,
+}
+
+pub struct PngReader<R> {
+    inner: R,
+    offset: u64,
+    index: usize,
+    pending: Option<ChunkHeader>,
+    lenient: bool,
+    failed: bool,
+    corrupted_chunks: Vec<CorruptedChunk>,
+}
+
+/**
+ * Reads a PNG one chunk at a time from any `Read`, holding at most one chunk body in memory.
+ *
+ * `next_header` reads a chunk's length and type; the body is then read with `read_body`, streamed
+ * through the CRC and dropped with `skip_body`, or jumped over with `seek_body` when the reader
+ * supports seeking. `seek_body` checks no CRC, so it is only for callers that explicitly opt out of
+ * verification. Asking for the next header skips any body left unread, verifying it.
+ *
+ * @returns new - Checks the PNG signature; chunks with a bad CRC are rejected.
+ * @returns new_lenient - Same as `new` but records chunks with a bad CRC instead of rejecting them.
+ * @returns next_header - Returns the header of the next chunk, or `None` at the end of the stream.
+ * @returns read_body - Reads the pending chunk's data and CRC into a `Chunk`.
+ * @returns skip_body - Discards the pending chunk's data, still verifying its CRC.
+ * @returns seek_body - Seeks past the pending chunk's data and CRC without reading or verifying them, failing if the input ends first.
+ * @returns next_chunk - Reads the next complete chunk, or `None` at the end of the stream.
+ * @returns offset - Returns the number of bytes consumed so far, which is the file size once all chunks are read.
+ * @returns corrupted_chunks - Returns the chunks that failed CRC verification (only populated in lenient mode).
+ */
+impl<R: Read> PngReader<R> {
+    pub fn new(inner: R) -> Result<PngReader<R>, PngError> {
+        PngReader::open(inner, false)
+    }
+
+    pub fn new_lenient(inner: R) -> Result<PngReader<R>, PngError> {
+        PngReader::open(inner, true)
+    }
+
+    pub fn next_header(&mut self) -> Result<Option<ChunkHeader>, PngError> {
+        if self.pending.is_some() {
+            self.skip_body()?;
+        }
+
+        let mut prefix = [0u8; 8];
+        let filled = read_full(&mut self.inner, &mut prefix)?;
+        if filled == 0 {
+            return Ok(None);
+        }
+        if filled < prefix.len() {
+            return Err(PngError::UnexpectedEOF { offset: self.offset + filled as u64 });
+        }
+
+        let type_bytes = [prefix[4], prefix[5], prefix[6], prefix[7]];
+        let chunk_type = ChunkType::try_from(type_bytes).map_err(|_| ChunkError::InvalidChunkType(type_bytes))?;
+        let header = ChunkHeader {
+            index: self.index,
+            offset: self.offset,
+            length: u32::from_be_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]),
+            chunk_type: chunk_type.to_string(),
+        };
+
+        self.offset += prefix.len() as u64;
+        self.pending = Some(header.clone());
+        Ok(Some(header))
+    }
+
+    pub fn read_body(&mut self) -> Result<Chunk, PngError> {
+        let header = self.pending.take().ok_or(PngError::NoPendingChunk)?;
+        let length = header.length as usize;
+
+        // Grow the buffer as data arrives rather than trusting the length field up front
+        let mut bytes = Vec::with_capacity(8 + length.min(1 << 16) + 4);
+        bytes.extend(header.length.to_be_bytes());
+        bytes.extend(header.chunk_type.as_bytes());
+        (&mut self.inner).take(length as u64 + 4).read_to_end(&mut bytes)?;
+
+        if bytes.len() as u64 != 12 + header.length as u64 {
+            return Err(PngError::UnexpectedEOF { offset: self.offset + bytes.len() as u64 - 8 });
+        }
+
+        let chunk = Chunk::from_bytes_unchecked(&bytes)?;
+        self.finish(&header, chunk.crc(), chunk.computed_crc())?;
+        Ok(chunk)
+    }
+
+    pub fn skip_body(&mut self) -> Result<(), PngError> {
+        let header = self.pending.take().ok_or(PngError::NoPendingChunk)?;
+        let crc_algorithm = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+        let mut digest = crc_algorithm.digest();
+        digest.update(header.chunk_type.as_bytes());
+
+        let mut body = (&mut self.inner).take(header.length as u64);
+        let mut buf = [0u8; 8192];
+        let mut remaining = header.length as usize;
+        while remaining > 0 {
+            let n = body.read(&mut buf)?;
+            if n == 0 {
+                let offset = self.offset + (header.length as usize - remaining) as u64;
+                return Err(PngError::UnexpectedEOF { offset });
+            }
+            digest.update(&buf[..n]);
+            remaining -= n;
+        }
+
+        let mut crc = [0u8; 4];
+        let filled = read_full(&mut self.inner, &mut crc)?;
+        if filled < crc.len() {
+            return Err(PngError::UnexpectedEOF { offset: self.offset + header.length as u64 + filled as u64 });
+        }
+
+        self.finish(&header, u32::from_be_bytes(crc), digest.finalize())
+    }
+
+    pub fn next_chunk(&mut self) -> Result<Option<Chunk>, PngError> {
+        match self.next_header()? {
+            Some(_) => self.read_body().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn corrupted_chunks(&self) -> &[CorruptedChunk] {
+        &self.corrupted_chunks
+    }
+
+    fn open(mut inner: R, lenient: bool) -> Result<PngReader<R>, PngError> {
+        let mut signature = [0u8; 8];
+        let filled = read_full(&mut inner, &mut signature)?;
+        if signature[..filled] != Png::STANDARD_HEADER {
+            return Err(PngError::InvalidSignature(signature[..filled].to_vec()));
+        }
+
+        Ok(PngReader {
+            inner,
+            offset: signature.len() as u64,
+            index: 0,
+            pending: None,
+            lenient,
+            failed: false,
+            corrupted_chunks: Vec::new(),
+        })
+    }
+
+    // Moves past the chunk, recording or rejecting it if the stored and computed CRCs differ
+    fn finish(&mut self, header: &ChunkHeader, expected: u32, actual: u32) -> Result<(), PngError> {
+        self.offset += header.length as u64 + 4;
+        self.index += 1;
+
+        if expected != actual {
+            let corrupted = CorruptedChunk {
+                index: header.index,
+                chunk_type: header.chunk_type.clone(),
+                offset: header.offset as usize,
+                expected,
+                actual,
+            };
+
+            if !self.lenient {
+                return Err(PngError::CorruptedChunk(corrupted));
+            }
+            self.corrupted_chunks.push(corrupted);
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> PngReader<R> {
+    pub fn seek_body(&mut self) -> Result<(), PngError> {
+        let header = self.pending.take().ok_or(PngError::NoPendingChunk)?;
+        let skip = header.length as u64 + 4;
+
+        // Seeking past the end is not an error, so read the CRC's last byte to catch a truncated file
+        self.inner.seek(SeekFrom::Current(skip as i64 - 1))?;
+        if read_full(&mut self.inner, &mut [0u8; 1])? == 0 {
+            // A stream that cannot seek from the end has stopped at its end already
+            let offset = self.inner.seek(SeekFrom::End(0)).or_else(|_| self.inner.stream_position())?;
+            return Err(PngError::UnexpectedEOF { offset });
+        }
+
+        self.offset += skip;
+        self.index += 1;
+        Ok(())
+    }
+}
+
+// Yields complete chunks until the end of the stream or the first error
+impl<R: Read> Iterator for PngReader<R> {
+    type Item = Result<Chunk, PngError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let next = self.next_chunk().transpose();
+        self.failed = matches!(next, Some(Err(_)));
+        next
+    }
+}
+
+// Like `read_exact`, but returns how many bytes were read when the stream ends early
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize, io::Error> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
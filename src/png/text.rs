@@ -0,0 +1,291 @@
+use std::fmt::{self, Display};
+use std::io::{Read, Write};
+use std::str::FromStr;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use crate::png::chunk::Chunk;
+use crate::png::chunk_type::ChunkType;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextKind {
+    Text,
+    Compressed,
+    International {
+        compressed: bool,
+        language_tag: String,
+        translated_keyword: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEntry {
+    pub keyword: String,
+    pub text: String,
+    pub kind: TextKind,
+}
+
+#[derive(Debug)]
+pub enum TextError {
+    NotTextChunk(String),
+    InvalidKeyword(String),
+    MissingSeparator,
+    InvalidCompressionFlag(u8),
+    InvalidCompressionMethod(u8),
+    NotLatin1,
+    InvalidUtf8,
+    Compression(std::io::Error),
+    TooLong(usize),
+}
+
+pub const TEXT_CHUNK_TYPES: [&str; 3] = ["tEXt", "zTXt", "iTXt"];
+
+// Compressed text is not inflated past this, so a few KB of zTXt cannot expand into gigabytes
+pub const MAX_TEXT_LENGTH: usize = 16 * 1024 * 1024;
+
+/**
+ * Provides methods for working with the textual metadata chunks (tEXt, zTXt and iTXt).
+ *
+ * @returns new - Builds a tEXt entry holding Latin-1 text.
+ * @returns compressed - Builds a zTXt entry holding zlib-compressed Latin-1 text.
+ * @returns international - Builds an iTXt entry holding UTF-8 text with a language tag and translated keyword.
+ * @returns chunk_type - Returns the chunk type the entry is stored in.
+ * @returns to_chunk - Serializes the entry into a chunk, checking the keyword and text encoding.
+ * @returns is_text_chunk - Returns `true` if the chunk is one of the three textual chunk types.
+ * @returns keyword_of - Reads only the keyword of a text chunk, leaving compressed text alone.
+ */
+impl TextEntry {
+    pub fn new(keyword: &str, text: &str) -> TextEntry {
+        TextEntry {
+            keyword: keyword.to_string(),
+            text: text.to_string(),
+            kind: TextKind::Text,
+        }
+    }
+
+    pub fn compressed(keyword: &str, text: &str) -> TextEntry {
+        TextEntry {
+            keyword: keyword.to_string(),
+            text: text.to_string(),
+            kind: TextKind::Compressed,
+        }
+    }
+
+    pub fn international(keyword: &str, text: &str, language_tag: &str, translated_keyword: &str, compressed: bool) -> TextEntry {
+        TextEntry {
+            keyword: keyword.to_string(),
+            text: text.to_string(),
+            kind: TextKind::International {
+                compressed,
+                language_tag: language_tag.to_string(),
+                translated_keyword: translated_keyword.to_string(),
+            },
+        }
+    }
+
+    pub fn chunk_type(&self) -> &'static str {
+        match self.kind {
+            TextKind::Text => "tEXt",
+            TextKind::Compressed => "zTXt",
+            TextKind::International { .. } => "iTXt",
+        }
+    }
+
+    pub fn is_text_chunk(chunk: &Chunk) -> bool {
+        TEXT_CHUNK_TYPES.contains(&chunk.chunk_type().to_string().as_str())
+    }
+
+    pub fn keyword_of(chunk: &Chunk) -> Result<String, TextError> {
+        if !TextEntry::is_text_chunk(chunk) {
+            return Err(TextError::NotTextChunk(chunk.chunk_type().to_string()));
+        }
+
+        let (keyword, _) = split_null(chunk.data())?;
+        let keyword = decode_latin1(keyword);
+        validate_keyword(&keyword)?;
+        Ok(keyword)
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk, TextError> {
+        validate_keyword(&self.keyword)?;
+        let mut data = encode_latin1(&self.keyword)?;
+        data.push(0);
+
+        match &self.kind {
+            TextKind::Text => {
+                data.extend(encode_latin1(&self.text)?);
+            }
+            TextKind::Compressed => {
+                data.push(0);
+                data.extend(deflate(&encode_latin1(&self.text)?)?);
+            }
+            TextKind::International { compressed, language_tag, translated_keyword } => {
+                data.push(*compressed as u8);
+                data.push(0);
+                data.extend(language_tag.as_bytes());
+                data.push(0);
+                data.extend(translated_keyword.as_bytes());
+                data.push(0);
+                if *compressed {
+                    data.extend(deflate(self.text.as_bytes())?);
+                } else {
+                    data.extend(self.text.as_bytes());
+                }
+            }
+        }
+
+        let chunk_type = ChunkType::from_str(self.chunk_type()).expect("text chunk types are valid");
+        Ok(Chunk::new(chunk_type, data))
+    }
+}
+
+// Keywords are 1-79 Latin-1 characters without leading, trailing or consecutive spaces
+fn validate_keyword(keyword: &str) -> Result<(), TextError> {
+    let invalid = keyword.is_empty()
+        || keyword.chars().count() > 79
+        || keyword.starts_with(' ')
+        || keyword.ends_with(' ')
+        || keyword.contains("  ")
+        || keyword.chars().any(|c| c as u32 > 0xff || (c as u32) < 0x20 || (0x7f..0xa1).contains(&(c as u32)));
+
+    if invalid {
+        Err(TextError::InvalidKeyword(keyword.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+fn encode_latin1(s: &str) -> Result<Vec<u8>, TextError> {
+    s.chars()
+        .map(|c| u8::try_from(c as u32).map_err(|_| TextError::NotLatin1))
+        .collect()
+}
+
+fn decode_latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+fn deflate(bytes: &[u8]) -> Result<Vec<u8>, TextError> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes).map_err(TextError::Compression)?;
+    encoder.finish().map_err(TextError::Compression)
+}
+
+fn inflate(bytes: &[u8]) -> Result<Vec<u8>, TextError> {
+    let mut out = Vec::new();
+    ZlibDecoder::new(bytes)
+        .take(MAX_TEXT_LENGTH as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(TextError::Compression)?;
+
+    if out.len() > MAX_TEXT_LENGTH {
+        return Err(TextError::TooLong(MAX_TEXT_LENGTH));
+    }
+    Ok(out)
+}
+
+// Splits off the bytes up to the next null separator
+fn split_null(bytes: &[u8]) -> Result<(&[u8], &[u8]), TextError> {
+    let idx = bytes.iter().position(|&b| b == 0).ok_or(TextError::MissingSeparator)?;
+    Ok((&bytes[..idx], &bytes[idx + 1..]))
+}
+
+// Takes in a tEXt, zTXt or iTXt chunk and decodes its entry
+impl TryFrom<&Chunk> for TextEntry {
+    type Error = TextError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        let chunk_type = chunk.chunk_type().to_string();
+        let (keyword, rest) = split_null(chunk.data())?;
+        let keyword = decode_latin1(keyword);
+        validate_keyword(&keyword)?;
+
+        match chunk_type.as_str() {
+            "tEXt" => Ok(TextEntry {
+                keyword,
+                text: decode_latin1(rest),
+                kind: TextKind::Text,
+            }),
+            "zTXt" => {
+                let (&method, compressed) = rest.split_first().ok_or(TextError::MissingSeparator)?;
+                if method != 0 {
+                    return Err(TextError::InvalidCompressionMethod(method));
+                }
+
+                Ok(TextEntry {
+                    keyword,
+                    text: decode_latin1(&inflate(compressed)?),
+                    kind: TextKind::Compressed,
+                })
+            }
+            "iTXt" => {
+                if rest.len() < 2 {
+                    return Err(TextError::MissingSeparator);
+                }
+                let (flag, method) = (rest[0], rest[1]);
+                if flag > 1 {
+                    return Err(TextError::InvalidCompressionFlag(flag));
+                }
+                if method != 0 {
+                    return Err(TextError::InvalidCompressionMethod(method));
+                }
+
+                let (language_tag, rest) = split_null(&rest[2..])?;
+                let (translated_keyword, text) = split_null(rest)?;
+                let text = if flag == 1 { inflate(text)? } else { text.to_vec() };
+                let to_utf8 = |bytes: Vec<u8>| String::from_utf8(bytes).map_err(|_| TextError::InvalidUtf8);
+
+                Ok(TextEntry {
+                    keyword,
+                    text: to_utf8(text)?,
+                    kind: TextKind::International {
+                        compressed: flag == 1,
+                        language_tag: to_utf8(language_tag.to_vec())?,
+                        translated_keyword: to_utf8(translated_keyword.to_vec())?,
+                    },
+                })
+            }
+            _ => Err(TextError::NotTextChunk(chunk_type)),
+        }
+    }
+}
+
+impl Display for TextEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}", self.keyword, self.chunk_type())?;
+        if let TextKind::International { language_tag, translated_keyword, .. } = &self.kind {
+            if !language_tag.is_empty() {
+                write!(f, ", lang: {}", language_tag)?;
+            }
+            if !translated_keyword.is_empty() {
+                write!(f, ", translated: {}", translated_keyword)?;
+            }
+        }
+        write!(f, "]: {}", self.text)
+    }
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextError::NotTextChunk(chunk_type) => write!(f, "{} is not a text chunk", chunk_type),
+            TextError::InvalidKeyword(keyword) => write!(f, "Invalid keyword '{}'", keyword),
+            TextError::MissingSeparator => write!(f, "Text chunk is missing the null separator after its keyword"),
+            TextError::InvalidCompressionFlag(flag) => write!(f, "Invalid compression flag {}", flag),
+            TextError::InvalidCompressionMethod(method) => write!(f, "Invalid compression method {}", method),
+            TextError::NotLatin1 => write!(f, "Text cannot be stored as Latin-1"),
+            TextError::InvalidUtf8 => write!(f, "Text is not valid UTF-8"),
+            TextError::Compression(_) => write!(f, "Failed to compress or decompress text"),
+            TextError::TooLong(limit) => write!(f, "Decompressed text is longer than {} bytes", limit),
+        }
+    }
+}
+
+impl std::error::Error for TextError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TextError::Compression(e) => Some(e),
+            _ => None,
+        }
+    }
+}
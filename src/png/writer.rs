@@ -0,0 +1,120 @@
+use std::io::{self, Read, Write};
+use crc::{Crc, CRC_32_ISO_HDLC};
+use crate::png::chunk::Chunk;
+use crate::png::chunk_type::ChunkType;
+use crate::png::image::{Png, PngError};
+
+pub struct PngWriter<W: Write> {
+    inner: W,
+    checked: bool,
+    chunks_written: usize,
+    iend_written: bool,
+}
+
+/**
+ * Writes a PNG one chunk at a time to any `Write`, computing each CRC as the data goes out.
+ *
+ * A checked writer requires IHDR to be the first chunk and IEND the last one written before
+ * `finish`. An unchecked writer writes chunks in whatever order it is given, which is what
+ * re-saving a file that already breaks the rules needs.
+ *
+ * @returns new - Writes the PNG signature and returns a checked writer.
+ * @returns new_unchecked - Writes the PNG signature and returns a writer that does not check chunk order.
+ * @returns write_chunk - Writes a complete chunk.
+ * @returns write_chunk_from - Writes a chunk whose `length` bytes of data are streamed from a reader.
+ * @returns finish - Checks that IEND was written last, flushes and returns the inner writer.
+ */
+impl<W: Write> PngWriter<W> {
+    pub fn new(inner: W) -> Result<PngWriter<W>, PngError> {
+        PngWriter::open(inner, true)
+    }
+
+    pub fn new_unchecked(inner: W) -> Result<PngWriter<W>, PngError> {
+        PngWriter::open(inner, false)
+    }
+
+    pub fn write_chunk(&mut self, chunk: &Chunk) -> Result<(), PngError> {
+        self.check_order(chunk.chunk_type())?;
+        self.inner.write_all(&chunk.as_bytes())?;
+        self.advance(chunk.chunk_type());
+        Ok(())
+    }
+
+    // Fails with an `UnexpectedEof` I/O error if the reader runs out early; the output is incomplete by then
+    pub fn write_chunk_from<R: Read>(&mut self, chunk_type: &ChunkType, length: u32, data: R) -> Result<(), PngError> {
+        self.check_order(chunk_type)?;
+
+        let crc_algorithm = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+        let mut digest = crc_algorithm.digest();
+        digest.update(&chunk_type.bytes());
+
+        self.inner.write_all(&length.to_be_bytes())?;
+        self.inner.write_all(&chunk_type.bytes())?;
+
+        let mut data = data.take(length as u64);
+        let mut buf = [0u8; 8192];
+        let mut remaining = length as usize;
+        while remaining > 0 {
+            let n = match data.read(&mut buf) {
+                Ok(0) => {
+                    let message = format!("chunk data ended after {} of {} bytes", length as usize - remaining, length);
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, message).into());
+                }
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            digest.update(&buf[..n]);
+            self.inner.write_all(&buf[..n])?;
+            remaining -= n;
+        }
+
+        self.inner.write_all(&digest.finalize().to_be_bytes())?;
+        self.advance(chunk_type);
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W, PngError> {
+        if self.checked && !self.iend_written {
+            return Err(PngError::ChunkOrder("IEND must be the last chunk".to_string()));
+        }
+
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn open(mut inner: W, checked: bool) -> Result<PngWriter<W>, PngError> {
+        inner.write_all(&Png::STANDARD_HEADER)?;
+
+        Ok(PngWriter {
+            inner,
+            checked,
+            chunks_written: 0,
+            iend_written: false,
+        })
+    }
+
+    fn check_order(&self, chunk_type: &ChunkType) -> Result<(), PngError> {
+        if !self.checked {
+            return Ok(());
+        }
+
+        let is_ihdr = chunk_type.bytes() == *b"IHDR";
+        if self.chunks_written == 0 && !is_ihdr {
+            return Err(PngError::ChunkOrder("IHDR must be the first chunk".to_string()));
+        }
+        if self.chunks_written > 0 && is_ihdr {
+            return Err(PngError::ChunkOrder("IHDR must only appear once".to_string()));
+        }
+        if self.iend_written {
+            return Err(PngError::ChunkOrder(format!("{} cannot come after IEND", chunk_type)));
+        }
+
+        Ok(())
+    }
+
+    fn advance(&mut self, chunk_type: &ChunkType) {
+        self.chunks_written += 1;
+        self.iend_written = chunk_type.bytes() == *b"IEND";
+    }
+}
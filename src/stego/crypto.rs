@@ -0,0 +1,158 @@
+use std::fmt;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+#[derive(Debug)]
+pub enum CryptoError {
+    KeyDerivation,
+    Encryption,
+    AuthenticationFailed,
+    InvalidPayload,
+    UnsupportedVersion(u8),
+    ParamsTooLarge,
+}
+
+pub const VERSION: u8 = 1;
+pub const SALT_LEN: usize = 16;
+pub const NONCE_LEN: usize = 24;
+pub const KEY_LEN: usize = 32;
+
+// Upper bounds on KDF parameters, checked when sealing and when reading them back from a payload. encode
+// always uses the defaults, so a payload asking for more work than that was not written by this tool
+pub const MAX_MEMORY_KIB: u32 = 19 * 1024;
+pub const MAX_ITERATIONS: u32 = 2;
+pub const MAX_PARALLELISM: u32 = 1;
+
+// version + three u32 KDF parameters + salt + nonce
+pub const HEADER_LEN: usize = 1 + 12 + SALT_LEN + NONCE_LEN;
+
+impl Default for KdfParams {
+    // Argon2id parameters recommended by OWASP
+    fn default() -> Self {
+        KdfParams {
+            memory_kib: MAX_MEMORY_KIB,
+            iterations: MAX_ITERATIONS,
+            parallelism: MAX_PARALLELISM,
+        }
+    }
+}
+
+impl KdfParams {
+    // No more memory, passes or lanes than the defaults
+    pub fn is_within_bounds(&self) -> bool {
+        self.memory_kib <= MAX_MEMORY_KIB && self.iterations <= MAX_ITERATIONS && self.parallelism <= MAX_PARALLELISM
+    }
+}
+
+/**
+ * Passphrase-based authenticated encryption of hidden payloads.
+ *
+ * The key is derived with Argon2id and the payload sealed with XChaCha20-Poly1305. The sealed payload
+ * is laid out as `version | memory_kib | iterations | parallelism | salt | nonce | ciphertext + tag`,
+ * so everything needed to decrypt except the secret travels with the data.
+ *
+ * @returns encrypt - Seals the plaintext with a key derived from the secret using the default KDF parameters.
+ * @returns encrypt_with_params - Same as `encrypt` with explicit KDF parameters, failing with `ParamsTooLarge` past the bounds decrypt accepts.
+ * @returns decrypt - Opens a sealed payload, failing with `AuthenticationFailed` on a wrong secret or tampered data.
+ */
+pub fn encrypt(plaintext: &[u8], secret: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    encrypt_with_params(plaintext, secret, KdfParams::default())
+}
+
+pub fn encrypt_with_params(plaintext: &[u8], secret: &[u8], params: KdfParams) -> Result<Vec<u8>, CryptoError> {
+    // A payload sealed with larger parameters could never be opened again
+    if !params.is_within_bounds() {
+        return Err(CryptoError::ParamsTooLarge);
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+
+    let key = derive_key(secret, &salt, params)?;
+    let cipher = XChaCha20Poly1305::new(&key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| CryptoError::Encryption)?;
+
+    Ok([
+        &[VERSION][..],
+        &params.memory_kib.to_be_bytes(),
+        &params.iterations.to_be_bytes(),
+        &params.parallelism.to_be_bytes(),
+        &salt,
+        nonce.as_slice(),
+        &ciphertext,
+    ]
+    .concat())
+}
+
+pub fn decrypt(payload: &[u8], secret: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if payload.len() < HEADER_LEN {
+        return Err(CryptoError::InvalidPayload);
+    }
+
+    if payload[0] != VERSION {
+        return Err(CryptoError::UnsupportedVersion(payload[0]));
+    }
+
+    let read_u32 = |at: usize| u32::from_be_bytes([payload[at], payload[at + 1], payload[at + 2], payload[at + 3]]);
+    let params = KdfParams {
+        memory_kib: read_u32(1),
+        iterations: read_u32(5),
+        parallelism: read_u32(9),
+    };
+
+    if !params.is_within_bounds() {
+        return Err(CryptoError::InvalidPayload);
+    }
+
+    let salt = &payload[13..13 + SALT_LEN];
+    let nonce = XNonce::from_slice(&payload[13 + SALT_LEN..HEADER_LEN]);
+    let ciphertext = &payload[HEADER_LEN..];
+
+    let key = derive_key(secret, salt, params)?;
+    let cipher = XChaCha20Poly1305::new(&key.into());
+
+    cipher
+        .decrypt(nonce, ciphertext)
+        .map_err(|_| CryptoError::AuthenticationFailed)
+}
+
+pub(crate) fn derive_key(secret: &[u8], salt: &[u8], params: KdfParams) -> Result<[u8; KEY_LEN], CryptoError> {
+    let params = Params::new(params.memory_kib, params.iterations, params.parallelism, Some(KEY_LEN))
+        .map_err(|_| CryptoError::KeyDerivation)?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+    let mut key = [0u8; KEY_LEN];
+    argon2
+        .hash_password_into(secret, salt, &mut key)
+        .map_err(|_| CryptoError::KeyDerivation)?;
+
+    Ok(key)
+}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CryptoError::KeyDerivation => write!(f, "Failed to derive a key from the passphrase"),
+            CryptoError::Encryption => write!(f, "Failed to encrypt the message"),
+            CryptoError::AuthenticationFailed => write!(f, "Authentication failed: wrong passphrase or tampered data"),
+            CryptoError::InvalidPayload => write!(f, "Payload is not a valid encrypted message"),
+            CryptoError::UnsupportedVersion(v) => write!(f, "Unsupported encrypted payload version {}", v),
+            CryptoError::ParamsTooLarge => write!(f, "KDF parameters exceed the supported maximum"),
+        }
+    }
+}
+
+impl std::error::Error for CryptoError {}
@@ -0,0 +1,259 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::png::ihdr::ColorType;
+use crate::png::pixels::{PixelBuffer, Samples};
+use crate::stego::crypto::{self, CryptoError, KdfParams};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Gray,
+    Alpha,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LsbOptions {
+    pub bits_per_channel: u8,
+    pub channels: Vec<Channel>,
+    pub key: Option<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub enum LsbError {
+    UnsupportedColorType(ColorType),
+    InvalidBitsPerChannel(u8),
+    MissingChannel(Channel),
+    PayloadTooLarge { needed: usize, capacity: usize },
+    InvalidLength { length: usize, capacity: usize },
+    Crypto(CryptoError),
+}
+
+// The payload is prefixed with its length in bytes as a big-endian u32
+pub const LENGTH_BITS: usize = 32;
+
+// Fixed salt for deriving the pixel order, so the key alone is enough to find the payload again
+const ORDER_SALT: &[u8; 16] = b"ImgMod LSB order";
+
+// Seeds the pixel order when no key is given, so an unkeyed payload is still scattered across the image
+const DEFAULT_ORDER_SEED: [u8; 32] = *b"ImgMod LSB default pixel order!!";
+
+impl Default for LsbOptions {
+    // One bit in each color channel, visited in the default pseudo-random order
+    fn default() -> Self {
+        LsbOptions {
+            bits_per_channel: 1,
+            channels: Vec::new(),
+            key: None,
+        }
+    }
+}
+
+/**
+ * Hides payloads in the least significant bits of decoded pixel samples.
+ *
+ * Pixels are visited in a pseudo-random order seeded from the key, or from a fixed default seed when
+ * no key is given, so the payload never sits in the first scanlines. Within each pixel the selected channels are visited in order, and within each channel the
+ * lowest `bits_per_channel` bits. No channels selected means every color channel, leaving alpha alone.
+ * Indexed images are rejected since changing a palette index changes the color entirely.
+ *
+ * @returns capacity - Returns how many payload bytes fit, not counting the length prefix.
+ * @returns embed - Writes the length-prefixed payload into the pixels, failing if it does not fit.
+ * @returns extract - Reads a length-prefixed payload back, failing if the length does not fit the image.
+ */
+pub fn capacity(pixels: &PixelBuffer, options: &LsbOptions) -> Result<usize, LsbError> {
+    let layout = Layout::new(pixels, options)?;
+    Ok(layout.total_bits().saturating_sub(LENGTH_BITS) / 8)
+}
+
+pub fn embed(pixels: &mut PixelBuffer, payload: &[u8], options: &LsbOptions) -> Result<(), LsbError> {
+    let layout = Layout::new(pixels, options)?;
+    let capacity = layout.total_bits().saturating_sub(LENGTH_BITS) / 8;
+
+    let length = u32::try_from(payload.len()).ok().filter(|_| payload.len() <= capacity);
+    let Some(length) = length else {
+        return Err(LsbError::PayloadTooLarge { needed: payload.len(), capacity });
+    };
+
+    let bytes = [&length.to_be_bytes()[..], payload].concat();
+    let bits = bytes.iter().flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1));
+
+    let samples = pixels.samples_mut();
+    for ((idx, bit), value) in layout.positions().zip(bits) {
+        match samples {
+            Samples::Eight(s) => s[idx] = (s[idx] & !(1 << bit)) | (value << bit),
+            Samples::Sixteen(s) => s[idx] = (s[idx] & !(1 << bit)) | ((value as u16) << bit),
+        }
+    }
+
+    Ok(())
+}
+
+pub fn extract(pixels: &PixelBuffer, options: &LsbOptions) -> Result<Vec<u8>, LsbError> {
+    let layout = Layout::new(pixels, options)?;
+    let capacity = layout.total_bits().saturating_sub(LENGTH_BITS) / 8;
+
+    let mut bits = layout.positions().map(|(idx, bit)| {
+        let sample = match pixels.samples() {
+            Samples::Eight(s) => s[idx] as u16,
+            Samples::Sixteen(s) => s[idx],
+        };
+        ((sample >> bit) & 1) as u8
+    });
+    let mut next_byte = || (0..8).fold(0u8, |acc, _| (acc << 1) | bits.next().unwrap_or(0));
+
+    if layout.total_bits() < LENGTH_BITS {
+        return Err(LsbError::InvalidLength { length: 0, capacity });
+    }
+
+    let length = u32::from_be_bytes([next_byte(), next_byte(), next_byte(), next_byte()]) as usize;
+    if length > capacity {
+        return Err(LsbError::InvalidLength { length, capacity });
+    }
+
+    Ok((0..length).map(|_| next_byte()).collect())
+}
+
+// The sample indices and bit positions available for embedding, in the order they are used
+struct Layout {
+    order: Vec<usize>,
+    channels: Vec<usize>,
+    samples_per_pixel: usize,
+    bits_per_channel: usize,
+}
+
+impl Layout {
+    fn new(pixels: &PixelBuffer, options: &LsbOptions) -> Result<Layout, LsbError> {
+        let color_type = pixels.color_type();
+        if color_type == ColorType::Indexed {
+            return Err(LsbError::UnsupportedColorType(color_type));
+        }
+
+        let bits = options.bits_per_channel;
+        if bits == 0 || bits > pixels.bit_depth() {
+            return Err(LsbError::InvalidBitsPerChannel(bits));
+        }
+
+        let mut channels = Vec::new();
+        if options.channels.is_empty() {
+            let alpha = Channel::Alpha.index(color_type);
+            channels.extend((0..pixels.channels()).filter(|&c| Some(c) != alpha));
+        }
+        for &channel in &options.channels {
+            let idx = channel.index(color_type).ok_or(LsbError::MissingChannel(channel))?;
+            if !channels.contains(&idx) {
+                channels.push(idx);
+            }
+        }
+
+        let pixel_count = pixels.width() as usize * pixels.height() as usize;
+        let mut order: Vec<usize> = (0..pixel_count).collect();
+        let seed = match &options.key {
+            Some(key) => crypto::derive_key(key, ORDER_SALT, KdfParams::default()).map_err(LsbError::Crypto)?,
+            None => DEFAULT_ORDER_SEED,
+        };
+        shuffle(&mut order, seed);
+
+        Ok(Layout {
+            order,
+            channels,
+            samples_per_pixel: pixels.channels(),
+            bits_per_channel: bits as usize,
+        })
+    }
+
+    fn total_bits(&self) -> usize {
+        self.order.len() * self.channels.len() * self.bits_per_channel
+    }
+
+    fn positions(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.order.iter().flat_map(move |&pixel| {
+            self.channels.iter().flat_map(move |&channel| {
+                (0..self.bits_per_channel).map(move |bit| (pixel * self.samples_per_pixel + channel, bit))
+            })
+        })
+    }
+}
+
+// Fisher-Yates shuffle driven by xoshiro256** seeded with the derived key
+fn shuffle(order: &mut [usize], seed: [u8; 32]) {
+    let mut state = [0u64; 4];
+    for (word, bytes) in state.iter_mut().zip(seed.chunks_exact(8)) {
+        *word = u64::from_le_bytes(bytes.try_into().expect("chunks are 8 bytes"));
+    }
+
+    let mut next = || {
+        let result = state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = state[1] << 17;
+        state[2] ^= state[0];
+        state[3] ^= state[1];
+        state[1] ^= state[2];
+        state[0] ^= state[3];
+        state[2] ^= t;
+        state[3] = state[3].rotate_left(45);
+        result
+    };
+
+    for i in (1..order.len()).rev() {
+        let j = ((next() as u128 * (i as u128 + 1)) >> 64) as usize;
+        order.swap(i, j);
+    }
+}
+
+impl Channel {
+    // Position of the channel within a pixel, if the color type has it
+    pub fn index(&self, color_type: ColorType) -> Option<usize> {
+        match (self, color_type) {
+            (Channel::Red, ColorType::Rgb | ColorType::Rgba) => Some(0),
+            (Channel::Green, ColorType::Rgb | ColorType::Rgba) => Some(1),
+            (Channel::Blue, ColorType::Rgb | ColorType::Rgba) => Some(2),
+            (Channel::Alpha, ColorType::Rgba) => Some(3),
+            (Channel::Gray, ColorType::Grayscale | ColorType::GrayscaleAlpha) => Some(0),
+            (Channel::Alpha, ColorType::GrayscaleAlpha) => Some(1),
+            _ => None,
+        }
+    }
+}
+
+// Takes in a channel name and see if it is one we can embed into
+impl FromStr for Channel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "red" | "r" => Ok(Channel::Red),
+            "green" | "g" => Ok(Channel::Green),
+            "blue" | "b" => Ok(Channel::Blue),
+            "gray" | "grey" => Ok(Channel::Gray),
+            "alpha" | "a" => Ok(Channel::Alpha),
+            _ => Err(format!("invalid channel '{}', expected red, green, blue, gray or alpha", s)),
+        }
+    }
+}
+
+impl fmt::Display for LsbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LsbError::UnsupportedColorType(color_type) => write!(f, "LSB embedding does not support {} images", color_type),
+            LsbError::InvalidBitsPerChannel(bits) => write!(f, "Invalid bits per channel {}", bits),
+            LsbError::MissingChannel(channel) => write!(f, "Image has no {:?} channel", channel),
+            LsbError::PayloadTooLarge { needed, capacity } => {
+                write!(f, "Payload of {} bytes does not fit, capacity is {} bytes", needed, capacity)
+            }
+            LsbError::InvalidLength { length, capacity } => {
+                write!(f, "No hidden payload found: length {} exceeds capacity of {} bytes", length, capacity)
+            }
+            LsbError::Crypto(_) => write!(f, "Failed to derive the pixel order from the key"),
+        }
+    }
+}
+
+impl std::error::Error for LsbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LsbError::Crypto(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod crypto;
+pub mod lsb;
+pub mod split;
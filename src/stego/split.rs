@@ -0,0 +1,181 @@
+use std::fmt;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piece {
+    pub message_id: u32,
+    pub index: u32,
+    pub total: u32,
+    pub digest: [u8; DIGEST_LEN],
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum SplitError {
+    InvalidPieceSize(usize),
+    TooManyPieces(usize),
+    NoPieces,
+    InvalidHeader,
+    UnsupportedVersion(u8),
+    MixedMessages,
+    InconsistentHeaders,
+    DuplicatePiece(u32),
+    MissingPiece(u32),
+    DigestMismatch,
+}
+
+pub const VERSION: u8 = 1;
+pub const DIGEST_LEN: usize = 32;
+
+// version + message id + index + total + SHA-256 of the whole payload
+pub const HEADER_LEN: usize = 1 + 12 + DIGEST_LEN;
+
+/**
+ * Splits a payload into sequenced pieces small enough to store in separate chunks.
+ *
+ * Every piece carries `version | message_id | index | total | digest` ahead of its share of the
+ * payload, with the integers as big-endian u32 and the digest being SHA-256 of the whole payload.
+ * The message id ties the pieces of one `split` call together, so several split messages can share a
+ * chunk type.
+ *
+ * @returns split - Cuts the payload into pieces of at most `max_piece_size` bytes, header included.
+ * @returns join - Puts pieces of one message back together in index order and verifies the digest.
+ * @returns in_order - Returns `true` if the pieces are already in index order.
+ */
+pub fn split(payload: &[u8], max_piece_size: usize) -> Result<Vec<Vec<u8>>, SplitError> {
+    if max_piece_size <= HEADER_LEN || max_piece_size > i32::MAX as usize {
+        return Err(SplitError::InvalidPieceSize(max_piece_size));
+    }
+
+    let per_piece = max_piece_size - HEADER_LEN;
+    let count = payload.len().div_ceil(per_piece).max(1);
+    let total = u32::try_from(count).map_err(|_| SplitError::TooManyPieces(count))?;
+
+    let message_id = OsRng.next_u32();
+    let digest: [u8; DIGEST_LEN] = Sha256::digest(payload).into();
+
+    let mut data = payload.chunks(per_piece).collect::<Vec<_>>();
+    if data.is_empty() {
+        data.push(&[]);
+    }
+
+    Ok(data
+        .into_iter()
+        .zip(0..total)
+        .map(|(data, index)| {
+            Piece {
+                message_id,
+                index,
+                total,
+                digest,
+                data: data.to_vec(),
+            }
+            .as_bytes()
+        })
+        .collect())
+}
+
+pub fn join(pieces: &[Piece]) -> Result<Vec<u8>, SplitError> {
+    let first = pieces.first().ok_or(SplitError::NoPieces)?;
+
+    if pieces.iter().any(|p| p.message_id != first.message_id) {
+        return Err(SplitError::MixedMessages);
+    }
+
+    if pieces.iter().any(|p| p.total != first.total || p.digest != first.digest || p.index >= p.total) {
+        return Err(SplitError::InconsistentHeaders);
+    }
+
+    let mut sorted: Vec<&Piece> = pieces.iter().collect();
+    sorted.sort_by_key(|p| p.index);
+
+    let mut expected = 0;
+    for piece in &sorted {
+        if piece.index < expected {
+            return Err(SplitError::DuplicatePiece(piece.index));
+        }
+        if piece.index > expected {
+            return Err(SplitError::MissingPiece(expected));
+        }
+        expected += 1;
+    }
+
+    if expected < first.total {
+        return Err(SplitError::MissingPiece(expected));
+    }
+
+    let payload: Vec<u8> = sorted.iter().flat_map(|p| p.data.iter().copied()).collect();
+    if Sha256::digest(&payload).as_slice() != first.digest {
+        return Err(SplitError::DigestMismatch);
+    }
+
+    Ok(payload)
+}
+
+pub fn in_order(pieces: &[Piece]) -> bool {
+    pieces.windows(2).all(|w| w[0].index < w[1].index)
+}
+
+impl Piece {
+    pub fn as_bytes(&self) -> Vec<u8> {
+        [
+            &[VERSION][..],
+            &self.message_id.to_be_bytes(),
+            &self.index.to_be_bytes(),
+            &self.total.to_be_bytes(),
+            &self.digest,
+            &self.data,
+        ]
+        .concat()
+    }
+}
+
+// Takes in the data of one chunk and see if it starts with a valid sequence header
+impl TryFrom<&[u8]> for Piece {
+    type Error = SplitError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() < HEADER_LEN {
+            return Err(SplitError::InvalidHeader);
+        }
+
+        if bytes[0] != VERSION {
+            return Err(SplitError::UnsupportedVersion(bytes[0]));
+        }
+
+        let read_u32 = |at: usize| u32::from_be_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+        let mut digest = [0u8; DIGEST_LEN];
+        digest.copy_from_slice(&bytes[13..HEADER_LEN]);
+
+        Ok(Piece {
+            message_id: read_u32(1),
+            index: read_u32(5),
+            total: read_u32(9),
+            digest,
+            data: bytes[HEADER_LEN..].to_vec(),
+        })
+    }
+}
+
+impl fmt::Display for SplitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SplitError::InvalidPieceSize(size) => {
+                write!(f, "Piece size {} must be larger than the {} byte sequence header", size, HEADER_LEN)
+            }
+            SplitError::TooManyPieces(count) => write!(f, "Payload would need {} pieces", count),
+            SplitError::NoPieces => write!(f, "No pieces to reassemble"),
+            SplitError::InvalidHeader => write!(f, "Chunk does not start with a sequence header"),
+            SplitError::UnsupportedVersion(v) => write!(f, "Unsupported sequence header version {}", v),
+            SplitError::MixedMessages => write!(f, "Pieces belong to different messages"),
+            SplitError::InconsistentHeaders => write!(f, "Pieces disagree on the piece count or digest"),
+            SplitError::DuplicatePiece(index) => write!(f, "Piece {} appears more than once", index),
+            SplitError::MissingPiece(index) => write!(f, "Piece {} is missing", index),
+            SplitError::DigestMismatch => write!(f, "Reassembled payload does not match its digest"),
+        }
+    }
+}
+
+impl std::error::Error for SplitError {}
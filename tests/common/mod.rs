@@ -0,0 +1,36 @@
+// Fixtures shared by the integration tests. Every test file is its own crate and uses only some of them
+#![allow(dead_code)]
+
+use std::fs;
+use std::str::FromStr;
+use tempfile::TempDir;
+use imgmod::png::chunk::Chunk;
+use imgmod::png::chunk_type::ChunkType;
+use imgmod::png::encoder::EncoderOptions;
+use imgmod::png::ihdr::ColorType;
+use imgmod::png::image::Png;
+use imgmod::png::pixels::{PixelBuffer, Samples};
+
+pub fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+    Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+}
+
+pub fn chunk_types(png: &Png) -> Vec<String> {
+    png.chunks().iter().map(|c| c.chunk_type().to_string()).collect()
+}
+
+// A copy of test_image.png in the directory, for commands that write the file back
+pub fn testing_file(dir: &TempDir, name: &str) -> String {
+    let file_path = dir.path().join(name);
+    fs::copy("./images/test_image.png", &file_path).unwrap();
+    file_path.to_str().unwrap().to_string()
+}
+
+// A 4x1 2-bit indexed image using every entry of a 4 color palette: black, red, half transparent green, blue
+pub fn indexed_png() -> Png {
+    let pixels = PixelBuffer::new(4, 1, ColorType::Indexed, 2, Samples::Eight(vec![0, 1, 2, 3])).unwrap();
+    let mut png = Png::from_pixels(&pixels, &EncoderOptions::default()).unwrap();
+    png.insert_after("IHDR", chunk("PLTE", &[0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255])).unwrap();
+    png.insert_after("PLTE", chunk("tRNS", &[255, 255, 128])).unwrap();
+    png
+}
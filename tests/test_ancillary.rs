@@ -0,0 +1,263 @@
+mod common;
+
+/**
+ * Tests for the typed ancillary chunks (gAMA, cHRM, sRGB, pHYs, tIME, bKGD, sBIT, hIST) and the commands that set them.
+ *
+ * Tests:
+ * - `test_round_trips` - Serializes a value of every type to a chunk and parses it back.
+ * - `test_invalid_data` - Checks that bad lengths, rendering intents, units and dates are rejected.
+ * - `test_time` - Converts Unix times to dates and parses ISO timestamps.
+ * - `test_dpi` - Converts between DPI and pixels per meter.
+ * - `test_parse_values` - Parses the text form of every type, as `chunk set` takes it.
+ * - `test_display` - Checks the human readable form `info` prints.
+ * - `test_set_ancillary` - Checks where new chunks are placed and that setting a type again replaces it.
+ * - `test_set_ancillary_checks` - Checks that bKGD, sBIT and hIST values must fit the color type and palette.
+ * - `test_set_dpi_and_touch` - Runs set-dpi, touch and chunk set and reads the result back with info.
+ * - `test_info_json` - Checks the decoded fields of pHYs and tIME in the JSON output of info.
+ */
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::str::FromStr;
+    use assert_cmd::Command;
+    use tempfile::tempdir;
+    use imgmod::img_cli::error;
+    use imgmod::png::ancillary::*;
+    use imgmod::png::image::{Png, PngError};
+    use crate::common::{chunk, chunk_types, indexed_png, testing_file};
+
+    fn round_trip<T: AncillaryChunk + PartialEq + std::fmt::Debug>(value: T) {
+        let chunk = value.to_chunk();
+        assert_eq!(chunk.chunk_type().to_string(), T::CHUNK_TYPE);
+        assert_eq!(T::from_chunk(&chunk).unwrap(), value);
+    }
+
+    #[test]
+    fn test_round_trips() {
+        round_trip(Gamma { gamma: 45455 });
+        round_trip(Chromaticities {
+            white_x: 31270, white_y: 32900, red_x: 64000, red_y: 33000,
+            green_x: 30000, green_y: 60000, blue_x: 15000, blue_y: 6000,
+        });
+        round_trip(Srgb { rendering_intent: RenderingIntent::Saturation });
+        round_trip(PhysicalDimensions { pixels_per_unit_x: 2835, pixels_per_unit_y: 5670, unit: PhysicalUnit::Meter });
+        round_trip(Time::new(2024, 2, 29, 23, 59, 60).unwrap());
+        round_trip(Background::Gray(1000));
+        round_trip(Background::Rgb(1, 2, 65535));
+        round_trip(Background::PaletteIndex(7));
+        round_trip(SignificantBits::GrayAlpha(4, 1));
+        round_trip(SignificantBits::Rgba(5, 6, 5, 8));
+        round_trip(Histogram { frequencies: vec![0, 10, 65535] });
+
+        assert_eq!(Gamma { gamma: 45455 }.to_data(), [0, 0, 0xb1, 0x8f]);
+        assert_eq!(Time::new(2024, 5, 1, 12, 30, 0).unwrap().to_data(), [0x07, 0xe8, 5, 1, 12, 30, 0]);
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        assert!(matches!(
+            Gamma::from_data(&[0, 0, 1]),
+            Err(AncillaryError::InvalidLength { chunk_type: "gAMA", length: 3 })
+        ));
+        assert!(matches!(Background::from_data(&[0, 0, 0, 0]), Err(AncillaryError::InvalidLength { .. })));
+        assert!(matches!(SignificantBits::from_data(&[]), Err(AncillaryError::InvalidLength { .. })));
+        assert!(matches!(Histogram::from_data(&[0, 1, 2]), Err(AncillaryError::InvalidLength { .. })));
+        assert!(matches!(Srgb::from_data(&[4]), Err(AncillaryError::InvalidRenderingIntent(4))));
+        assert!(matches!(PhysicalDimensions::from_data(&[0, 0, 0, 1, 0, 0, 0, 1, 2]), Err(AncillaryError::InvalidUnit(2))));
+        assert!(matches!(Time::from_data(&[0x07, 0xe7, 2, 29, 0, 0, 0]), Err(AncillaryError::InvalidTime(_))));
+        assert!(matches!(Time::new(2024, 13, 1, 0, 0, 0), Err(AncillaryError::InvalidTime(_))));
+        assert!(matches!(Time::new(2024, 4, 31, 0, 0, 0), Err(AncillaryError::InvalidTime(_))));
+
+        // A chunk of another type is not parsed
+        assert!(matches!(Gamma::from_chunk(&chunk("tIME", &[0; 4])), Err(AncillaryError::UnknownChunkType(_))));
+
+        // Other chunk types have no typed form, while a broken typed chunk is an error
+        assert!(Ancillary::from_chunk(&chunk("ruSt", b"data")).is_none());
+        assert!(matches!(Ancillary::from_chunk(&chunk("pHYs", &[1])), Some(Err(_))));
+    }
+
+    #[test]
+    fn test_time() {
+        assert_eq!(Time::from_unix(0).to_string(), "1970-01-01T00:00:00Z");
+        assert_eq!(Time::from_unix(1_709_209_805).to_string(), "2024-02-29T12:30:05Z");
+        assert_eq!(Time::from_unix(4_107_542_400).to_string(), "2100-03-01T00:00:00Z");
+
+        let time = Time::from_str("2024-02-29T12:30:05Z").unwrap();
+        assert_eq!(time, Time::new(2024, 2, 29, 12, 30, 5).unwrap());
+        assert_eq!(Time::from_str("2024-02-29T12:30:05").unwrap(), time);
+        assert!(Time::from_str("2024-02-29 12:30:05").is_err());
+        assert!(Time::from_str("2024-02-29T12:30").is_err());
+        assert!(Time::from_str("2023-02-29T00:00:00Z").is_err());
+
+        let now = Time::now();
+        assert!(now.year >= 2024);
+        assert_eq!(Time::from_str(&now.to_string()).unwrap(), now);
+    }
+
+    #[test]
+    fn test_dpi() {
+        let phys = PhysicalDimensions::from_dpi(300.0, 72.0).unwrap();
+        assert_eq!((phys.pixels_per_unit_x, phys.pixels_per_unit_y, phys.unit), (11811, 2835, PhysicalUnit::Meter));
+
+        let (dpi_x, dpi_y) = phys.dpi().unwrap();
+        assert!((dpi_x - 300.0).abs() < 0.01 && (dpi_y - 72.0).abs() < 0.01);
+
+        let aspect = PhysicalDimensions { pixels_per_unit_x: 1, pixels_per_unit_y: 2, unit: PhysicalUnit::Unknown };
+        assert_eq!(aspect.dpi(), None);
+
+        assert_eq!(PhysicalDimensions::from_str("300").unwrap(), PhysicalDimensions::from_dpi(300.0, 300.0).unwrap());
+        assert_eq!(PhysicalDimensions::from_str("300x72").unwrap(), phys);
+        assert!(PhysicalDimensions::from_dpi(0.0, 300.0).is_err());
+        assert!(PhysicalDimensions::from_str("-5").is_err());
+        assert!(PhysicalDimensions::from_str("high").is_err());
+    }
+
+    #[test]
+    fn test_parse_values() {
+        assert_eq!(Ancillary::parse("gAMA", "0.45455").unwrap(), Ancillary::Gamma(Gamma { gamma: 45455 }));
+        assert_eq!(
+            Ancillary::parse("sRGB", "relative").unwrap(),
+            Ancillary::Srgb(Srgb { rendering_intent: RenderingIntent::RelativeColorimetric })
+        );
+        assert_eq!(
+            Ancillary::parse("sRGB", "3").unwrap(),
+            Ancillary::Srgb(Srgb { rendering_intent: RenderingIntent::AbsoluteColorimetric })
+        );
+        assert_eq!(Ancillary::parse("bKGD", "rgb:255,255,255").unwrap(), Ancillary::Background(Background::Rgb(255, 255, 255)));
+        assert_eq!(Ancillary::parse("bKGD", "index:3").unwrap(), Ancillary::Background(Background::PaletteIndex(3)));
+        assert_eq!(Ancillary::parse("sBIT", "5,6,5").unwrap(), Ancillary::SignificantBits(SignificantBits::Rgb(5, 6, 5)));
+        assert_eq!(Ancillary::parse("hIST", "4,0,1").unwrap(), Ancillary::Histogram(Histogram { frequencies: vec![4, 0, 1] }));
+
+        let chrm = Ancillary::parse("cHRM", "0.3127,0.329,0.64,0.33,0.3,0.6,0.15,0.06").unwrap();
+        assert!(matches!(chrm, Ancillary::Chromaticities(Chromaticities { white_x: 31270, blue_y: 6000, .. })));
+
+        assert!(Ancillary::parse("gAMA", "-1").is_err());
+        assert!(Ancillary::parse("cHRM", "0.3,0.3").is_err());
+        assert!(Ancillary::parse("sRGB", "vivid").is_err());
+        assert!(Ancillary::parse("bKGD", "index:256").is_err());
+        assert!(Ancillary::parse("bKGD", "rgb:1,2").is_err());
+        assert!(Ancillary::parse("sBIT", "1,2,3,4,5").is_err());
+        assert!(matches!(Ancillary::parse("ruSt", "1"), Err(AncillaryError::UnknownChunkType(_))));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Gamma { gamma: 45455 }.to_string(), "gamma 0.45455");
+        assert_eq!(PhysicalDimensions::from_dpi(300.0, 300.0).unwrap().to_string(), "300 DPI (11811 pixels per meter)");
+        assert_eq!(
+            PhysicalDimensions::from_dpi(300.0, 72.0).unwrap().to_string(),
+            "300 x 72 DPI (11811 x 2835 pixels per meter)"
+        );
+        assert_eq!(
+            PhysicalDimensions { pixels_per_unit_x: 1, pixels_per_unit_y: 2, unit: PhysicalUnit::Unknown }.to_string(),
+            "aspect ratio 1:2 (no physical unit)"
+        );
+        assert_eq!(Srgb { rendering_intent: RenderingIntent::Perceptual }.to_string(), "rendering intent perceptual (0)");
+        assert_eq!(Background::Rgb(255, 0, 0).to_string(), "rgb(255, 0, 0)");
+        assert_eq!(SignificantBits::Rgb(5, 6, 5).to_string(), "significant bits: red 5, green 6, blue 5");
+        assert_eq!(Histogram { frequencies: vec![3, 0, 1] }.to_string(), "3 palette entries, 2 in use");
+    }
+
+    #[test]
+    fn test_set_ancillary() {
+        let mut png = Png::from_file("./images/test_image.png").unwrap();
+        png.set_ancillary(&Ancillary::Time(Time::from_unix(0))).unwrap();
+        png.set_ancillary(&Ancillary::PhysicalDimensions(PhysicalDimensions::from_dpi(72.0, 72.0).unwrap())).unwrap();
+        png.set_ancillary(&Ancillary::Gamma(Gamma { gamma: 45455 })).unwrap();
+        assert_eq!(chunk_types(&png), ["IHDR", "pHYs", "gAMA", "IDAT", "tIME", "IEND"]);
+
+        // Setting a type again replaces the chunk in place, dropping any duplicates
+        png.insert_before("IEND", Time::from_unix(1).to_chunk()).unwrap();
+        png.set_ancillary(&Ancillary::Time(Time::from_unix(2))).unwrap();
+        assert_eq!(chunk_types(&png), ["IHDR", "pHYs", "gAMA", "IDAT", "tIME", "IEND"]);
+        assert_eq!(png.ancillary::<Time>().unwrap(), Some(Time::from_unix(2)));
+        assert_eq!(png.ancillary::<Srgb>().unwrap(), None);
+
+        let values: Vec<(usize, String)> =
+            png.ancillary_chunks().unwrap().into_iter().map(|(idx, value)| (idx, value.to_string())).collect();
+        assert_eq!(values[0], (1, "72 DPI (2835 pixels per meter)".to_string()));
+        assert_eq!(values[2], (4, "1970-01-01T00:00:02Z".to_string()));
+
+        // gAMA goes before the palette, bKGD and hIST after it
+        let mut png = indexed_png();
+        png.set_ancillary(&Ancillary::Background(Background::PaletteIndex(1))).unwrap();
+        png.set_ancillary(&Ancillary::Histogram(Histogram { frequencies: vec![1, 1, 1, 1] })).unwrap();
+        png.set_ancillary(&Ancillary::Gamma(Gamma { gamma: 100000 })).unwrap();
+        assert_eq!(chunk_types(&png), ["IHDR", "gAMA", "PLTE", "tRNS", "bKGD", "hIST", "IDAT", "IEND"]);
+    }
+
+    #[test]
+    fn test_set_ancillary_checks() {
+        let mut png = Png::from_file("./images/test_image.png").unwrap();
+        let mismatch = |result: Result<(), PngError>| matches!(result, Err(PngError::Ancillary(_)));
+
+        assert!(mismatch(png.set_ancillary(&Ancillary::Background(Background::Gray(0)))));
+        assert!(mismatch(png.set_ancillary(&Ancillary::SignificantBits(SignificantBits::Rgb(8, 8, 8)))));
+        assert!(mismatch(png.set_ancillary(&Ancillary::SignificantBits(SignificantBits::Rgba(8, 8, 8, 9)))));
+        assert!(mismatch(png.set_ancillary(&Ancillary::Histogram(Histogram { frequencies: vec![1] }))));
+        png.set_ancillary(&Ancillary::Background(Background::Rgb(0, 0, 0))).unwrap();
+        png.set_ancillary(&Ancillary::SignificantBits(SignificantBits::Rgba(8, 8, 8, 1))).unwrap();
+
+        let mut png = indexed_png();
+        assert!(mismatch(png.set_ancillary(&Ancillary::Background(Background::PaletteIndex(4)))));
+        assert!(mismatch(png.set_ancillary(&Ancillary::Histogram(Histogram { frequencies: vec![1, 2, 3] }))));
+        png.set_ancillary(&Ancillary::SignificantBits(SignificantBits::Rgb(5, 6, 5))).unwrap();
+    }
+
+    #[test]
+    fn test_set_dpi_and_touch() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = testing_file(&dir, "image.png");
+        let run = |args: &[&str]| {
+            let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+            cmd.args(args);
+            cmd.assert()
+        };
+
+        run(&["set-dpi", &file, "300"]).success();
+        run(&["touch", &file, "--time", "2024-05-01T12:30:00Z"]).success();
+        run(&["chunk", "set", &file, "gAMA", "0.45455"]).success();
+        run(&["set-dpi", &file, "150x300"]).success();
+
+        let png = Png::from_file(&file).unwrap();
+        assert_eq!(chunk_types(&png), ["IHDR", "pHYs", "gAMA", "IDAT", "tIME", "IEND"]);
+        assert_eq!(png.ancillary::<PhysicalDimensions>().unwrap(), PhysicalDimensions::from_dpi(150.0, 300.0).ok());
+
+        let output = run(&["info", &file]).success();
+        let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+        assert!(stdout.contains("  pHYs: 150 x 300 DPI (5906 x 11811 pixels per meter)\n"));
+        assert!(stdout.contains("  gAMA: gamma 0.45455\n"));
+        assert!(stdout.contains("  tIME: 2024-05-01T12:30:00Z\n"));
+
+        // Without --time, touch stores the current time
+        run(&["touch", &file]).success();
+        assert!(Png::from_file(&file).unwrap().ancillary::<Time>().unwrap().unwrap().year >= 2024);
+
+        let original = fs::read(&file).unwrap();
+        run(&["set-dpi", &file, "0"]).code(error::EXIT_USAGE);
+        run(&["touch", &file, "--time", "2024-02-30T00:00:00Z"]).code(error::EXIT_USAGE);
+        run(&["chunk", "set", &file, "ruSt", "1"]).code(error::EXIT_USAGE);
+        run(&["chunk", "set", &file, "bKGD", "gray:0"]).code(error::EXIT_INVALID_DATA);
+        assert_eq!(fs::read(&file).unwrap(), original);
+    }
+
+    #[test]
+    fn test_info_json() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = testing_file(&dir, "image.png");
+        let mut png = Png::from_file(&file).unwrap();
+        png.set_ancillary(&Ancillary::PhysicalDimensions(PhysicalDimensions::from_dpi(72.0, 72.0).unwrap())).unwrap();
+        png.set_ancillary(&Ancillary::Time(Time::from_unix(1_709_209_805))).unwrap();
+        png.write_file(&file).unwrap();
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        let output = cmd.args(["info", &file, "--format", "json"]).assert().success();
+        let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+
+        assert!(stdout.contains("\"pixels_per_unit_x\": 2835,"));
+        assert!(stdout.contains("\"unit\": \"meter\","));
+        assert!(stdout.contains("\"dpi_x\": 72.009,"));
+        assert!(stdout.contains("\"year\": 2024,"));
+        assert!(stdout.contains("\"iso\": \"2024-02-29T12:30:05Z\""));
+    }
+}
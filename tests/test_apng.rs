@@ -0,0 +1,265 @@
+/**
+ * Integration tests for animated PNGs (acTL, fcTL and fdAT).
+ *
+ * Tests:
+ * - `test_animation_control_round_trip` - Checks that acTL survives serialization and parsing.
+ * - `test_frame_control_round_trip` - Checks that fcTL survives serialization and parsing, and that unknown ops are rejected.
+ * - `test_from_frames` - Checks that assembled frames get consecutive sequence numbers and the first is the default image.
+ * - `test_from_frames_read_by_png_crate` - Checks that the `png` crate decodes an assembled APNG frame by frame.
+ * - `test_frames_from_png_crate` - Checks that frames of an APNG written by the `png` crate are listed with their settings.
+ * - `test_frame_png` - Checks that an extracted frame decodes to the pixels it was assembled from, alone or with every frame.
+ * - `test_invalid_sequence` - Ensures that out of order sequence numbers are rejected.
+ * - `test_frame_count_mismatch` - Ensures that an acTL frame count that does not match the fcTL chunks is rejected.
+ * - `test_frame_out_of_bounds` - Ensures that a frame reaching past the canvas is rejected.
+ * - `test_not_animated` - Ensures that a still image has no animation control and no frames.
+ * - `test_apng_cli` - Assembles, lists (as text and JSON) and extracts frames through the CLI.
+ */
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use assert_cmd::Command;
+    use tempfile::tempdir;
+    use imgmod::png::apng::{AnimationControl, ApngError, BlendOp, DisposeOp, FrameControl, FrameOptions};
+    use imgmod::png::chunk::Chunk;
+    use imgmod::png::chunk_type::ChunkType;
+    use imgmod::png::encoder::EncoderOptions;
+    use imgmod::png::ihdr::ColorType;
+    use imgmod::png::image::{Png, PngError};
+    use imgmod::png::pixels::{PixelBuffer, Samples};
+
+    fn frame_pixels(width: u32, height: u32, shade: u8) -> PixelBuffer {
+        let samples = (0..width * height * 3).map(|i| shade.wrapping_add(i as u8)).collect();
+        PixelBuffer::new(width, height, ColorType::Rgb, 8, Samples::Eight(samples)).unwrap()
+    }
+
+    fn frame(width: u32, height: u32, shade: u8) -> Png {
+        Png::from_pixels(&frame_pixels(width, height, shade), &EncoderOptions::default()).unwrap()
+    }
+
+    fn testing_apng() -> Png {
+        let options = FrameOptions { delay_num: 50, delay_den: 1000, ..FrameOptions::default() };
+        let offset = FrameOptions { x_offset: 2, y_offset: 1, dispose_op: DisposeOp::Background, ..options };
+        let frames = vec![(frame(6, 4, 0), options), (frame(6, 4, 100), options), (frame(3, 2, 200), offset)];
+        Png::from_frames(&frames, 2).unwrap()
+    }
+
+    // Replaces the first chunk of this type, which from_chunks callers use to corrupt an APNG
+    fn replace_first(png: &Png, chunk_type: &str, chunk: Chunk) -> Png {
+        let mut chunks: Vec<Chunk> = png.chunks().to_vec();
+        let idx = chunks.iter().position(|c| c.chunk_type().to_string() == chunk_type).unwrap();
+        chunks[idx] = chunk;
+        Png::from_chunks(chunks)
+    }
+
+    #[test]
+    fn test_animation_control_round_trip() {
+        let control = AnimationControl { num_frames: 3, num_plays: 0 };
+        let chunk = control.to_chunk();
+
+        assert_eq!(chunk.chunk_type().to_string(), "acTL");
+        assert_eq!(chunk.data(), &[0, 0, 0, 3, 0, 0, 0, 0]);
+        assert_eq!(AnimationControl::from_chunk(&chunk).unwrap(), control);
+        assert_eq!(control.to_string(), "frames: 3, plays: forever");
+
+        let short = Chunk::new(ChunkType::from_str("acTL").unwrap(), vec![0; 4]);
+        assert!(matches!(AnimationControl::from_chunk(&short), Err(ApngError::InvalidLength { length: 4, .. })));
+    }
+
+    #[test]
+    fn test_frame_control_round_trip() {
+        let control = FrameControl {
+            sequence_number: 7,
+            width: 10,
+            height: 20,
+            x_offset: 1,
+            y_offset: 2,
+            delay_num: 1,
+            delay_den: 0,
+            dispose_op: DisposeOp::Previous,
+            blend_op: BlendOp::Over,
+        };
+        let chunk = control.to_chunk();
+
+        assert_eq!(chunk.data().len(), FrameControl::LENGTH);
+        assert_eq!(FrameControl::from_chunk(&chunk).unwrap(), control);
+        assert_eq!(control.delay_seconds(), 0.01);
+
+        let mut data = chunk.data().to_vec();
+        data[24] = 3;
+        let invalid = Chunk::new(ChunkType::from_str("fcTL").unwrap(), data);
+        assert!(matches!(FrameControl::from_chunk(&invalid), Err(ApngError::InvalidDisposeOp(3))));
+    }
+
+    #[test]
+    fn test_from_frames() {
+        let png = testing_apng();
+        let types: Vec<String> = png.chunks().iter().map(|c| c.chunk_type().to_string()).collect();
+        assert_eq!(types, ["IHDR", "acTL", "fcTL", "IDAT", "fcTL", "fdAT", "fcTL", "fdAT", "IEND"]);
+
+        assert_eq!(png.animation_control().unwrap(), Some(AnimationControl { num_frames: 3, num_plays: 2 }));
+
+        let frames = png.frames().unwrap();
+        let sequence: Vec<u32> = frames.iter().map(|f| f.control.sequence_number).collect();
+        assert_eq!(sequence, [0, 1, 3]);
+        assert!(frames[0].is_default_image);
+        assert!(!frames[1].is_default_image);
+        assert_eq!((frames[2].control.x_offset, frames[2].control.y_offset), (2, 1));
+        assert_eq!((frames[2].control.width, frames[2].control.height), (3, 2));
+        assert_eq!(frames[2].control.dispose_op, DisposeOp::Background);
+        assert_eq!(frames[1].control.delay_seconds(), 0.05);
+
+        let mismatched = vec![(frame(6, 4, 0), FrameOptions::default()), (Png::from_file("./images/test_image.png").unwrap(), FrameOptions::default())];
+        assert!(matches!(Png::from_frames(&mismatched, 0), Err(PngError::Apng(ApngError::FrameMismatch(1)))));
+        assert!(matches!(Png::from_frames(&[], 0), Err(PngError::Apng(ApngError::NoFrames))));
+    }
+
+    #[test]
+    fn test_from_frames_read_by_png_crate() {
+        let bytes = testing_apng().as_bytes();
+        let mut reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+
+        let control = reader.info().animation_control.unwrap();
+        assert_eq!((control.num_frames, control.num_plays), (3, 2));
+
+        let mut buf = vec![0; reader.output_buffer_size()];
+        for (shade, (width, height)) in [(0, (6, 4)), (100, (6, 4)), (200, (3, 2))] {
+            let info = reader.next_frame(&mut buf).unwrap();
+            assert_eq!((info.width, info.height), (width, height));
+
+            let expected = frame_pixels(width, height, shade);
+            let Samples::Eight(samples) = expected.samples() else { unreachable!() };
+            assert_eq!(&buf[..info.buffer_size()], samples.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_frames_from_png_crate() {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, 4, 4);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_animated(2, 0).unwrap();
+        encoder.set_frame_delay(1, 4).unwrap();
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[10; 16]).unwrap();
+        writer.set_blend_op(png::BlendOp::Over).unwrap();
+        writer.set_frame_dimension(2, 2).unwrap();
+        writer.set_frame_position(1, 1).unwrap();
+        writer.write_image_data(&[200; 4]).unwrap();
+        writer.finish().unwrap();
+
+        let png = Png::try_from(bytes.as_slice()).unwrap();
+        let frames = png.frames().unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].control.delay_seconds(), 0.25);
+        assert_eq!(frames[1].control.blend_op, BlendOp::Over);
+        assert_eq!((frames[1].control.x_offset, frames[1].control.width), (1, 2));
+
+        let pixels = png.frame_png(1).unwrap().pixels().unwrap();
+        assert_eq!(pixels.samples(), &Samples::Eight(vec![200; 4]));
+    }
+
+    #[test]
+    fn test_frame_png() {
+        let png = testing_apng();
+
+        for (index, (shade, (width, height))) in [(0, (6, 4)), (100, (6, 4)), (200, (3, 2))].into_iter().enumerate() {
+            let extracted = png.frame_png(index).unwrap();
+            assert!(extracted.animation_control().unwrap().is_none());
+            assert_eq!(extracted.pixels().unwrap(), frame_pixels(width, height, shade));
+        }
+
+        assert!(matches!(png.frame_png(3), Err(PngError::IndexOutOfBounds(3))));
+
+        let frames = png.frame_pngs().unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[2].as_bytes(), png.frame_png(2).unwrap().as_bytes());
+    }
+
+    #[test]
+    fn test_invalid_sequence() {
+        let png = testing_apng();
+        let mut control = png.frames().unwrap()[1].control;
+        control.sequence_number = 5;
+        let second = png.chunks().iter().filter(|c| c.chunk_type().to_string() == "fcTL").nth(1).unwrap();
+        let idx = png.chunks().iter().position(|c| std::ptr::eq(c, second)).unwrap();
+
+        let mut chunks = png.chunks().to_vec();
+        chunks[idx] = control.to_chunk();
+        let png = Png::from_chunks(chunks);
+
+        assert!(matches!(png.frames(), Err(PngError::Apng(ApngError::InvalidSequence { expected: 1, found: 5 }))));
+    }
+
+    #[test]
+    fn test_frame_count_mismatch() {
+        let png = replace_first(&testing_apng(), "acTL", AnimationControl { num_frames: 4, num_plays: 0 }.to_chunk());
+
+        assert!(matches!(
+            png.frames(),
+            Err(PngError::Apng(ApngError::FrameCountMismatch { expected: 4, actual: 3 }))
+        ));
+    }
+
+    #[test]
+    fn test_frame_out_of_bounds() {
+        let options = FrameOptions { x_offset: 4, ..FrameOptions::default() };
+        let frames = vec![(frame(6, 4, 0), FrameOptions::default()), (frame(3, 2, 0), options)];
+
+        assert!(matches!(Png::from_frames(&frames, 0), Err(PngError::Apng(ApngError::FrameOutOfBounds(1)))));
+    }
+
+    #[test]
+    fn test_not_animated() {
+        let png = frame(2, 2, 0);
+
+        assert!(png.animation_control().unwrap().is_none());
+        assert!(matches!(png.frames(), Err(PngError::Apng(ApngError::NotAnimated))));
+    }
+
+    #[test]
+    fn test_apng_cli() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let frame_paths: Vec<String> = (0..2u8)
+            .map(|i| {
+                let path = dir.path().join(format!("input_{}.png", i));
+                frame(4, 4, i * 50).write_file(&path).unwrap();
+                path.to_str().unwrap().to_string()
+            })
+            .collect();
+        let animation = dir.path().join("animation.png");
+        let animation = animation.to_str().unwrap();
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["apng", "assemble", animation, &frame_paths[0], &frame_paths[1], "--delay", "250", "--dispose", "background"]);
+        cmd.assert().success();
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["apng", "list", animation]);
+        cmd.assert().success().stdout(
+"frames: 2, plays: forever
+  frame#0{ sequence: 0, size: 4x4, offset: (0, 0), delay: 250/1000 (0.250s), dispose: background, blend: source} (default image)
+  frame#1{ sequence: 1, size: 4x4, offset: (0, 0), delay: 250/1000 (0.250s), dispose: background, blend: source}
+");
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["apng", "list", animation, "--format", "json"]);
+        let assert = cmd.assert().success();
+        let json = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+        assert!(json.starts_with(&format!("{{\n  \"file\": \"{}\",\n  \"animation\": {{\n    \"num_frames\": 2,\n    \"num_plays\": 0\n  }},", animation)));
+        assert_eq!(json.matches("\"default_image\": true").count(), 1);
+        assert_eq!(json.matches("\"dispose_op\": \"background\"").count(), 2);
+
+        let output_dir = dir.path().join("frames");
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["apng", "extract", animation, output_dir.to_str().unwrap()]);
+        cmd.assert().success();
+
+        let extracted = Png::from_file(output_dir.join("frame_001.png")).unwrap();
+        assert_eq!(extracted.pixels().unwrap(), frame_pixels(4, 4, 50));
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["apng", "list", &frame_paths[0]]);
+        cmd.assert().failure();
+    }
+}
@@ -0,0 +1,155 @@
+/**
+ * Integration tests for atomic file writes and backups.
+ *
+ * Tests:
+ * - `test_write_replaces_file` - Checks that a write replaces the file and leaves no temporary file behind.
+ * - `test_failed_write_keeps_original` - Checks that a write failing halfway leaves the original file untouched.
+ * - `test_backup` - Checks that the replaced file is kept under the backup suffix, and a new file gets no backup.
+ * - `test_preserve_permissions` - Checks that the new file keeps the permissions of the one it replaces.
+ * - `test_preserve_mtime` - Checks that the modification time is only kept when asked to.
+ * - `test_symlink` - Checks that writing through a symlink replaces its target and keeps the link.
+ * - `test_backup_cli` - Runs remove and text set with `--backup` and `--backup=SUFFIX`.
+ */
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Write;
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+    use assert_cmd::Command;
+    use tempfile::tempdir;
+    use imgmod::png::atomic::{backup_path, write_atomic, WriteOptions};
+    use imgmod::png::chunk::Chunk;
+    use imgmod::png::image::{Png, PngError};
+
+    fn testing_png() -> Png {
+        let mut png = Png::from_file("./images/test_image.png").unwrap();
+        png.insert_before("IEND", Chunk::from_strings("ruSt", "Hello Matt!").unwrap()).unwrap();
+        png
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn backup(suffix: &str) -> WriteOptions {
+        WriteOptions { backup: Some(suffix.to_string()), ..WriteOptions::default() }
+    }
+
+    #[test]
+    fn test_write_replaces_file() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("image.png");
+        fs::copy("./images/test_image.png", &file_path).unwrap();
+
+        let png = testing_png();
+        png.write_file(&file_path).unwrap();
+
+        assert_eq!(fs::read(&file_path).unwrap(), png.as_bytes());
+        assert_eq!(file_names(dir.path()), ["image.png"]);
+    }
+
+    #[test]
+    fn test_failed_write_keeps_original() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("image.png");
+        fs::copy("./images/test_image.png", &file_path).unwrap();
+
+        let result = write_atomic(&file_path, &backup(".bak"), |writer| {
+            writer.write_all(b"half a file")?;
+            Err(PngError::ChunkOrder("interrupted".to_string()))
+        });
+
+        assert!(matches!(result, Err(PngError::ChunkOrder(_))));
+        assert_eq!(fs::read(&file_path).unwrap(), fs::read("./images/test_image.png").unwrap());
+        assert_eq!(file_names(dir.path()), ["image.png"]);
+    }
+
+    #[test]
+    fn test_backup() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("image.png");
+        fs::copy("./images/test_image.png", &file_path).unwrap();
+
+        testing_png().write_file_with(&file_path, &backup("~")).unwrap();
+        assert_eq!(backup_path(&file_path, "~"), dir.path().join("image.png~"));
+        assert_eq!(fs::read(dir.path().join("image.png~")).unwrap(), fs::read("./images/test_image.png").unwrap());
+
+        // An older backup is replaced by the latest original
+        Png::from_file("./images/test_image.png").unwrap().write_file_with(&file_path, &backup("~")).unwrap();
+        assert_eq!(fs::read(dir.path().join("image.png~")).unwrap(), testing_png().as_bytes());
+
+        testing_png().write_file_with(dir.path().join("new.png"), &backup("~")).unwrap();
+        assert_eq!(file_names(dir.path()), ["image.png", "image.png~", "new.png"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_preserve_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("image.png");
+        fs::copy("./images/test_image.png", &file_path).unwrap();
+        fs::set_permissions(&file_path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        testing_png().write_file(&file_path).unwrap();
+        assert_eq!(fs::metadata(&file_path).unwrap().permissions().mode() & 0o777, 0o640);
+    }
+
+    #[test]
+    fn test_preserve_mtime() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("image.png");
+        fs::copy("./images/test_image.png", &file_path).unwrap();
+
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        fs::File::options().write(true).open(&file_path).unwrap().set_modified(old).unwrap();
+
+        let options = WriteOptions { preserve_mtime: true, ..WriteOptions::default() };
+        testing_png().write_file_with(&file_path, &options).unwrap();
+        assert_eq!(fs::metadata(&file_path).unwrap().modified().unwrap(), old);
+
+        testing_png().write_file(&file_path).unwrap();
+        assert!(fs::metadata(&file_path).unwrap().modified().unwrap() > old);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let target = dir.path().join("image.png");
+        let link = dir.path().join("link.png");
+        fs::copy("./images/test_image.png", &target).unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        testing_png().write_file(&link).unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read(&target).unwrap(), testing_png().as_bytes());
+    }
+
+    #[test]
+    fn test_backup_cli() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("image.png");
+        testing_png().write_file(&file_path).unwrap();
+        let file = file_path.to_str().unwrap();
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["remove", file, "ruSt", "--backup"]);
+        cmd.assert().success();
+        assert_eq!(fs::read(dir.path().join("image.png.bak")).unwrap(), testing_png().as_bytes());
+        assert_eq!(fs::read(&file_path).unwrap(), fs::read("./images/test_image.png").unwrap());
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["text", "set", "--backup=.orig", file, "Title", "Test"]);
+        cmd.assert().success();
+        assert_eq!(fs::read(dir.path().join("image.png.orig")).unwrap(), fs::read("./images/test_image.png").unwrap());
+        assert_eq!(file_names(dir.path()), ["image.png", "image.png.bak", "image.png.orig"]);
+    }
+}
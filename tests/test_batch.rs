@@ -0,0 +1,224 @@
+/**
+ * Integration tests for running commands over several files, directories and glob patterns.
+ *
+ * Tests:
+ * - `test_resolve` - Checks how paths, directories and patterns expand, with and without `--recursive`.
+ * - `test_resolve_no_matches` - Checks that a pattern or directory without files is an error.
+ * - `test_multiple_paths` - Runs print, encode and remove on several paths and checks every file is handled.
+ * - `test_legacy_positionals` - Checks that the old file-first positional form still works with one input.
+ * - `test_glob` - Encodes a message, given or piped on stdin, into every file a glob pattern matches.
+ * - `test_continue_past_failures` - Checks that a failing file is reported and the other files still run.
+ * - `test_jobs` - Runs lint and print on several threads and checks the summary and that outputs do not interleave.
+ * - `test_single_file_only` - Checks that options naming a single output are rejected with several files.
+ */
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::path::{Path, PathBuf};
+    use assert_cmd::Command;
+    use tempfile::{tempdir, TempDir};
+    use imgmod::img_cli::args::BatchArgs;
+    use imgmod::img_cli::batch::Batch;
+    use imgmod::img_cli::error::{self, CommandError};
+
+    // a.png, b.png and sub/c.png, plus a notes.txt that is never picked up
+    fn testing_dir() -> TempDir {
+        let dir = tempdir().expect("Error creating temporary directory");
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        for name in ["a.png", "b.png", "sub/c.png"] {
+            std::fs::copy("./images/test_image.png", dir.path().join(name)).unwrap();
+        }
+        std::fs::write(dir.path().join("notes.txt"), "not an image").unwrap();
+        dir
+    }
+
+    fn options(recursive: bool) -> BatchArgs {
+        BatchArgs { recursive, jobs: NonZeroUsize::MIN }
+    }
+
+    fn path(dir: &Path, name: &str) -> String {
+        dir.join(name).to_str().unwrap().to_string()
+    }
+
+    fn stderr(cmd: &mut Command, code: i32) -> String {
+        let assert = cmd.assert().code(code);
+        String::from_utf8(assert.get_output().stderr.clone()).unwrap()
+    }
+
+    fn resolved(inputs: &[PathBuf], recursive: bool) -> Vec<PathBuf> {
+        Batch::resolve(inputs, &options(recursive)).unwrap().files().to_vec()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let dir = testing_dir();
+        let root = dir.path();
+
+        assert_eq!(resolved(&[root.join("b.png")], false), [root.join("b.png")]);
+        assert_eq!(resolved(&[root.to_path_buf()], false), [root.join("a.png"), root.join("b.png")]);
+        assert_eq!(
+            resolved(&[root.to_path_buf()], true),
+            [root.join("a.png"), root.join("b.png"), root.join("sub/c.png")]
+        );
+        assert_eq!(resolved(&[root.join("*/*.png"), root.join("a.png")], false), [root.join("sub/c.png"), root.join("a.png")]);
+    }
+
+    #[test]
+    fn test_resolve_no_matches() {
+        let dir = testing_dir();
+        let empty = dir.path().join("empty");
+        std::fs::create_dir(&empty).unwrap();
+
+        assert!(matches!(Batch::resolve(&[empty], &options(true)), Err(CommandError::NoMatches(_))));
+        assert!(matches!(
+            Batch::resolve(&[dir.path().join("*.jpg")], &options(false)),
+            Err(CommandError::NoMatches(_))
+        ));
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["lint", &path(dir.path(), "*.jpg")]);
+        cmd.assert().code(error::EXIT_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_multiple_paths() {
+        let dir = testing_dir();
+        let (a, b) = (path(dir.path(), "a.png"), path(dir.path(), "b.png"));
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["print", &a, &b]);
+        let assert = cmd.assert().success();
+        assert!(String::from_utf8(assert.get_output().stderr.clone()).unwrap().contains("2 file(s): 2 ok, 0 failed"));
+        let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+        assert_eq!(output.matches("IHDR").count(), 2);
+
+        // Every other input is an option, so any number of paths can come before or after them
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["encode", &a, &b, "-c", "ruSt", "-m", "Hello Matt!"]);
+        cmd.assert().success();
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["remove", &a, &b, "-c", "ruSt"]);
+        cmd.assert().success();
+        for file in [&a, &b] {
+            let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+            cmd.args(["decode", file, "-c", "ruSt"]);
+            cmd.assert().code(error::EXIT_NOT_FOUND);
+        }
+    }
+
+    #[test]
+    fn test_legacy_positionals() {
+        let dir = testing_dir();
+        let (a, b) = (path(dir.path(), "a.png"), path(dir.path(), "b.png"));
+
+        // A missing option takes the positionals after the first input, in their old order
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["encode", &a, "ruSt", "Hello Matt!"]);
+        cmd.assert().success();
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["decode", &a, "ruSt"]);
+        cmd.assert().success().stdout("msg: Hello Matt!\n");
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["remove", &a, "ruSt", &b]);
+        cmd.assert().code(error::EXIT_USAGE);
+
+        // An existing second path is another input, not the output file
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["strip", &a, &b]);
+        let assert = cmd.assert().success();
+        assert!(String::from_utf8(assert.get_output().stderr.clone()).unwrap().contains("2 file(s): 2 ok, 0 failed"));
+        let output = path(dir.path(), "stripped.png");
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["strip", &a, &output]);
+        cmd.assert().success();
+        assert!(Path::new(&output).exists());
+    }
+
+    #[test]
+    fn test_glob() {
+        let dir = testing_dir();
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["encode", &path(dir.path(), "*.png"), "-c", "ruSt", "-m", "Hello Matt!"]);
+        cmd.assert().success();
+
+        for name in ["a.png", "b.png"] {
+            let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+            cmd.args(["decode", &path(dir.path(), name), "-c", "ruSt"]);
+            let assert = cmd.assert().success();
+            assert!(String::from_utf8(assert.get_output().stdout.clone()).unwrap().contains("Hello Matt!"));
+        }
+
+        // Only matched by a recursive directory walk
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["decode", &path(dir.path(), "sub/c.png"), "-c", "ruSt"]);
+        cmd.assert().failure();
+
+        // stdin is read once and goes into every file
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["encode", &path(dir.path(), "*.png"), "-c", "biNr", "--input-file", "-"]).write_stdin("piped");
+        cmd.assert().success();
+        for name in ["a.png", "b.png"] {
+            let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+            cmd.args(["decode", &path(dir.path(), name), "-c", "biNr"]);
+            cmd.assert().success().stdout("msg: piped\n");
+        }
+    }
+
+    #[test]
+    fn test_continue_past_failures() {
+        let dir = testing_dir();
+        std::fs::write(dir.path().join("broken.png"), "not a png").unwrap();
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["lint", dir.path().to_str().unwrap()]);
+        let output = stderr(&mut cmd, error::EXIT_BATCH);
+        assert!(output.contains(&format!("ok: {}\n", path(dir.path(), "a.png"))));
+        assert!(output.contains(&format!("failed: {}: ", path(dir.path(), "broken.png"))));
+        assert!(output.contains(&format!("(exit code {})", error::EXIT_INVALID_DATA)));
+        assert!(output.contains("3 file(s): 2 ok, 1 failed"));
+    }
+
+    #[test]
+    fn test_jobs() {
+        let dir = testing_dir();
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["lint", "--recursive", "--jobs", "4", dir.path().to_str().unwrap()]);
+        assert!(stderr(&mut cmd, 0).contains("3 file(s): 3 ok, 0 failed"));
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["lint", "--jobs", "0", dir.path().to_str().unwrap()]);
+        cmd.assert().code(error::EXIT_USAGE);
+
+        // Each file's output is printed whole, so it shows up exactly as it does for that file alone
+        for i in 0..8 {
+            std::fs::copy("./images/test_image.png", dir.path().join(format!("copy{}.png", i))).unwrap();
+        }
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["print", "--jobs", "4", dir.path().to_str().unwrap()]);
+        let output = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+        for file in resolved(&[dir.path().to_path_buf()], false) {
+            let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+            cmd.args(["print", file.to_str().unwrap()]);
+            let alone = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+            assert!(output.contains(&alone));
+        }
+    }
+
+    #[test]
+    fn test_single_file_only() {
+        let dir = testing_dir();
+        let root = dir.path().to_str().unwrap();
+        let output = path(dir.path(), "out.png");
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["strip", root, "-o", &output]);
+        cmd.assert().code(error::EXIT_USAGE);
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["encode", root, "-c", "ruSt", "-m", "Hello", "-o", &output]);
+        cmd.assert().code(error::EXIT_USAGE);
+        assert!(!Path::new(&output).exists());
+    }
+}
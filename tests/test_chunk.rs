@@ -15,10 +15,13 @@
  * - `test_chunk_crc` - Confirms that the computed CRC for the chunk is accurate.
  * - `test_valid_chunk_from_bytes` - Tests that a valid chunk can be created from a raw byte array.
  * - `test_chunk_trait_impls` - Validates trait implementations (such as `Display`) for the `Chunk` struct.
+ * - `test_invalid_chunk_crc` - Ensures that a chunk whose stored CRC does not match its contents is rejected.
+ * - `test_unchecked_chunk_crc` - Checks that an unchecked parse keeps the stored CRC and reports it as invalid.
+ * - `test_chunk_length_past_end` - Ensures that a length field larger than the input is an error rather than a panic.
  */
 #[cfg(test)]
 mod tests {
-    use imgmod::png::chunk::Chunk;
+    use imgmod::png::chunk::{Chunk, ChunkError};
     use imgmod::png::chunk_type::ChunkType;
     use std::str::FromStr;
 
@@ -122,4 +125,57 @@ mod tests {
         
         let _chunk_string = format!("{}", chunk);
     }
+
+    fn corrupted_chunk_bytes() -> Vec<u8> {
+        let data_length: u32 = 42;
+        let chunk_type = "RuSt".as_bytes();
+        let message_bytes = "This is where your secret message will be!".as_bytes();
+        let crc: u32 = 2882656333;
+
+        data_length
+            .to_be_bytes()
+            .iter()
+            .chain(chunk_type.iter())
+            .chain(message_bytes.iter())
+            .chain(crc.to_be_bytes().iter())
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn test_invalid_chunk_crc() {
+        let chunk_data = corrupted_chunk_bytes();
+        let chunk = Chunk::try_from(chunk_data.as_ref());
+
+        match chunk {
+            Err(ChunkError::CrcMismatch { expected, actual }) => {
+                assert_eq!(expected, 2882656333);
+                assert_eq!(actual, 2882656334);
+            }
+            _ => panic!("expected a CRC mismatch"),
+        }
+    }
+
+    #[test]
+    fn test_unchecked_chunk_crc() {
+        let chunk_data = corrupted_chunk_bytes();
+        let chunk = Chunk::from_bytes_unchecked(chunk_data.as_ref()).unwrap();
+
+        assert_eq!(chunk.crc(), 2882656333);
+        assert_eq!(chunk.computed_crc(), 2882656334);
+        assert!(!chunk.is_crc_valid());
+    }
+
+    #[test]
+    fn test_chunk_length_past_end() {
+        let mut chunk_data = corrupted_chunk_bytes();
+        chunk_data[..4].copy_from_slice(&u32::MAX.to_be_bytes());
+
+        let chunk = Chunk::try_from(chunk_data.as_ref());
+        assert!(matches!(chunk, Err(ChunkError::Truncated { actual: 54, .. })));
+
+        for len in 0..12 {
+            assert!(matches!(Chunk::try_from(&chunk_data[..len]), Err(ChunkError::Truncated { .. })));
+        }
+    }
 }
@@ -0,0 +1,148 @@
+mod common;
+
+/**
+ * Integration tests for extracting chunks to files and injecting them into other images.
+ *
+ * Tests:
+ * - `test_extract_data` - Extracts the data of a chunk selected by type and by index.
+ * - `test_extract_record` - Extracts a whole chunk record with `--record`.
+ * - `test_extract_missing` - Checks the errors for a missing chunk type, an out-of-range index and an invalid chunk type.
+ * - `test_inject_data` - Injects a data file as a new chunk at a given index.
+ * - `test_inject_stdin_batch` - Checks that data piped on stdin goes into every file of a batch.
+ * - `test_transplant_record` - Moves a chunk record from one image to another.
+ * - `test_inject_bad_record` - Checks that a record with a bad CRC or trailing bytes is rejected.
+ */
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use assert_cmd::Command;
+    use tempfile::{tempdir, TempDir};
+    use imgmod::img_cli::error;
+    use imgmod::png::chunk::Chunk;
+    use imgmod::png::image::Png;
+    use crate::common::{chunk_types, testing_file};
+
+    // test_image.png with a ruSt chunk before IEND
+    fn message_file(dir: &TempDir, name: &str) -> String {
+        let file = testing_file(dir, name);
+        let mut png = Png::from_file(&file).unwrap();
+        png.insert_before("IEND", Chunk::from_strings("ruSt", "Hello Matt!").unwrap()).unwrap();
+        png.write_file(&file).unwrap();
+        file
+    }
+
+    fn run(args: &[&str]) -> Command {
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.arg("chunk").args(args);
+        cmd
+    }
+
+    #[test]
+    fn test_extract_data() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = message_file(&dir, "image.png");
+        let output = dir.path().join("data.bin");
+
+        run(&["extract", &file, "ruSt", "-o", output.to_str().unwrap()])
+            .assert()
+            .success()
+            .stdout(format!("Wrote 11 bytes of chunk#2 (ruSt) to {}\n", output.display()));
+        assert_eq!(fs::read(&output).unwrap(), b"Hello Matt!");
+
+        run(&["extract", &file, "2", "-o", "-"]).assert().success().stdout("Hello Matt!");
+    }
+
+    #[test]
+    fn test_extract_record() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = message_file(&dir, "image.png");
+        let output = dir.path().join("record.bin");
+
+        run(&["extract", &file, "ruSt", "--record", "--output-file", output.to_str().unwrap()]).assert().success();
+        assert_eq!(fs::read(&output).unwrap(), Chunk::from_strings("ruSt", "Hello Matt!").unwrap().as_bytes());
+    }
+
+    #[test]
+    fn test_extract_missing() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = message_file(&dir, "image.png");
+
+        run(&["extract", &file, "teSt", "-o", "-"]).assert().code(error::EXIT_NOT_FOUND);
+        run(&["extract", &file, "4", "-o", "-"]).assert().code(error::EXIT_NOT_FOUND);
+        run(&["extract", &file, "ab", "-o", "-"]).assert().code(error::EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_inject_data() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = message_file(&dir, "image.png");
+        let data = dir.path().join("profile.bin");
+        fs::write(&data, [0u8, 1, 2, 255]).unwrap();
+
+        run(&["inject", &file, "apPd", "--data-file", data.to_str().unwrap(), "--at", "1"])
+            .assert()
+            .success()
+            .stdout("Inserted chunk#1 (apPd, 4 bytes)\n");
+        assert_eq!(chunk_types(&Png::from_file(&file).unwrap()), ["IHDR", "apPd", "IDAT", "ruSt", "IEND"]);
+        assert_eq!(Png::from_file(&file).unwrap().chunks()[1].data(), [0, 1, 2, 255]);
+
+        // From stdin, before IEND by default
+        run(&["inject", &file, "teSt", "--data-file", "-"]).write_stdin("piped").assert().success();
+        assert_eq!(chunk_types(&Png::from_file(&file).unwrap()), ["IHDR", "apPd", "IDAT", "ruSt", "teSt", "IEND"]);
+
+        run(&["inject", &file, "teSt", "--data-file", data.to_str().unwrap(), "--at", "9"])
+            .assert()
+            .code(error::EXIT_NOT_FOUND)
+            .stdout("");
+    }
+
+    #[test]
+    fn test_inject_stdin_batch() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let first = message_file(&dir, "first.png");
+        let second = message_file(&dir, "second.png");
+
+        run(&["inject", dir.path().to_str().unwrap(), "teSt", "--data-file", "-"]).write_stdin("hello").assert().success();
+        for file in [first, second] {
+            assert_eq!(Png::from_file(&file).unwrap().chunk_by_type("teSt").unwrap().data(), b"hello");
+        }
+    }
+
+    #[test]
+    fn test_transplant_record() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let source = message_file(&dir, "source.png");
+        let target = dir.path().join("target.png");
+        fs::copy("./images/test_image.png", &target).unwrap();
+        let record = dir.path().join("record.bin");
+
+        run(&["extract", &source, "ruSt", "--record", "-o", record.to_str().unwrap()]).assert().success();
+        run(&["inject", target.to_str().unwrap(), "--record", "--data-file", record.to_str().unwrap(), "--at", "after-ihdr"])
+            .assert()
+            .success();
+
+        let target = Png::from_file(&target).unwrap();
+        assert_eq!(target.chunks()[1].as_bytes(), Png::from_file(&source).unwrap().chunks()[2].as_bytes());
+    }
+
+    #[test]
+    fn test_inject_bad_record() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = message_file(&dir, "image.png");
+        let record = dir.path().join("record.bin");
+        let original = fs::read(&file).unwrap();
+
+        let mut bytes = Chunk::from_strings("ruSt", "Hello Matt!").unwrap().as_bytes();
+        bytes.push(0);
+        fs::write(&record, &bytes).unwrap();
+        run(&["inject", &file, "--record", "--data-file", record.to_str().unwrap()]).assert().code(error::EXIT_INVALID_DATA);
+
+        bytes.pop();
+        *bytes.last_mut().unwrap() ^= 1;
+        fs::write(&record, &bytes).unwrap();
+        run(&["inject", &file, "--record", "--data-file", record.to_str().unwrap()]).assert().code(error::EXIT_INVALID_DATA);
+
+        assert_eq!(fs::read(Path::new(&file)).unwrap(), original);
+    }
+}
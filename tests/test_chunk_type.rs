@@ -1,5 +1,4 @@
 #[allow(dead_code)]
-
 /**
  * Unit tests for the `ChunkType` struct.
  *
@@ -16,7 +15,6 @@
  * - `test_is_safe_to_copy` - Checks if a chunk is correctly identified as safe to copy.
  * - `test_is_valid` - Ensures that a chunk is considered valid only if it meets all required conditions.
  */
-
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -43,12 +41,18 @@ mod tests {
     #[test]
     fn test_is_critical() {
         let chunk = ChunkType::from_str("Matt").unwrap();
+        assert!(chunk.is_critical());
+
+        let chunk = ChunkType::from_str("maTt").unwrap();
         assert!(!chunk.is_critical());
     }
 
     #[test]
     fn test_is_private() {
         let chunk = ChunkType::from_str("Matt").unwrap();
+        assert!(chunk.is_private());
+
+        let chunk = ChunkType::from_str("MATt").unwrap();
         assert!(!chunk.is_private());
     }
 
@@ -56,11 +60,17 @@ mod tests {
     fn test_is_reserved() {
         let chunk = ChunkType::from_str("Matt").unwrap();
         assert!(!chunk.is_reserved_bit_valid());
+
+        let chunk = ChunkType::from_str("maTt").unwrap();
+        assert!(chunk.is_reserved_bit_valid());
     }
 
     #[test]
     fn test_is_safe_to_copy() {
         let chunk = ChunkType::from_str("Matt").unwrap();
+        assert!(chunk.is_safe_to_copy());
+
+        let chunk = ChunkType::from_str("MatT").unwrap();
         assert!(!chunk.is_safe_to_copy());
     }
 
@@ -68,5 +78,8 @@ mod tests {
     fn test_is_valid() {
         let chunk = ChunkType::from_str("Matt").unwrap();
         assert!(!chunk.is_valid());
+
+        let chunk = ChunkType::from_str("maTt").unwrap();
+        assert!(chunk.is_valid());
     }
 }
\ No newline at end of file
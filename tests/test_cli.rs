@@ -5,6 +5,7 @@ mod tests {
     use assert_cmd::Command;
     use serial_test::serial;
     use tempfile::tempdir;
+    use imgmod::img_cli::error;
 
     struct TestCli;
 
@@ -62,6 +63,130 @@ mod tests {
         );
     }
 
+    #[test]
+    #[serial]
+    fn test_info() {
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["info", "./images/test_image.png"]);
+        cmd.assert().success().stdout(
+  "File: ./images/test_image.png, Size: 261999
+  chunk#0{ chunk_type: IHDR, data_length: 13}
+  chunk#1{ chunk_type: IDAT, data_length: 261942}
+  chunk#2{ chunk_type: IEND, data_length: 0}
+  width: 840
+  height: 654
+  bit_depth: 8
+  color_type: RGBA (6)
+  compression_method: 0
+  filter_method: 0
+  interlace_method: 0 (none)\n"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_lint() {
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["lint", "./images/test_image.png"]);
+        cmd.assert().success().stdout("./images/test_image.png: OK\n");
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["encode", "./images/test_image.png", "maTt", "Hello Matt!", "--position", "end"]);
+        cmd.assert().success();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["lint", "./images/test_image.png"]);
+        cmd.assert()
+            .failure()
+            .stdout("error: chunk#2 (IEND) IEND must be the last chunk\n");
+        TestCli::remove_chunks();
+    }
+
+    #[test]
+    fn test_text_metadata() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("text.png");
+        std::fs::copy("./images/test_image.png", &file_path).unwrap();
+        let file = file_path.to_str().unwrap();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["text", "set", file, "Title", "Test image"]);
+        cmd.assert().success();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["text", "set", file, "Comment", "Hello Matt!", "--kind", "itxt", "--language", "en"]);
+        cmd.assert().success();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["text", "list", file]);
+        cmd.assert().success().stdout("Title [tEXt]: Test image\nComment [iTXt, lang: en]: Hello Matt!\n");
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["text", "delete", file, "Title"]);
+        cmd.assert().success();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["text", "get", file, "Title"]);
+        cmd.assert().failure();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["text", "get", file, "Comment"]);
+        cmd.assert().success().stdout("Hello Matt!\n");
+    }
+
+    #[test]
+    fn test_strip() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("strip.png");
+        let output_path = dir.path().join("stripped.png");
+        std::fs::copy("./images/test_image.png", &file_path).unwrap();
+        let file = file_path.to_str().unwrap();
+        let output = output_path.to_str().unwrap();
+
+        for args in [&["text", "set", file, "Title", "Test image"][..], &["encode", file, "ruSt", "Hello Matt!"]] {
+            let mut cmd = Command::cargo_bin("ImgMod")
+                .unwrap();
+            cmd.args(args);
+            cmd.assert().success();
+        }
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["strip", file, output, "--remove", "tEXt"]);
+        cmd.assert().success().stdout("Removed tEXt (28 bytes)\nRemoved 1 chunk(s), saved 28 bytes\n");
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", output, "ruSt"]);
+        cmd.assert().success().stdout("msg: Hello Matt!\n");
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["strip", file, "--keep", "ruSt"]);
+        cmd.assert().success().stdout("Removed tEXt (28 bytes)\nRemoved 1 chunk(s), saved 28 bytes\n");
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["strip", file]);
+        cmd.assert().success().stdout("Removed ruSt (23 bytes)\nRemoved 1 chunk(s), saved 23 bytes\n");
+        assert_eq!(std::fs::read(file).unwrap(), std::fs::read("./images/test_image.png").unwrap());
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["strip", file, "--remove", "IDAT"]);
+        cmd.assert().failure();
+    }
+
     #[test]
     #[serial]
     fn test_encode() {
@@ -72,8 +197,8 @@ mod tests {
   "File: ./images/test_image.png, Size: 262022
   chunk#0{ chunk_type: IHDR, data_length: 13}
   chunk#1{ chunk_type: IDAT, data_length: 261942}
-  chunk#2{ chunk_type: IEND, data_length: 0}
-  chunk#3{ chunk_type: maTt, data_length: 11}\n"
+  chunk#2{ chunk_type: maTt, data_length: 11}
+  chunk#3{ chunk_type: IEND, data_length: 0}\n"
             );
         };
         TestCli::encode();
@@ -81,6 +206,186 @@ mod tests {
         TestCli::remove_chunks();
     }
 
+    #[test]
+    #[serial]
+    fn test_encode_at_end() {
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["encode", "./images/test_image.png", "maTt", "Hello Matt!", "--position", "end"]);
+        cmd.assert().success();
+
+        let assert = TestCli::print_chunks();
+        assert.stdout(
+  "File: ./images/test_image.png, Size: 262022
+  chunk#0{ chunk_type: IHDR, data_length: 13}
+  chunk#1{ chunk_type: IDAT, data_length: 261942}
+  chunk#2{ chunk_type: IEND, data_length: 0}
+  chunk#3{ chunk_type: maTt, data_length: 11}\n"
+        );
+        TestCli::remove_chunks();
+    }
+
+    #[test]
+    fn test_encrypted_message() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("secret.png");
+        let key_path = dir.path().join("key.bin");
+        std::fs::copy("./images/test_image.png", &file_path).unwrap();
+        std::fs::write(&key_path, [7u8; 64]).unwrap();
+        let file = file_path.to_str().unwrap();
+        let key = key_path.to_str().unwrap();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["encode", file, "maTt", "Hello Matt!", "--passphrase", "hunter2"]);
+        cmd.assert().success();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", file, "maTt", "--passphrase", "hunter2"]);
+        cmd.assert().success().stdout("msg: Hello Matt!\n");
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", file, "maTt", "--passphrase", "wrong"]);
+        cmd.assert().failure();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["encode", file, "ruSt", "Hello Key!", "--key-file", key]);
+        cmd.assert().success();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", file, "ruSt", "--key-file", key]);
+        cmd.assert().success().stdout("msg: Hello Key!\n");
+    }
+
+    #[test]
+    fn test_lsb_message() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("lsb.png");
+        let output_path = dir.path().join("lsb_out.png");
+        std::fs::copy("./images/test_image.png", &file_path).unwrap();
+        let file = file_path.to_str().unwrap();
+        let output = output_path.to_str().unwrap();
+
+        // 840x654 pixels with two bits in each of the three color channels
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["encode", file, "Hello Matt!", output, "--mode", "lsb", "--bits", "2", "--passphrase", "hunter2"]);
+        cmd.assert().success().stdout("").stderr("capacity: 412016 bytes, payload: 80 bytes\n");
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", output, "--mode", "lsb", "--bits", "2", "--passphrase", "hunter2"]);
+        cmd.assert().success().stdout("capacity: 412016 bytes\nmsg: Hello Matt!\n");
+
+        // The message lives in the pixels, so no chunk is added
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["print", output]);
+        let out = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+        assert!(out.contains("IDAT") && !out.contains("maTt"));
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", output, "--mode", "lsb", "--bits", "2", "--passphrase", "wrong"]);
+        cmd.assert().failure();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["encode", file, &"x".repeat(100_000), "--mode", "lsb", "--channels", "alpha"]);
+        cmd.assert().failure();
+    }
+
+    #[test]
+    fn test_binary_payload() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("binary.png");
+        let input_path = dir.path().join("payload.bin");
+        let output_path = dir.path().join("decoded.bin");
+        std::fs::copy("./images/test_image.png", &file_path).unwrap();
+        let payload: Vec<u8> = (0..=255u8).rev().cycle().take(1000).collect();
+        std::fs::write(&input_path, &payload).unwrap();
+        let file = file_path.to_str().unwrap();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["encode", file, "biNr", "--input-file", input_path.to_str().unwrap()]);
+        cmd.assert().success();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", file, "biNr", "--output-file", output_path.to_str().unwrap()]);
+        cmd.assert().success().stdout("");
+        assert_eq!(std::fs::read(&output_path).unwrap(), payload);
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", file, "biNr"]);
+        let out = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+        assert!(out.starts_with("msg (1000 bytes, not UTF-8, hex): fffefd"));
+
+        // Payload from stdin, hidden in the pixels, written back to stdout
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["encode", file, "--mode", "lsb", "--input-file", "-"]).write_stdin(payload.clone());
+        cmd.assert().success();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", file, "--mode", "lsb", "--output-file", "-"]);
+        cmd.assert().success().stdout(payload);
+    }
+
+    #[test]
+    fn test_split_message() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("split.png");
+        std::fs::copy("./images/test_image.png", &file_path).unwrap();
+        let file = file_path.to_str().unwrap();
+        let message = "Hello Matt! ".repeat(50);
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["encode", file, "spLt", &message, "--split", "100"]);
+        cmd.assert().success();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["print", file]);
+        let out = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+        assert_eq!(out.matches("chunk_type: spLt, data_length: 100}").count(), 600 / 55);
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", file, "spLt", "--split"]);
+        cmd.assert().success().stdout(format!("msg: {}\n", message));
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["remove", file, "spLt"]);
+        cmd.assert().success();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", file, "spLt", "--split"]);
+        cmd.assert().failure();
+    }
+
+    #[test]
+    fn test_print_from_stdin() {
+        let bytes = std::fs::read("./images/Gengar.png").unwrap();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["print", "-"]).write_stdin(bytes);
+        cmd.assert().success().stdout(
+            "File: -, Size: 95013\n  chunk#0{ chunk_type: IHDR, data_length: 13}\n  chunk#1{ chunk_type: IDAT, data_length: 94956}\n  chunk#2{ chunk_type: IEND, data_length: 0}\n",
+        );
+    }
+
     #[test]
     #[serial]
     fn test_decode() {
@@ -94,8 +399,88 @@ mod tests {
     fn test_remove() {
         TestCli::encode();
         TestCli::remove_chunks();
-        TestCli::print_chunks();
-        TestCli::remove_chunks();
+        TestCli::print_chunks().stdout(
+  "File: ./images/test_image.png, Size: 261999
+  chunk#0{ chunk_type: IHDR, data_length: 13}
+  chunk#1{ chunk_type: IDAT, data_length: 261942}
+  chunk#2{ chunk_type: IEND, data_length: 0}\n"
+        );
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["remove", "./images/test_image.png", "maTt"]);
+        cmd.assert().failure();
+    }
+
+    // A copy of test_image.png with ruSt chunks "three" at chunk#1, "one" at chunk#3 and "two" at chunk#4
+    fn duplicated_messages(dir: &tempfile::TempDir) -> String {
+        let file_path = dir.path().join("duplicates.png");
+        std::fs::copy("./images/test_image.png", &file_path).unwrap();
+        let file = file_path.to_str().unwrap().to_string();
+
+        for args in [&["one"][..], &["two"], &["three", "--position", "1"]] {
+            let mut cmd = Command::cargo_bin("ImgMod")
+                .unwrap();
+            cmd.args(["encode", &file, "ruSt"]).args(args);
+            cmd.assert().success();
+        }
+        file
+    }
+
+    #[test]
+    fn test_decode_all() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = duplicated_messages(&dir);
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", &file, "ruSt"]);
+        cmd.assert()
+            .success()
+            .stdout("msg: three\n")
+            .stderr("note: 2 more ruSt chunk(s) found, use --all to decode them all\n");
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", &file, "ruSt", "--all"]);
+        cmd.assert().success().stdout("chunk#1 msg: three\nchunk#3 msg: one\nchunk#4 msg: two\n");
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", &file, "--index", "4"]);
+        cmd.assert().success().stdout("msg: two\n");
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", &file, "ruSt", "--index", "2"]);
+        cmd.assert().code(error::EXIT_USAGE).stderr("Application error: chunk#2 is IDAT, not ruSt\n");
+    }
+
+    #[test]
+    fn test_remove_all_and_index() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = duplicated_messages(&dir);
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["remove", &file, "ruSt", "--index", "3"]);
+        cmd.assert().success();
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["decode", &file, "ruSt", "--all"]);
+        cmd.assert().success().stdout("chunk#1 msg: three\nchunk#3 msg: two\n");
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["remove", &file, "ruSt", "--all"]);
+        cmd.assert().success().stdout("Removed 2 ruSt chunk(s)\n");
+        assert_eq!(std::fs::read(&file).unwrap(), std::fs::read("./images/test_image.png").unwrap());
+
+        let mut cmd = Command::cargo_bin("ImgMod")
+            .unwrap();
+        cmd.args(["remove", &file, "ruSt", "--all"]);
+        cmd.assert().failure();
     }
 
     #[test]
@@ -103,6 +488,7 @@ mod tests {
 
         let dir = tempdir().expect("Error creating temporary directory");
         let file_path = dir.path().join("test.png");
+        std::fs::write(&file_path, b"").expect("Error creating temporary file");
     
         let mut cmd = Command::cargo_bin("ImgMod")
         .unwrap();
@@ -0,0 +1,87 @@
+/**
+ * Unit tests for passphrase-based payload encryption.
+ *
+ * This module checks that sealed payloads round-trip, and that a wrong secret, tampered data or a
+ * malformed header is reported as an error instead of returning garbage.
+ *
+ * Tests:
+ * - `test_encrypt_round_trip` - Checks that a payload decrypts back to the original plaintext.
+ * - `test_encrypt_is_randomized` - Ensures that salt and nonce differ between two encryptions.
+ * - `test_decrypt_wrong_secret` - Ensures that a wrong secret fails authentication.
+ * - `test_decrypt_tampered_payload` - Ensures that a flipped ciphertext byte fails authentication.
+ * - `test_encrypt_params_too_large` - Ensures that KDF parameters decrypt would reject are refused when sealing.
+ * - `test_decrypt_invalid_header` - Ensures that truncated payloads, unknown versions and excessive KDF parameters are rejected.
+ */
+#[cfg(test)]
+mod tests {
+    use imgmod::stego::crypto::{decrypt, encrypt_with_params, CryptoError, KdfParams, HEADER_LEN, MAX_ITERATIONS, MAX_MEMORY_KIB, MAX_PARALLELISM};
+
+    // Small KDF parameters keep the tests fast
+    const PARAMS: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    fn sealed() -> Vec<u8> {
+        encrypt_with_params(b"Hello Matt!", b"hunter2", PARAMS).unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let payload = sealed();
+
+        assert_eq!(payload.len(), HEADER_LEN + b"Hello Matt!".len() + 16);
+        assert_eq!(decrypt(&payload, b"hunter2").unwrap(), b"Hello Matt!");
+    }
+
+    #[test]
+    fn test_encrypt_is_randomized() {
+        assert_ne!(sealed(), sealed());
+    }
+
+    #[test]
+    fn test_decrypt_wrong_secret() {
+        let result = decrypt(&sealed(), b"hunter3");
+        assert!(matches!(result, Err(CryptoError::AuthenticationFailed)));
+    }
+
+    #[test]
+    fn test_decrypt_tampered_payload() {
+        let mut payload = sealed();
+        let last = payload.len() - 1;
+        payload[last] ^= 1;
+
+        let result = decrypt(&payload, b"hunter2");
+        assert!(matches!(result, Err(CryptoError::AuthenticationFailed)));
+    }
+
+    #[test]
+    fn test_encrypt_params_too_large() {
+        for params in [
+            KdfParams { memory_kib: MAX_MEMORY_KIB + 1, ..PARAMS },
+            KdfParams { iterations: MAX_ITERATIONS + 1, ..PARAMS },
+            KdfParams { parallelism: MAX_PARALLELISM + 1, ..PARAMS },
+        ] {
+            assert!(matches!(encrypt_with_params(b"Hello Matt!", b"hunter2", params), Err(CryptoError::ParamsTooLarge)));
+        }
+    }
+
+    #[test]
+    fn test_decrypt_invalid_header() {
+        let payload = sealed();
+        assert!(matches!(decrypt(&payload[..HEADER_LEN - 1], b"hunter2"), Err(CryptoError::InvalidPayload)));
+
+        let mut payload = sealed();
+        payload[0] = 9;
+        assert!(matches!(decrypt(&payload, b"hunter2"), Err(CryptoError::UnsupportedVersion(9))));
+
+        let mut payload = sealed();
+        payload[1..5].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(decrypt(&payload, b"hunter2"), Err(CryptoError::InvalidPayload)));
+
+        let mut payload = sealed();
+        payload[5..9].copy_from_slice(&(MAX_ITERATIONS + 1).to_be_bytes());
+        assert!(matches!(decrypt(&payload, b"hunter2"), Err(CryptoError::InvalidPayload)));
+    }
+}
@@ -0,0 +1,186 @@
+mod common;
+
+/**
+ * Integration tests for the chunk-level diff between two PNGs.
+ *
+ * Tests:
+ * - `test_identical` - Checks that an image has no changes against itself.
+ * - `test_added_and_removed` - Checks that chunks only in one image are reported as added or removed.
+ * - `test_moved` - Checks that a chunk that left the common order is reported as moved, and larger chunks stay in place.
+ * - `test_moved_across_split_image_data` - Checks that a chunk hopping over thousands of IDAT chunks is the only one moved.
+ * - `test_modified_bytes` - Checks the byte summary of a chunk whose data changed.
+ * - `test_modified_ihdr` - Checks that IHDR changes are listed field by field.
+ * - `test_modified_text` - Checks that text chunks are paired by keyword and their fields compared.
+ * - `test_diff_cli` - Runs the diff command with text and JSON output.
+ */
+#[cfg(test)]
+mod tests {
+    use assert_cmd::Command;
+    use tempfile::tempdir;
+    use imgmod::png::chunk::Chunk;
+    use imgmod::png::diff::{diff, ByteSummary, ChunkChange, FieldChange};
+    use imgmod::png::image::Png;
+    use imgmod::png::text::TextEntry;
+    use crate::common::chunk;
+
+    fn ihdr(width: u32, color_type: u8) -> Chunk {
+        let mut data = width.to_be_bytes().to_vec();
+        data.extend_from_slice(&[0, 0, 0, 1, 8, color_type, 0, 0, 0]);
+        chunk("IHDR", &data)
+    }
+
+    fn testing_png() -> Png {
+        Png::from_chunks(vec![
+            ihdr(1, 2),
+            chunk("ruSt", b"hidden"),
+            chunk("IDAT", b"lots of pixel data"),
+            chunk("IEND", b""),
+        ])
+    }
+
+    fn field(field: &str, old: &str, new: &str) -> FieldChange {
+        FieldChange { field: field.to_string(), old: old.to_string(), new: new.to_string() }
+    }
+
+    #[test]
+    fn test_identical() {
+        assert!(diff(&testing_png(), &testing_png()).is_empty());
+    }
+
+    #[test]
+    fn test_added_and_removed() {
+        let old = testing_png();
+        let mut new = testing_png();
+        new.remove_chunk("ruSt").unwrap();
+        new.insert_before("IEND", chunk("teSt", b"new")).unwrap();
+
+        assert_eq!(
+            diff(&old, &new),
+            [
+                ChunkChange::Removed { old_index: 1, chunk_type: "ruSt".to_string(), length: 6 },
+                ChunkChange::Added { new_index: 2, chunk_type: "teSt".to_string(), length: 3 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_moved() {
+        let old = testing_png();
+        let mut new = testing_png();
+        let moved = new.remove_chunk("ruSt").unwrap();
+        new.insert_before("IEND", moved).unwrap();
+
+        assert_eq!(
+            diff(&old, &new),
+            [ChunkChange::Moved { old_index: 1, new_index: 2, chunk_type: "ruSt".to_string() }]
+        );
+    }
+
+    #[test]
+    fn test_moved_across_split_image_data() {
+        let idats: Vec<Chunk> = (0..5000u16).map(|i| chunk("IDAT", &i.to_be_bytes())).collect();
+        let old = Png::from_chunks([vec![ihdr(1, 2), chunk("ruSt", b"hidden")], idats.clone(), vec![chunk("IEND", b"")]].concat());
+        let new = Png::from_chunks([vec![ihdr(1, 2)], idats, vec![chunk("ruSt", b"hidden"), chunk("IEND", b"")]].concat());
+
+        assert_eq!(
+            diff(&old, &new),
+            [ChunkChange::Moved { old_index: 1, new_index: 5001, chunk_type: "ruSt".to_string() }]
+        );
+    }
+
+    #[test]
+    fn test_modified_bytes() {
+        assert_eq!(
+            ByteSummary::new(b"hidden", b"hiddle!!"),
+            ByteSummary { old_length: 6, new_length: 8, changed_bytes: 4, first_difference: 4 }
+        );
+
+        let old = testing_png();
+        let mut new = testing_png();
+        new.remove_chunk("ruSt").unwrap();
+        new.insert_after("IHDR", chunk("ruSt", b"hiddle!!")).unwrap();
+
+        let changes = diff(&old, &new);
+        assert!(matches!(
+            changes.as_slice(),
+            [ChunkChange::Modified { old_index: 1, new_index: 1, bytes, fields, .. }] if bytes.changed_bytes == 4 && fields.is_empty()
+        ));
+    }
+
+    #[test]
+    fn test_modified_ihdr() {
+        let old = testing_png();
+        let mut chunks = old.chunks().to_vec();
+        chunks[0] = ihdr(2, 6);
+        let new = Png::from_chunks(chunks);
+
+        let changes = diff(&old, &new);
+        let [ChunkChange::Modified { chunk_type, fields, .. }] = changes.as_slice() else {
+            panic!("expected a single modified chunk");
+        };
+        assert_eq!(chunk_type, "IHDR");
+        assert_eq!(fields, &[field("width", "1", "2"), field("color_type", "RGB (2)", "RGBA (6)")]);
+    }
+
+    #[test]
+    fn test_modified_text() {
+        let mut old = testing_png();
+        old.set_text(TextEntry::new("Title", "Old")).unwrap();
+        old.set_text(TextEntry::new("Author", "Matt")).unwrap();
+
+        let mut new = testing_png();
+        new.set_text(TextEntry::new("Author", "Matt")).unwrap();
+        new.set_text(TextEntry::compressed("Title", "New")).unwrap();
+
+        let changes = diff(&old, &new);
+        let [ChunkChange::Moved { chunk_type: moved, .. }, ChunkChange::Modified { chunk_type, fields, .. }] = changes.as_slice() else {
+            panic!("expected a moved Author chunk and a modified Title chunk, got {:?}", changes);
+        };
+        assert_eq!(moved, "tEXt");
+        assert_eq!(chunk_type, "zTXt");
+        assert_eq!(
+            fields,
+            &[field("chunk_type", "tEXt", "zTXt"), field("text", "Old", "New"), field("compressed", "false", "true")]
+        );
+    }
+
+    #[test]
+    fn test_diff_cli() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let old_path = dir.path().join("old.png");
+        let new_path = dir.path().join("new.png");
+        std::fs::copy("./images/test_image.png", &old_path).unwrap();
+        let old = old_path.to_str().unwrap();
+        let new = new_path.to_str().unwrap();
+
+        let mut png = Png::from_file(old).unwrap();
+        png.set_text(TextEntry::new("Comment", "say \"hi\"")).unwrap();
+        png.write_file(new).unwrap();
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["diff", old, old]);
+        cmd.assert().success().stdout("No differences\n");
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["diff", old, new]);
+        cmd.assert().success().stdout("added: chunk#2 (tEXt) 16 bytes\n");
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["diff", new, old, "--format", "json"]);
+        cmd.assert().success().stdout(format!(
+"{{
+  \"old_file\": \"{}\",
+  \"new_file\": \"{}\",
+  \"identical\": false,
+  \"changes\": [
+    {{
+      \"change\": \"removed\",
+      \"chunk_type\": \"tEXt\",
+      \"old_index\": 2,
+      \"length\": 16
+    }}
+  ]
+}}
+", new, old));
+    }
+}
@@ -0,0 +1,179 @@
+/**
+ * Integration tests for encoding pixel buffers into PNGs.
+ *
+ * Encoded images are decoded both with `Png::pixels` and with the `png` crate, so the tests check
+ * the filters, interlacing and IDAT splitting against an independent decoder.
+ *
+ * Tests:
+ * - `test_encode_round_trip_all_color_types` - Round-trips every legal color type and bit depth.
+ * - `test_encode_interlaced` - Round-trips Adam7-interlaced images.
+ * - `test_encode_filter_strategies` - Checks that every filter strategy produces identical pixels.
+ * - `test_encode_fixed_filter` - Ensures that a fixed strategy uses its filter on every scanline.
+ * - `test_encode_idat_chunk_size` - Checks that image data is split into IDAT chunks of the configured size.
+ * - `test_encode_reference_decoder` - Ensures that the `png` crate decodes our output to the same pixels.
+ * - `test_encode_invalid_compression_level` - Ensures that compression levels above 9 are rejected.
+ * - `test_replace_pixels` - Checks that re-encoding keeps ancillary chunks and their order.
+ */
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use flate2::read::ZlibDecoder;
+    use imgmod::png::encoder::{EncoderOptions, FilterStrategy};
+    use imgmod::png::filter::FilterType;
+    use imgmod::png::ihdr::ColorType;
+    use imgmod::png::image::Png;
+    use imgmod::png::pixels::{PixelBuffer, PixelError, Samples};
+    use imgmod::png::text::TextEntry;
+
+    const WIDTH: u32 = 11;
+    const HEIGHT: u32 = 9;
+
+    fn test_pixels(color_type: ColorType, bit_depth: u8) -> PixelBuffer {
+        let count = (WIDTH * HEIGHT) as usize * color_type.channels();
+        let mut state: u32 = 0xdead_beef;
+        let mut next = || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            state >> 8
+        };
+
+        let samples = if bit_depth == 16 {
+            Samples::Sixteen((0..count).map(|_| next() as u16).collect())
+        } else {
+            let max = (1u32 << bit_depth) - 1;
+            Samples::Eight((0..count).map(|_| (next() & max) as u8).collect())
+        };
+        PixelBuffer::new(WIDTH, HEIGHT, color_type, bit_depth, samples).unwrap()
+    }
+
+    fn all_formats() -> Vec<(ColorType, u8)> {
+        [ColorType::Grayscale, ColorType::Rgb, ColorType::Indexed, ColorType::GrayscaleAlpha, ColorType::Rgba]
+            .iter()
+            .flat_map(|&c| c.allowed_bit_depths().iter().map(move |&d| (c, d)))
+            .collect()
+    }
+
+    fn raw_scanlines(png: &Png) -> Vec<u8> {
+        let mut raw = Vec::new();
+        ZlibDecoder::new(png.image_data().as_slice()).read_to_end(&mut raw).unwrap();
+        raw
+    }
+
+    #[test]
+    fn test_encode_round_trip_all_color_types() {
+        for (color_type, bit_depth) in all_formats() {
+            let pixels = test_pixels(color_type, bit_depth);
+            let png = Png::from_pixels(&pixels, &EncoderOptions::default()).unwrap();
+
+            assert_eq!(png.pixels().unwrap(), pixels, "{:?} at {} bits", color_type, bit_depth);
+        }
+    }
+
+    #[test]
+    fn test_encode_interlaced() {
+        let options = EncoderOptions { interlaced: true, ..EncoderOptions::default() };
+
+        for (color_type, bit_depth) in all_formats() {
+            let pixels = test_pixels(color_type, bit_depth);
+            let png = Png::from_pixels(&pixels, &options).unwrap();
+
+            assert!(png.ihdr().unwrap().is_interlaced());
+            assert_eq!(png.pixels().unwrap(), pixels, "{:?} at {} bits", color_type, bit_depth);
+        }
+    }
+
+    #[test]
+    fn test_encode_filter_strategies() {
+        let pixels = Png::from_file("./images/Gengar.png").unwrap().pixels().unwrap();
+        let strategies = [
+            FilterStrategy::Fixed(FilterType::None),
+            FilterStrategy::Fixed(FilterType::Average),
+            FilterStrategy::MinSum,
+            FilterStrategy::BruteForce,
+        ];
+
+        for filter in strategies {
+            let options = EncoderOptions { filter, compression_level: 1, ..EncoderOptions::default() };
+            let png = Png::from_pixels(&pixels, &options).unwrap();
+            assert_eq!(png.pixels().unwrap(), pixels, "{:?}", filter);
+        }
+    }
+
+    #[test]
+    fn test_encode_fixed_filter() {
+        let pixels = test_pixels(ColorType::Rgb, 8);
+        let options = EncoderOptions {
+            filter: FilterStrategy::Fixed(FilterType::Paeth),
+            ..EncoderOptions::default()
+        };
+        let png = Png::from_pixels(&pixels, &options).unwrap();
+
+        let raw = raw_scanlines(&png);
+        let stride = 1 + WIDTH as usize * 3;
+        assert_eq!(raw.len(), stride * HEIGHT as usize);
+        assert!(raw.chunks(stride).all(|row| row[0] == FilterType::Paeth.value()));
+    }
+
+    #[test]
+    fn test_encode_idat_chunk_size() {
+        let pixels = test_pixels(ColorType::Rgba, 16);
+        let options = EncoderOptions {
+            idat_chunk_size: 100,
+            compression_level: 0,
+            ..EncoderOptions::default()
+        };
+        let png = Png::from_pixels(&pixels, &options).unwrap();
+
+        let types: Vec<String> = png.chunks().iter().map(|c| c.chunk_type().to_string()).collect();
+        let idats: Vec<_> = png.chunks().iter().filter(|c| c.chunk_type().to_string() == "IDAT").collect();
+
+        assert_eq!(types.first().unwrap(), "IHDR");
+        assert_eq!(types.last().unwrap(), "IEND");
+        assert_eq!(idats.len(), png.image_data().len().div_ceil(100));
+        assert!(idats.iter().all(|c| c.length() <= 100));
+        assert_eq!(png.pixels().unwrap(), pixels);
+    }
+
+    #[test]
+    fn test_encode_reference_decoder() {
+        let pixels = test_pixels(ColorType::Rgb, 16);
+        let png = Png::from_pixels(&pixels, &EncoderOptions::default()).unwrap();
+        let bytes = png.as_bytes();
+
+        let decoder = png::Decoder::new(bytes.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut buf).unwrap();
+
+        let expected: Vec<u8> = match pixels.samples() {
+            Samples::Sixteen(s) => s.iter().flat_map(|v| v.to_be_bytes()).collect(),
+            Samples::Eight(_) => unreachable!(),
+        };
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn test_encode_invalid_compression_level() {
+        let pixels = test_pixels(ColorType::Grayscale, 8);
+        let options = EncoderOptions { compression_level: 10, ..EncoderOptions::default() };
+
+        let result = imgmod::png::encoder::encode(&pixels, &options);
+        assert!(matches!(result, Err(PixelError::InvalidCompressionLevel(10))));
+    }
+
+    #[test]
+    fn test_replace_pixels() {
+        let mut png = Png::from_pixels(&test_pixels(ColorType::Rgb, 8), &EncoderOptions::default()).unwrap();
+        png.set_text(TextEntry::new("Title", "before IEND")).unwrap();
+        png.insert_after("IHDR", TextEntry::new("Author", "after IHDR").to_chunk().unwrap()).unwrap();
+
+        let replacement = test_pixels(ColorType::Grayscale, 4);
+        let options = EncoderOptions { idat_chunk_size: 10, ..EncoderOptions::default() };
+        png.replace_pixels(&replacement, &options).unwrap();
+
+        let types: Vec<String> = png.chunks().iter().map(|c| c.chunk_type().to_string()).collect();
+        assert_eq!(&types[..3], ["IHDR", "tEXt", "IDAT"]);
+        assert_eq!(&types[types.len() - 2..], ["tEXt", "IEND"]);
+        assert_eq!(png.pixels().unwrap(), replacement);
+        assert_eq!(png.text_entries().len(), 2);
+    }
+}
@@ -0,0 +1,196 @@
+/**
+ * Integration tests for the error types and the CLI exit codes derived from them.
+ *
+ * Test Cases:
+ * - `test_io_error_has_path()`: Ensures that failing to open a file reports the path and chains the I/O error.
+ * - `test_chunk_not_found()`: Checks that removing a missing chunk names the chunk type.
+ * - `test_invalid_chunk_type()`: Ensures that a chunk type with invalid bytes reports the bytes found.
+ * - `test_chunk_truncated()`: Ensures that a chunk cut off before its CRC reports the expected and actual length.
+ * - `test_crc_mismatch()`: Checks that a bad CRC is reported with both the stored and computed values.
+ * - `test_source_chain()`: Checks that wrapped errors expose their cause through `source`.
+ * - `test_command_error_exit_codes()`: Checks the exit code picked for each kind of command error.
+ * - `test_cli_exit_codes()`: Runs the CLI against missing files, chunks and keywords and checks the exit code.
+ * - `test_cli_corrupted_chunk()`: Checks that print, info, lint and decode reject a file with a bad CRC in any output format.
+ */
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::io;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+    use assert_cmd::Command;
+    use tempfile::tempdir;
+    use imgmod::img_cli::error::{self, CommandError};
+    use imgmod::png::chunk::{Chunk, ChunkError};
+    use imgmod::png::chunk_type::ChunkType;
+    use imgmod::png::image::{Png, PngError};
+    use imgmod::stego::crypto::CryptoError;
+
+    fn chunk_bytes(chunk_type: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), data.to_vec());
+        let mut bytes = chunk.as_bytes();
+        bytes[4..8].copy_from_slice(chunk_type);
+        bytes
+    }
+
+    #[test]
+    fn test_io_error_has_path() {
+        let path = PathBuf::from("./images/does_not_exist.png");
+        let result = Png::from_file(&path);
+
+        let Err(e) = result else {
+            panic!("expected an I/O error");
+        };
+        assert!(matches!(&e, PngError::Io { path: Some(p), .. } if *p == path));
+        assert!(e.to_string().contains("does_not_exist.png"));
+
+        let source = e.source().and_then(|s| s.downcast_ref::<io::Error>()).unwrap();
+        assert_eq!(source.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_chunk_not_found() {
+        let mut png = Png::from_file("./images/test_image.png").unwrap();
+        let result = png.remove_chunk("ruSt");
+
+        let Err(e) = result else {
+            panic!("expected ChunkNotFound");
+        };
+        assert!(matches!(&e, PngError::ChunkNotFound(t) if t == "ruSt"));
+        assert_eq!(e.to_string(), "No ruSt chunk found");
+    }
+
+    #[test]
+    fn test_invalid_chunk_type() {
+        let bytes = chunk_bytes(b"ru1t", b"data");
+        let result = Chunk::try_from(bytes.as_slice());
+
+        assert!(matches!(result, Err(ChunkError::InvalidChunkType(b)) if &b == b"ru1t"));
+    }
+
+    #[test]
+    fn test_chunk_truncated() {
+        let bytes = chunk_bytes(b"ruSt", b"some data");
+        let result = Chunk::from_bytes_unchecked(&bytes[..bytes.len() - 2]);
+
+        assert!(matches!(
+            result,
+            Err(ChunkError::Truncated { expected, actual }) if expected == bytes.len() && actual == bytes.len() - 2
+        ));
+    }
+
+    #[test]
+    fn test_crc_mismatch() {
+        let mut bytes = chunk_bytes(b"ruSt", b"some data");
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+
+        let stored = u32::from_be_bytes(bytes[last - 3..].try_into().unwrap());
+        let result = Chunk::try_from(bytes.as_slice());
+
+        assert!(matches!(
+            result,
+            Err(ChunkError::CrcMismatch { expected, actual }) if expected == stored && actual == stored ^ 0xff
+        ));
+    }
+
+    #[test]
+    fn test_source_chain() {
+        let Err(chunk_error) = Chunk::from_strings("ru1t", "data") else {
+            panic!("expected an invalid chunk type");
+        };
+        assert!(matches!(chunk_error, ChunkError::ChunkType(_)));
+        assert!(chunk_error.source().is_some());
+
+        let png_error = PngError::from(chunk_error);
+        let cause = png_error.source().unwrap();
+        assert!(cause.downcast_ref::<ChunkError>().is_some());
+        assert!(cause.source().is_some());
+
+        // Wrapped errors display as themselves, so the chain skips straight to their cause
+        let command_error = CommandError::from(png_error);
+        assert_eq!(command_error.to_string(), "Invalid chunk");
+        assert!(command_error.source().unwrap().downcast_ref::<ChunkError>().is_some());
+    }
+
+    #[test]
+    fn test_command_error_exit_codes() {
+        let io_error = || io::Error::from(io::ErrorKind::NotFound);
+
+        assert_eq!(CommandError::MissingArgument("message").exit_code(), error::EXIT_USAGE);
+        assert_eq!(CommandError::io(&PathBuf::from("x"), io_error()).exit_code(), error::EXIT_IO);
+        assert_eq!(CommandError::from(PngError::from(io_error())).exit_code(), error::EXIT_IO);
+        assert_eq!(CommandError::from(PngError::InvalidSignature(vec![0])).exit_code(), error::EXIT_INVALID_DATA);
+        assert_eq!(CommandError::from(PngError::ChunkNotFound("ruSt".to_string())).exit_code(), error::EXIT_NOT_FOUND);
+        assert_eq!(CommandError::KeywordNotFound("Title".to_string()).exit_code(), error::EXIT_NOT_FOUND);
+        assert_eq!(CommandError::ValidationFailed(1).exit_code(), error::EXIT_VALIDATION);
+        assert_eq!(CommandError::from(CryptoError::AuthenticationFailed).exit_code(), error::EXIT_CRYPTO);
+    }
+
+    #[test]
+    fn test_cli_exit_codes() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("errors.png");
+        std::fs::copy("./images/test_image.png", &file_path).unwrap();
+        let file = file_path.to_str().unwrap();
+
+        let not_png = dir.path().join("not_a.png");
+        std::fs::write(&not_png, "plain text").unwrap();
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["print", "./images/does_not_exist.png"]);
+        cmd.assert().code(error::EXIT_IO);
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["print", not_png.to_str().unwrap()]);
+        cmd.assert().code(error::EXIT_INVALID_DATA);
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["decode", file, "ruSt"]);
+        cmd.assert().code(error::EXIT_NOT_FOUND).stderr("Application error: No ruSt chunk found\n");
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["text", "get", file, "Title"]);
+        cmd.assert().code(error::EXIT_NOT_FOUND);
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["encode", file, "ruSt", "secret", "--passphrase", "right"]);
+        cmd.assert().success();
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["decode", file, "ruSt", "--passphrase", "wrong"]);
+        cmd.assert().code(error::EXIT_CRYPTO);
+    }
+
+    #[test]
+    fn test_cli_corrupted_chunk() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("corrupted.png");
+        let mut bytes = std::fs::read("./images/test_image.png").unwrap();
+        let idat = bytes.windows(4).position(|window| window == b"IDAT").unwrap();
+        bytes[idat + 14] ^= 0xff;
+        std::fs::write(&file_path, bytes).unwrap();
+        let file = file_path.to_str().unwrap();
+
+        for args in [
+            &["print", file][..],
+            &["print", file, "--format", "json"],
+            &["info", file],
+            &["info", file, "--format", "yaml"],
+            &["lint", file],
+            &["lint", file, "--format", "json"],
+            &["decode", file, "-c", "ruSt"],
+        ] {
+            let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+            cmd.args(args);
+            let assert = cmd.assert().code(error::EXIT_INVALID_DATA);
+            let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+            assert!(stderr.contains("(IDAT)"), "{:?}: {}", args, stderr);
+        }
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["print", file, "--lenient"]);
+        let assert = cmd.assert().success();
+        assert!(String::from_utf8(assert.get_output().stdout.clone()).unwrap().contains("corrupted:"));
+    }
+}
@@ -0,0 +1,222 @@
+/**
+ * Fuzz tests for PNG parsing and the CLI commands, driven by libafl.
+ *
+ * Each run mutates a few small seed images for a fixed number of iterations with a fixed seed, so
+ * failures reproduce. Any panic counts as a crash and fails the test with the input that caused it.
+ * The harness is not instrumented for code coverage; instead it records which outcome each step had
+ * (which error, or success), and inputs that reach a new outcome are kept for further mutation.
+ *
+ * Tests:
+ * - `fuzz_png_parsing()`: Feeds mutated bytes to `Png::try_from`, the lenient parser, IHDR, pixel, text and ancillary chunk decoding and lint.
+ * - `fuzz_cli_commands()`: Writes mutated bytes to a file and runs print (as text and JSON), info, lint, text, decode, encode and remove on it.
+ */
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+    use std::fmt::Debug;
+    use std::hash::{Hash, Hasher};
+    use std::panic::{self, AssertUnwindSafe};
+    use std::path::Path;
+    use std::str::FromStr;
+    use std::sync::Once;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use clap::Parser;
+    use libafl::corpus::InMemoryCorpus;
+    use libafl::events::NopEventManager;
+    use libafl::executors::{ExitKind, InProcessExecutor};
+    use libafl::feedbacks::{CrashFeedback, MapFeedbackMetadata, MaxMapFeedback};
+    use libafl::inputs::{BytesInput, HasTargetBytes};
+    use libafl::mutators::{havoc_mutations, StdScheduledMutator};
+    use libafl::observers::StdMapObserver;
+    use libafl::schedulers::QueueScheduler;
+    use libafl::stages::{RetryCountRestartHelper, StdMutationalStage};
+    use libafl::state::StdState;
+    use libafl::{Evaluator, Fuzzer, StdFuzzer};
+    use libafl_bolts::rands::StdRand;
+    use libafl_bolts::serdeany::RegistryBuilder;
+    use libafl_bolts::tuples::tuple_list;
+    use libafl_bolts::AsSlice;
+    use tempfile::tempdir;
+
+    use imgmod::img_cli::args::CliArgs;
+    use imgmod::png::ancillary::{Ancillary, PhysicalDimensions, Time};
+    use imgmod::png::chunk::Chunk;
+    use imgmod::png::chunk_type::ChunkType;
+    use imgmod::png::encoder::EncoderOptions;
+    use imgmod::png::ihdr::ColorType;
+    use imgmod::png::image::Png;
+    use imgmod::png::lint::lint;
+    use imgmod::png::pixels::{PixelBuffer, Samples};
+    use imgmod::png::text::TextEntry;
+
+    const MAP_SIZE: usize = 4096;
+
+    // libafl is built without std, so it asks the host for the time
+    #[unsafe(no_mangle)]
+    pub extern "C" fn external_current_millis() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+    }
+
+    // Without std libafl cannot register its metadata types on startup, so the ones used here are registered by hand
+    fn register_metadata() {
+        static REGISTER: Once = Once::new();
+        REGISTER.call_once(|| unsafe {
+            RegistryBuilder::register::<MapFeedbackMetadata<u8>>();
+            RegistryBuilder::register::<RetryCountRestartHelper>();
+            RegistryBuilder::register::<ExitKind>();
+        });
+    }
+
+    fn seeds() -> Vec<Vec<u8>> {
+        let samples = (0..4 * 3 * 4).map(|i| (i * 5) as u8).collect();
+        let pixels = PixelBuffer::new(4, 3, ColorType::Rgba, 8, Samples::Eight(samples)).unwrap();
+        let mut png = Png::from_pixels(&pixels, &EncoderOptions::default()).unwrap();
+        png.set_text(TextEntry::new("Title", "fuzz")).unwrap();
+        png.set_ancillary(&Ancillary::PhysicalDimensions(PhysicalDimensions::from_dpi(72.0, 72.0).unwrap())).unwrap();
+        png.set_ancillary(&Ancillary::Time(Time::from_unix(1_700_000_000))).unwrap();
+        png.insert_before("IEND", Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hidden".to_vec())).unwrap();
+
+        let samples = (0..5 * 5).map(|i| (i * 2000) as u16).collect();
+        let pixels = PixelBuffer::new(5, 5, ColorType::Grayscale, 16, Samples::Sixteen(samples)).unwrap();
+        let options = EncoderOptions { interlaced: true, ..EncoderOptions::default() };
+        let interlaced = Png::from_pixels(&pixels, &options).unwrap();
+
+        vec![png.as_bytes(), interlaced.as_bytes(), Png::STANDARD_HEADER.to_vec()]
+    }
+
+    fn outcome<T, E: Debug>(result: &Result<T, E>) -> String {
+        match result {
+            Ok(_) => "ok".to_string(),
+            Err(e) => format!("{:?}", e),
+        }
+    }
+
+    // Digits are dropped so offsets and lengths do not count as new behaviour
+    fn slot(outcome: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        outcome.chars().filter(|c| !c.is_ascii_digit()).collect::<String>().hash(&mut hasher);
+        hasher.finish() as usize % MAP_SIZE
+    }
+
+    fn parse_png(bytes: &[u8]) -> Vec<String> {
+        let mut outcomes = vec![outcome(&Png::try_from(bytes))];
+
+        let lenient = Png::from_bytes_lenient(bytes);
+        outcomes.push(outcome(&lenient));
+        if let Ok(png) = lenient {
+            outcomes.push(outcome(&png.ihdr()));
+            outcomes.push(outcome(&png.pixels()));
+            outcomes.extend(png.chunks().iter().filter(|c| TextEntry::is_text_chunk(c)).map(|c| outcome(&TextEntry::try_from(c))));
+            outcomes.push(outcome(&png.ancillary_chunks()));
+            outcomes.extend(lint(&png).iter().map(|d| d.to_string()));
+            outcomes.push(outcome(&Png::try_from(png.as_bytes().as_slice())));
+        }
+
+        outcomes
+    }
+
+    fn run_commands(bytes: &[u8], file: &Path) -> Vec<String> {
+        std::fs::write(file, bytes).unwrap();
+        let file = file.to_str().unwrap();
+
+        let commands: [&[&str]; 11] = [
+            &["print", file],
+            &["print", file, "--lenient"],
+            &["print", file, "--lenient", "--format", "json"],
+            &["info", file],
+            &["lint", file],
+            &["text", "list", file],
+            &["decode", file, "ruSt"],
+            &["decode", file, "ruSt", "--split"],
+            &["decode", file, "--mode", "lsb"],
+            &["encode", file, "ruSt", "fuzz"],
+            &["remove", file, "ruSt"],
+        ];
+
+        commands
+            .iter()
+            .map(|args| {
+                let cli = CliArgs::parse_from(std::iter::once("ImgMod").chain(args.iter().copied()));
+                match imgmod::run(cli) {
+                    Ok(()) => format!("{} ok", args[0]),
+                    Err(e) => format!("{} {}: {}", args[0], e.exit_code(), e),
+                }
+            })
+            .collect()
+    }
+
+    // Runs `target` on mutated seeds and returns every input that made it panic
+    fn fuzz<F>(seed: u64, iterations: u64, mut target: F) -> Vec<Vec<u8>>
+    where
+        F: FnMut(&[u8]) -> Vec<String>,
+    {
+        register_metadata();
+        let mut map = vec![0u8; MAP_SIZE];
+        let map_ptr = map.as_mut_ptr();
+        let mut crashes = Vec::new();
+
+        let mut harness = |input: &BytesInput| {
+            let bytes = input.target_bytes();
+            match panic::catch_unwind(AssertUnwindSafe(|| target(bytes.as_slice()))) {
+                Ok(outcomes) => {
+                    for outcome in outcomes {
+                        // The observer reads the map through the same pointer after each run
+                        unsafe { *map_ptr.add(slot(&outcome)) = 1 };
+                    }
+                    ExitKind::Ok
+                }
+                Err(_) => {
+                    crashes.push(bytes.as_slice().to_vec());
+                    ExitKind::Crash
+                }
+            }
+        };
+
+        let observer = unsafe { StdMapObserver::from_mut_ptr("outcomes", map_ptr, MAP_SIZE) };
+        let mut feedback = MaxMapFeedback::new(&observer);
+        let mut objective = CrashFeedback::new();
+
+        let mut state = StdState::new(
+            StdRand::with_seed(seed),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let mut executor = InProcessExecutor::new(&mut harness, tuple_list!(observer), &mut fuzzer, &mut state, &mut mgr).unwrap();
+
+        for seed in seeds() {
+            fuzzer.add_input(&mut state, &mut executor, &mut mgr, BytesInput::new(seed)).unwrap();
+        }
+
+        let mut stages = tuple_list!(StdMutationalStage::new(StdScheduledMutator::new(havoc_mutations())));
+        fuzzer.fuzz_loop_for(&mut stages, &mut executor, &mut state, &mut mgr, iterations).unwrap();
+
+        drop(executor);
+        drop(map);
+        crashes
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn fuzz_png_parsing() {
+        let crashes = fuzz(0x1337, 200, parse_png);
+        assert!(crashes.is_empty(), "{} crash(es), first input: {}", crashes.len(), hex(&crashes[0]));
+    }
+
+    #[test]
+    fn fuzz_cli_commands() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = dir.path().join("fuzz.png");
+
+        let crashes = fuzz(0xC0FFEE, 20, |bytes| run_commands(bytes, &file));
+        assert!(crashes.is_empty(), "{} crash(es), first input: {}", crashes.len(), hex(&crashes[0]));
+    }
+}
@@ -0,0 +1,108 @@
+/**
+ * Unit tests for the `Ihdr` struct.
+ *
+ * This module contains tests verifying that the IHDR chunk is parsed into typed fields and that
+ * values the PNG spec does not allow are rejected.
+ *
+ * Tests:
+ * - `test_ihdr_from_bytes` - Checks that every field is parsed from the 13-byte chunk data.
+ * - `test_ihdr_round_trip` - Ensures that `as_bytes` gives back the original chunk data.
+ * - `test_ihdr_invalid_length` - Ensures that chunk data that is not 13 bytes is rejected.
+ * - `test_ihdr_invalid_dimensions` - Ensures that a zero width or height is rejected.
+ * - `test_ihdr_invalid_color_type` - Ensures that an undefined color type is rejected.
+ * - `test_ihdr_invalid_bit_depth` - Ensures that a bit depth not allowed for the color type is rejected.
+ * - `test_ihdr_invalid_methods` - Ensures that unknown compression, filter and interlace methods are rejected.
+ * - `test_png_ihdr` - Checks that `Png::ihdr` parses the header of a real image.
+ */
+#[cfg(test)]
+mod tests {
+    use imgmod::png::ihdr::{ColorType, Ihdr, IhdrError};
+    use imgmod::png::image::Png;
+
+    fn ihdr_bytes(bit_depth: u8, color_type: u8) -> Vec<u8> {
+        [
+            840u32.to_be_bytes().as_ref(),
+            654u32.to_be_bytes().as_ref(),
+            &[bit_depth, color_type, 0, 0, 0],
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_ihdr_from_bytes() {
+        let ihdr = Ihdr::try_from(ihdr_bytes(8, 6).as_ref()).unwrap();
+
+        assert_eq!(ihdr.width, 840);
+        assert_eq!(ihdr.height, 654);
+        assert_eq!(ihdr.bit_depth, 8);
+        assert_eq!(ihdr.color_type, ColorType::Rgba);
+        assert_eq!(ihdr.compression_method, 0);
+        assert_eq!(ihdr.filter_method, 0);
+        assert!(!ihdr.is_interlaced());
+    }
+
+    #[test]
+    fn test_ihdr_round_trip() {
+        let bytes = ihdr_bytes(4, 3);
+        let ihdr = Ihdr::try_from(bytes.as_ref()).unwrap();
+
+        assert_eq!(ihdr.as_bytes(), bytes);
+        assert_eq!(ihdr.to_chunk().chunk_type().to_string(), "IHDR");
+    }
+
+    #[test]
+    fn test_ihdr_invalid_length() {
+        let bytes = ihdr_bytes(8, 6);
+        let ihdr = Ihdr::try_from(&bytes[..12]);
+
+        assert!(matches!(ihdr, Err(IhdrError::InvalidLength(12))));
+    }
+
+    #[test]
+    fn test_ihdr_invalid_dimensions() {
+        let ihdr = Ihdr::new(0, 10, 8, ColorType::Rgb, false);
+
+        assert!(matches!(ihdr, Err(IhdrError::InvalidDimensions)));
+    }
+
+    #[test]
+    fn test_ihdr_invalid_color_type() {
+        let ihdr = Ihdr::try_from(ihdr_bytes(8, 5).as_ref());
+
+        assert!(matches!(ihdr, Err(IhdrError::InvalidColorType(5))));
+    }
+
+    #[test]
+    fn test_ihdr_invalid_bit_depth() {
+        assert!(Ihdr::try_from(ihdr_bytes(4, 2).as_ref()).is_err());
+        assert!(Ihdr::try_from(ihdr_bytes(16, 3).as_ref()).is_err());
+        assert!(Ihdr::try_from(ihdr_bytes(3, 0).as_ref()).is_err());
+        assert!(Ihdr::try_from(ihdr_bytes(1, 0).as_ref()).is_ok());
+    }
+
+    #[test]
+    fn test_ihdr_invalid_methods() {
+        let mut bytes = ihdr_bytes(8, 6);
+        bytes[10] = 1;
+        assert!(matches!(Ihdr::try_from(bytes.as_ref()), Err(IhdrError::InvalidCompressionMethod(1))));
+
+        let mut bytes = ihdr_bytes(8, 6);
+        bytes[11] = 1;
+        assert!(matches!(Ihdr::try_from(bytes.as_ref()), Err(IhdrError::InvalidFilterMethod(1))));
+
+        let mut bytes = ihdr_bytes(8, 6);
+        bytes[12] = 2;
+        assert!(matches!(Ihdr::try_from(bytes.as_ref()), Err(IhdrError::InvalidInterlaceMethod(2))));
+    }
+
+    #[test]
+    fn test_png_ihdr() {
+        let png = Png::from_file("./images/Gengar.png").unwrap();
+        let ihdr = png.ihdr().unwrap();
+
+        assert_eq!(ihdr.width, 754);
+        assert_eq!(ihdr.height, 776);
+        assert_eq!(ihdr.bit_depth, 8);
+        assert_eq!(ihdr.color_type, ColorType::Rgb);
+    }
+}
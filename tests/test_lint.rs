@@ -0,0 +1,176 @@
+/**
+ * Integration tests for the PNG structural validator.
+ *
+ * This module builds small PNGs chunk by chunk and checks that `lint` reports the ordering,
+ * duplication and chunk type rules from the PNG spec.
+ *
+ * Tests:
+ * - `test_lint_valid_png` - Ensures that a well-formed PNG produces no diagnostics.
+ * - `test_lint_real_image` - Ensures that the bundled images pass validation.
+ * - `test_lint_ihdr_not_first` - Checks that IHDR must be the first chunk.
+ * - `test_lint_iend_not_last` - Checks that IEND must be the last chunk.
+ * - `test_lint_idat_not_consecutive` - Checks that IDAT chunks must be consecutive.
+ * - `test_lint_plte_after_idat` - Checks that PLTE must come before IDAT.
+ * - `test_lint_duplicate_chunks` - Checks that single-instance chunks such as gAMA are not duplicated.
+ * - `test_lint_unknown_critical_chunk` - Checks that unknown critical chunks are reported.
+ * - `test_lint_reserved_bit` - Checks that chunk types with the reserved bit set are reported.
+ * - `test_lint_srgb_and_iccp` - Checks that sRGB together with iCCP is only a warning.
+ */
+#[cfg(test)]
+mod tests {
+    use imgmod::png::chunk::Chunk;
+    use imgmod::png::chunk_type::ChunkType;
+    use imgmod::png::ihdr::{ColorType, Ihdr};
+    use imgmod::png::image::Png;
+    use imgmod::png::lint::{has_errors, lint, Diagnostic, Severity};
+    use std::str::FromStr;
+
+    fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+    }
+
+    fn ihdr(color_type: ColorType) -> Chunk {
+        Ihdr::new(1, 1, 8, color_type, false).unwrap().to_chunk()
+    }
+
+    fn png_from(chunks: Vec<Chunk>) -> Png {
+        Png::from_chunks(chunks)
+    }
+
+    fn errors_for(diagnostics: &[Diagnostic], chunk_type: &str) -> usize {
+        diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error && d.chunk_type == chunk_type)
+            .count()
+    }
+
+    #[test]
+    fn test_lint_valid_png() {
+        let png = png_from(vec![
+            ihdr(ColorType::Rgb),
+            chunk("gAMA", &[0, 0, 177, 143]),
+            chunk("IDAT", &[1]),
+            chunk("IDAT", &[2]),
+            chunk("tEXt", b"Comment\0hi"),
+            chunk("IEND", &[]),
+        ]);
+
+        assert!(lint(&png).is_empty());
+    }
+
+    #[test]
+    fn test_lint_real_image() {
+        let png = Png::from_file("./images/Gengar.png").unwrap();
+        assert!(!has_errors(&lint(&png)));
+    }
+
+    #[test]
+    fn test_lint_ihdr_not_first() {
+        let png = png_from(vec![
+            chunk("gAMA", &[0, 0, 177, 143]),
+            ihdr(ColorType::Rgb),
+            chunk("IDAT", &[1]),
+            chunk("IEND", &[]),
+        ]);
+        let diagnostics = lint(&png);
+
+        assert!(has_errors(&diagnostics));
+        assert_eq!(errors_for(&diagnostics, "IHDR"), 1);
+    }
+
+    #[test]
+    fn test_lint_iend_not_last() {
+        let png = png_from(vec![
+            ihdr(ColorType::Rgb),
+            chunk("IDAT", &[1]),
+            chunk("IEND", &[]),
+            chunk("maTt", b"hidden"),
+        ]);
+
+        assert_eq!(errors_for(&lint(&png), "IEND"), 1);
+    }
+
+    #[test]
+    fn test_lint_idat_not_consecutive() {
+        let png = png_from(vec![
+            ihdr(ColorType::Rgb),
+            chunk("IDAT", &[1]),
+            chunk("maTt", b"hidden"),
+            chunk("IDAT", &[2]),
+            chunk("IEND", &[]),
+        ]);
+        let diagnostics = lint(&png);
+
+        assert_eq!(errors_for(&diagnostics, "maTt"), 1);
+        assert_eq!(diagnostics[0].chunk_index, Some(2));
+    }
+
+    #[test]
+    fn test_lint_plte_after_idat() {
+        let png = png_from(vec![
+            ihdr(ColorType::Indexed),
+            chunk("IDAT", &[1]),
+            chunk("PLTE", &[0, 0, 0]),
+            chunk("IEND", &[]),
+        ]);
+
+        assert_eq!(errors_for(&lint(&png), "PLTE"), 1);
+    }
+
+    #[test]
+    fn test_lint_duplicate_chunks() {
+        let png = png_from(vec![
+            ihdr(ColorType::Rgb),
+            chunk("gAMA", &[0, 0, 177, 143]),
+            chunk("gAMA", &[0, 0, 177, 143]),
+            chunk("IDAT", &[1]),
+            chunk("IEND", &[]),
+        ]);
+        let diagnostics = lint(&png);
+
+        assert_eq!(errors_for(&diagnostics, "gAMA"), 1);
+        assert_eq!(diagnostics[0].chunk_index, Some(2));
+    }
+
+    #[test]
+    fn test_lint_unknown_critical_chunk() {
+        let png = png_from(vec![
+            ihdr(ColorType::Rgb),
+            chunk("IDAT", &[1]),
+            chunk("MaTt", b"hidden"),
+            chunk("IEND", &[]),
+        ]);
+
+        assert_eq!(errors_for(&lint(&png), "MaTt"), 1);
+    }
+
+    #[test]
+    fn test_lint_reserved_bit() {
+        let png = png_from(vec![
+            ihdr(ColorType::Rgb),
+            chunk("IDAT", &[1]),
+            chunk("matt", b"hidden"),
+            chunk("IEND", &[]),
+        ]);
+        let diagnostics = lint(&png);
+
+        assert_eq!(errors_for(&diagnostics, "matt"), 1);
+        assert!(diagnostics[0].message.contains("reserved bit"));
+    }
+
+    #[test]
+    fn test_lint_srgb_and_iccp() {
+        let png = png_from(vec![
+            ihdr(ColorType::Rgb),
+            chunk("sRGB", &[0]),
+            chunk("iCCP", b"icc\0\0"),
+            chunk("IDAT", &[1]),
+            chunk("IEND", &[]),
+        ]);
+        let diagnostics = lint(&png);
+
+        assert!(!has_errors(&diagnostics));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+    }
+}
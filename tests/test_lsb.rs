@@ -0,0 +1,131 @@
+/**
+ * Unit tests for hiding payloads in the least significant bits of pixel samples.
+ *
+ * Tests:
+ * - `test_lsb_round_trip` - Checks that a payload embedded with the default options is extracted unchanged.
+ * - `test_lsb_capacity` - Checks the capacity for different bit counts and channel selections.
+ * - `test_lsb_sixteen_bit` - Round-trips a payload through the low bits of 16-bit samples.
+ * - `test_lsb_selected_channels` - Ensures that only the selected channels and bits are modified.
+ * - `test_lsb_default_order` - Ensures that a payload embedded without a key is not laid out in scanline order.
+ * - `test_lsb_keyed_order` - Ensures that a keyed payload is scattered and only found with the same key.
+ * - `test_lsb_payload_too_large` - Ensures that payloads that do not fit are rejected.
+ * - `test_lsb_invalid_options` - Ensures that indexed images, missing channels and bad bit counts are rejected.
+ */
+#[cfg(test)]
+mod tests {
+    use imgmod::png::ihdr::ColorType;
+    use imgmod::png::pixels::{PixelBuffer, Samples};
+    use imgmod::stego::lsb::{capacity, embed, extract, Channel, LsbError, LsbOptions};
+
+    fn test_pixels(width: u32, height: u32, color_type: ColorType, bit_depth: u8) -> PixelBuffer {
+        let count = (width * height) as usize * color_type.channels();
+        let samples = if bit_depth == 16 {
+            Samples::Sixteen((0..count).map(|i| (i * 2477) as u16).collect())
+        } else {
+            let max = (1u32 << bit_depth) - 1;
+            Samples::Eight((0..count).map(|i| ((i * 37) as u32 & max) as u8).collect())
+        };
+        PixelBuffer::new(width, height, color_type, bit_depth, samples).unwrap()
+    }
+
+    fn options(bits_per_channel: u8, channels: Vec<Channel>) -> LsbOptions {
+        LsbOptions { bits_per_channel, channels, key: None }
+    }
+
+    #[test]
+    fn test_lsb_round_trip() {
+        let mut pixels = test_pixels(16, 16, ColorType::Rgb, 8);
+        let original = pixels.clone();
+        let options = LsbOptions::default();
+
+        embed(&mut pixels, b"Hello Matt!", &options).unwrap();
+
+        assert_ne!(pixels, original);
+        assert_eq!(extract(&pixels, &options).unwrap(), b"Hello Matt!");
+    }
+
+    #[test]
+    fn test_lsb_capacity() {
+        let rgba = test_pixels(11, 9, ColorType::Rgba, 8);
+
+        // 99 pixels * 3 color channels, less the 32-bit length prefix
+        assert_eq!(capacity(&rgba, &LsbOptions::default()).unwrap(), (99 * 3 - 32) / 8);
+        assert_eq!(capacity(&rgba, &options(2, vec![Channel::Red, Channel::Alpha])).unwrap(), (99 * 4 - 32) / 8);
+        assert_eq!(capacity(&test_pixels(2, 2, ColorType::Grayscale, 1), &LsbOptions::default()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_lsb_sixteen_bit() {
+        let mut pixels = test_pixels(20, 20, ColorType::GrayscaleAlpha, 16);
+        let options = options(12, vec![Channel::Gray, Channel::Alpha]);
+        let payload: Vec<u8> = (0..=255).collect();
+
+        embed(&mut pixels, &payload, &options).unwrap();
+        assert_eq!(extract(&pixels, &options).unwrap(), payload);
+    }
+
+    #[test]
+    fn test_lsb_selected_channels() {
+        let mut pixels = test_pixels(16, 16, ColorType::Rgba, 8);
+        let original = pixels.clone();
+
+        embed(&mut pixels, &[0xff; 20], &options(2, vec![Channel::Blue])).unwrap();
+
+        for y in 0..16 {
+            for x in 0..16 {
+                let (before, after) = (original.pixel(x, y), pixels.pixel(x, y));
+                assert_eq!([before[0], before[1], before[3]], [after[0], after[1], after[3]]);
+                assert_eq!(before[2] >> 2, after[2] >> 2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lsb_default_order() {
+        let mut pixels = test_pixels(32, 32, ColorType::Rgb, 8);
+        let original = pixels.clone();
+
+        // 24 bytes with the length prefix fill the first 64 pixels in scanline order
+        embed(&mut pixels, &[0xa5; 20], &LsbOptions::default()).unwrap();
+
+        let changed: Vec<u32> = (0..32 * 32).filter(|&i| original.pixel(i % 32, i / 32) != pixels.pixel(i % 32, i / 32)).collect();
+        assert!(changed.iter().any(|&i| i >= 64));
+        assert_eq!(extract(&pixels, &LsbOptions::default()).unwrap(), [0xa5; 20]);
+    }
+
+    #[test]
+    fn test_lsb_keyed_order() {
+        let mut pixels = test_pixels(32, 32, ColorType::Rgb, 8);
+        let keyed = LsbOptions { key: Some(b"hunter2".to_vec()), ..LsbOptions::default() };
+
+        embed(&mut pixels, b"Hello Matt!", &keyed).unwrap();
+
+        assert_eq!(extract(&pixels, &keyed).unwrap(), b"Hello Matt!");
+        match extract(&pixels, &LsbOptions::default()) {
+            Ok(payload) => assert_ne!(payload, b"Hello Matt!"),
+            Err(e) => assert!(matches!(e, LsbError::InvalidLength { .. })),
+        }
+    }
+
+    #[test]
+    fn test_lsb_payload_too_large() {
+        let mut pixels = test_pixels(11, 9, ColorType::Rgb, 8);
+        let original = pixels.clone();
+        let result = embed(&mut pixels, &[0; 34], &LsbOptions::default());
+
+        assert!(matches!(result, Err(LsbError::PayloadTooLarge { needed: 34, capacity: 33 })));
+        assert_eq!(pixels, original);
+        assert!(embed(&mut pixels, &[0; 33], &LsbOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_lsb_invalid_options() {
+        let indexed = test_pixels(8, 8, ColorType::Indexed, 8);
+        let gray = test_pixels(8, 8, ColorType::Grayscale, 4);
+
+        assert!(matches!(capacity(&indexed, &LsbOptions::default()), Err(LsbError::UnsupportedColorType(_))));
+        assert!(matches!(capacity(&gray, &options(1, vec![Channel::Red])), Err(LsbError::MissingChannel(Channel::Red))));
+        assert!(matches!(capacity(&gray, &options(5, vec![])), Err(LsbError::InvalidBitsPerChannel(5))));
+        assert!(matches!(capacity(&gray, &options(0, vec![])), Err(LsbError::InvalidBitsPerChannel(0))));
+    }
+}
@@ -0,0 +1,254 @@
+mod common;
+
+/**
+ * Integration tests for the structured output of `--format json` and `--format yaml`.
+ *
+ * Tests:
+ * - `test_json_rendering` - Checks JSON indentation, escaping, empty containers and numbers.
+ * - `test_yaml_rendering` - Checks YAML block style for nested objects and arrays, and quoted strings.
+ * - `test_yaml_scalar` - Checks that a bare scalar renders as a one-line YAML document.
+ * - `test_print_json` - Checks the chunk fields printed by `print --format json`.
+ * - `test_info_yaml` - Checks that `info --format yaml` includes the parsed IHDR and decoded text chunks.
+ * - `test_lint_json` - Checks that lint diagnostics are printed as JSON and the exit code still reflects errors.
+ * - `test_decode_json` - Checks the message fields printed by `decode --format json`.
+ * - `test_text_json` - Checks the text entries printed by `text list` and `text get` with `--format json`.
+ * - `test_batch_document` - Checks that several files print one JSON array or YAML sequence.
+ */
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use assert_cmd::Command;
+    use tempfile::tempdir;
+    use imgmod::img_cli::error;
+    use imgmod::img_cli::output::Value;
+    use imgmod::png::chunk::Chunk;
+    use imgmod::png::chunk_type::ChunkType;
+    use imgmod::png::image::Png;
+    use crate::common::testing_file;
+
+    fn testing_value() -> Value {
+        Value::object(vec![
+            ("name", "say \"hi\"\n".into()),
+            ("count", 3usize.into()),
+            ("ratio", 0.25.into()),
+            ("missing", Value::Null),
+            ("empty", Value::Array(Vec::new())),
+            (
+                "items",
+                Value::Array(vec![
+                    Value::object(vec![("index", 0usize.into()), ("ok", true.into())]),
+                    "plain".into(),
+                ]),
+            ),
+            ("nested", Value::object(vec![("inner", Value::object(Vec::<(&str, Value)>::new()))])),
+        ])
+    }
+
+    fn stdout(args: &[&str]) -> String {
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(args);
+        String::from_utf8(cmd.assert().get_output().stdout.clone()).unwrap()
+    }
+
+    #[test]
+    fn test_json_rendering() {
+        assert_eq!(
+            testing_value().to_json(),
+r#"{
+  "name": "say \"hi\"\n",
+  "count": 3,
+  "ratio": 0.25,
+  "missing": null,
+  "empty": [],
+  "items": [
+    {
+      "index": 0,
+      "ok": true
+    },
+    "plain"
+  ],
+  "nested": {
+    "inner": {}
+  }
+}"#
+        );
+    }
+
+    #[test]
+    fn test_yaml_rendering() {
+        assert_eq!(
+            testing_value().to_yaml(),
+r#"name: "say \"hi\"\n"
+count: 3
+ratio: 0.25
+missing: null
+empty: []
+items:
+  - index: 0
+    ok: true
+  - "plain"
+nested:
+  inner: {}
+"#
+        );
+
+        let nested = Value::Array(vec![Value::Array(vec![1usize.into(), 2usize.into()])]);
+        assert_eq!(nested.to_yaml(), "- - 1\n  - 2\n");
+    }
+
+    #[test]
+    fn test_yaml_scalar() {
+        assert_eq!(Value::from("text").to_yaml(), "\"text\"\n");
+        assert_eq!(Value::Array(Vec::new()).to_yaml(), "[]\n");
+    }
+
+    #[test]
+    fn test_print_json() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = testing_file(&dir, "output.png");
+
+        let output = stdout(&["print", &file, "--format", "json"]);
+        assert!(output.starts_with(&format!("{{\n  \"file\": \"{}\",\n  \"size\": 261999,\n", file)));
+        assert!(output.contains(
+r#"      "index": 1,
+      "chunk_type": "IDAT",
+      "offset": 33,
+      "length": 261942,
+"#));
+        assert!(output.contains(r#""crc_valid": true,"#));
+        assert!(output.contains(r#""width": 840,"#));
+        assert!(output.ends_with("  \"corrupted_chunks\": []\n}\n"));
+    }
+
+    #[test]
+    fn test_info_yaml() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = testing_file(&dir, "output.png");
+
+        let mut png = Png::from_file(&file).unwrap();
+        png.insert_before("IEND", Chunk::from_strings("tEXt", "Title\0Test image").unwrap()).unwrap();
+        png.write_file(&file).unwrap();
+
+        let output = stdout(&["--format", "yaml", "info", &file]);
+        assert!(output.starts_with(&format!("file: \"{}\"\nsize: 262027\nihdr:\n  width: 840\n  height: 654\n", file)));
+        assert!(output.contains(
+r#"  - index: 2
+    chunk_type: "tEXt"
+    offset: 261987
+    length: 16
+"#));
+        assert!(output.contains(
+r#"    fields:
+      keyword: "Title"
+      text: "Test image"
+      compressed: false
+      language_tag: null
+      translated_keyword: null
+"#));
+    }
+
+    #[test]
+    fn test_lint_json() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = testing_file(&dir, "output.png");
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["lint", &file, "--format", "json"]);
+        cmd.assert().success().stdout(format!(
+"{{
+  \"file\": \"{}\",
+  \"valid\": true,
+  \"errors\": 0,
+  \"warnings\": 0,
+  \"diagnostics\": []
+}}
+", file));
+
+        let mut png = Png::from_file(&file).unwrap();
+        png.append_chunk(Chunk::new(ChunkType::from_str("ruSt").unwrap(), Vec::new()));
+        png.write_file(&file).unwrap();
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["lint", &file, "--format", "json"]);
+        let assert = cmd.assert().code(error::EXIT_VALIDATION);
+        let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+        assert!(output.contains(
+r#"    {
+      "severity": "error",
+      "chunk_index": 2,
+      "chunk_type": "IEND",
+      "message": "IEND must be the last chunk"
+    }"#));
+    }
+
+    #[test]
+    fn test_decode_json() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = testing_file(&dir, "output.png");
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["encode", &file, "ruSt", "Hello Matt!"]);
+        cmd.assert().success();
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["decode", &file, "ruSt", "--format", "json"]);
+        cmd.assert().success().stdout(format!(
+"{{
+  \"file\": \"{}\",
+  \"mode\": \"chunk\",
+  \"chunk_type\": \"ruSt\",
+  \"capacity\": null,
+  \"encrypted\": false,
+  \"length\": 11,
+  \"utf8\": true,
+  \"message\": \"Hello Matt!\",
+  \"hex\": \"48656c6c6f204d61747421\",
+  \"output_file\": null
+}}
+", file));
+    }
+
+    #[test]
+    fn test_text_json() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file = testing_file(&dir, "output.png");
+
+        let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+        cmd.args(["text", "set", &file, "-k", "Comment", "-t", "Hello Matt!", "--kind", "itxt", "--language", "en"]);
+        cmd.assert().success();
+
+        let entry = "{
+    \"keyword\": \"Comment\",
+    \"text\": \"Hello Matt!\",
+    \"compressed\": false,
+    \"language_tag\": \"en\",
+    \"translated_keyword\": \"\"
+  }";
+        let list = stdout(&["text", "list", &file, "--format", "json"]);
+        assert_eq!(list, format!("{{\n  \"file\": \"{}\",\n  \"entries\": [\n    {}\n  ]\n}}\n", file, entry.replace("\n", "\n  ")));
+        let get = stdout(&["text", "get", &file, "-k", "Comment", "--format", "json"]);
+        assert_eq!(get, format!("{{\n  \"file\": \"{}\",\n  \"entry\": {}\n}}\n", file, entry));
+    }
+
+    #[test]
+    fn test_batch_document() {
+        let dir = tempdir().unwrap();
+        let file = testing_file(&dir, "output.png");
+        let other = dir.path().join("other.png");
+        std::fs::copy("./images/test_image.png", &other).unwrap();
+        let other = other.to_str().unwrap();
+
+        let json = stdout(&["info", &file, other, "--format", "json"]);
+        assert!(json.starts_with("[\n  {\n    \"file\": "));
+        assert!(json.ends_with("  }\n]\n"));
+        assert_eq!(json.matches("\n    \"file\": ").count(), 2);
+        assert!(json.find(&file).unwrap() < json.find(other).unwrap());
+
+        let yaml = stdout(&["info", &file, other, "--format", "yaml"]);
+        assert!(yaml.starts_with("- file: "));
+        assert_eq!(yaml.lines().filter(|line| line.starts_with("- file: ")).count(), 2);
+
+        // A single file still prints its document on its own
+        assert!(stdout(&["info", &file, "--format", "json"]).starts_with("{\n  \"file\": "));
+    }
+}
@@ -0,0 +1,221 @@
+mod common;
+
+/**
+ * Tests for PLTE and tRNS palettes and the palette commands.
+ *
+ * Tests:
+ * - `test_palette_entries` - Reads PLTE colors with their tRNS alpha, opaque past the end of tRNS.
+ * - `test_palette_checks` - Checks the PLTE length, the entry limit of the bit depth, tRNS length and color types without a palette.
+ * - `test_trns_data` - Checks that tRNS is cut after the last transparent entry and left out when every entry is opaque.
+ * - `test_set_palette` - Replaces PLTE and tRNS in place and adds or drops tRNS, leaving the image data alone.
+ * - `test_recolor` - Recolors by index and by color, swapping colors and keeping alpha unless one is given.
+ * - `test_parse_colors` - Parses colors and FROM=TO mappings.
+ * - `test_gpl` - Renders a palette as a GIMP palette file.
+ * - `test_palette_cli` - Runs palette list (as text and JSON), export and recolor.
+ */
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::str::FromStr;
+    use assert_cmd::Command;
+    use tempfile::tempdir;
+    use imgmod::img_cli::error;
+    use imgmod::png::encoder::EncoderOptions;
+    use imgmod::png::ihdr::{ColorType, Ihdr};
+    use imgmod::png::image::{Png, PngError};
+    use imgmod::png::palette::*;
+    use imgmod::png::pixels::{PixelBuffer, Samples};
+    use crate::common::{chunk_types, indexed_png};
+
+    fn entry(red: u8, green: u8, blue: u8, alpha: u8) -> PaletteEntry {
+        PaletteEntry { red, green, blue, alpha }
+    }
+
+    fn ihdr(color_type: ColorType, bit_depth: u8) -> Ihdr {
+        Ihdr::new(4, 1, bit_depth, color_type, false).unwrap()
+    }
+
+    #[test]
+    fn test_palette_entries() {
+        let palette = indexed_png().palette().unwrap().unwrap();
+        assert_eq!(palette.entries, [entry(0, 0, 0, 255), entry(255, 0, 0, 255), entry(0, 255, 0, 128), entry(0, 0, 255, 255)]);
+        assert_eq!(palette.entries[2].to_string(), "#00ff0080");
+        assert_eq!(palette.entries[3].to_string(), "#0000ff");
+
+        // tRNS is a single transparent color outside indexed images, not palette alpha
+        let palette = Palette::from_data(&[1, 2, 3], Some(&[0, 0, 0, 0, 0, 0]), &ihdr(ColorType::Rgb, 8)).unwrap();
+        assert_eq!(palette.entries, [entry(1, 2, 3, 255)]);
+
+        assert_eq!(Png::from_file("./images/test_image.png").unwrap().palette().unwrap(), None);
+    }
+
+    #[test]
+    fn test_palette_checks() {
+        let indexed = ihdr(ColorType::Indexed, 1);
+        assert!(matches!(Palette::from_data(&[], None, &indexed), Err(PaletteError::InvalidLength(0))));
+        assert!(matches!(Palette::from_data(&[0; 4], None, &indexed), Err(PaletteError::InvalidLength(4))));
+        assert!(matches!(
+            Palette::from_data(&[0; 9], None, &indexed),
+            Err(PaletteError::TooManyEntries { entries: 3, max: 2 })
+        ));
+        assert!(matches!(
+            Palette::from_data(&[0; 6], Some(&[0, 0, 0]), &indexed),
+            Err(PaletteError::TransparencyTooLong { alpha_entries: 3, entries: 2 })
+        ));
+        assert!(Palette::from_data(&[0; 768], None, &ihdr(ColorType::Indexed, 8)).is_ok());
+        assert!(Palette::from_data(&[0; 771], None, &ihdr(ColorType::Rgba, 8)).is_err());
+        assert!(matches!(
+            Palette::from_data(&[0; 3], None, &ihdr(ColorType::Grayscale, 8)),
+            Err(PaletteError::NotAllowed(ColorType::Grayscale))
+        ));
+
+        let transparent = Palette { entries: vec![entry(0, 0, 0, 0)] };
+        assert!(matches!(transparent.check(&ihdr(ColorType::Rgb, 8)), Err(PaletteError::TransparencyNotAllowed(_))));
+        assert!(transparent.check(&ihdr(ColorType::Indexed, 8)).is_ok());
+    }
+
+    #[test]
+    fn test_trns_data() {
+        let mut palette = Palette { entries: vec![entry(0, 0, 0, 0), entry(1, 1, 1, 255), entry(2, 2, 2, 10), entry(3, 3, 3, 255)] };
+        assert_eq!(palette.trns_data(), Some(vec![0, 255, 10]));
+        assert_eq!(palette.to_plte().data(), [0, 0, 0, 1, 1, 1, 2, 2, 2, 3, 3, 3]);
+
+        palette.entries.iter_mut().for_each(|entry| entry.alpha = 255);
+        assert_eq!(palette.trns_data(), None);
+        assert!(palette.to_trns().is_none());
+    }
+
+    #[test]
+    fn test_set_palette() {
+        let mut png = indexed_png();
+        let image_data = png.image_data();
+        let mut palette = png.palette().unwrap().unwrap();
+
+        palette.entries[3].alpha = 0;
+        png.set_palette(&palette).unwrap();
+        assert_eq!(chunk_types(&png), ["IHDR", "PLTE", "tRNS", "IDAT", "IEND"]);
+        assert_eq!(png.chunk_by_type("tRNS").unwrap().data(), [255, 255, 128, 0]);
+        assert_eq!(png.palette().unwrap().unwrap(), palette);
+
+        // Opaque palettes need no tRNS
+        palette.entries.iter_mut().for_each(|entry| entry.alpha = 255);
+        png.set_palette(&palette).unwrap();
+        assert_eq!(chunk_types(&png), ["IHDR", "PLTE", "IDAT", "IEND"]);
+
+        palette.entries[0].alpha = 7;
+        png.set_palette(&palette).unwrap();
+        assert_eq!(chunk_types(&png), ["IHDR", "PLTE", "tRNS", "IDAT", "IEND"]);
+        assert_eq!(png.image_data(), image_data);
+
+        // A palette too big for 2-bit indices is rejected
+        palette.entries.push(entry(9, 9, 9, 255));
+        assert!(matches!(png.set_palette(&palette), Err(PngError::Palette(PaletteError::TooManyEntries { .. }))));
+
+        // A new PLTE goes before the image data
+        let pixels = PixelBuffer::new(4, 1, ColorType::Indexed, 2, Samples::Eight(vec![0, 1, 0, 1])).unwrap();
+        let mut png = Png::from_pixels(&pixels, &EncoderOptions::default()).unwrap();
+        png.set_palette(&Palette { entries: vec![entry(0, 0, 0, 0), entry(1, 1, 1, 255)] }).unwrap();
+        assert_eq!(chunk_types(&png), ["IHDR", "PLTE", "tRNS", "IDAT", "IEND"]);
+    }
+
+    #[test]
+    fn test_recolor() {
+        let mut palette = indexed_png().palette().unwrap().unwrap();
+        let mappings: Vec<PaletteMapping> = ["#ff0000=#0000ff", "#0000ff=#ff0000", "2=#ffffff", "0=#000000"]
+            .iter()
+            .map(|mapping| mapping.parse().unwrap())
+            .collect();
+
+        assert_eq!(palette.recolor(&mappings).unwrap(), 3);
+        assert_eq!(palette.entries, [entry(0, 0, 0, 255), entry(0, 0, 255, 255), entry(255, 255, 255, 128), entry(255, 0, 0, 255)]);
+
+        // A color with an alpha only matches entries with that alpha, and sets it
+        let mappings = [PaletteMapping::from_str("#ffffff80=#00000000").unwrap(), PaletteMapping::from_str("#000000ff=#123456").unwrap()];
+        assert_eq!(palette.recolor(&mappings).unwrap(), 2);
+        assert_eq!(palette.entries[0], entry(0x12, 0x34, 0x56, 255));
+        assert_eq!(palette.entries[2], entry(0, 0, 0, 0));
+
+        let before = palette.clone();
+        assert!(matches!(
+            palette.recolor(&[PaletteMapping::from_str("4=#ffffff").unwrap()]),
+            Err(PaletteError::IndexOutOfRange { index: 4, entries: 4 })
+        ));
+        assert_eq!(palette.recolor(&[PaletteMapping::from_str("#abcdef=#ffffff").unwrap()]).unwrap(), 0);
+        assert_eq!(palette, before);
+    }
+
+    #[test]
+    fn test_parse_colors() {
+        assert_eq!(Color::from_str("#FF8800").unwrap(), Color { red: 255, green: 136, blue: 0, alpha: None });
+        assert_eq!(Color::from_str("#ff880080").unwrap(), Color { red: 255, green: 136, blue: 0, alpha: Some(128) });
+        assert!(matches!(Color::from_str("ff8800"), Err(PaletteError::InvalidColor(_))));
+        assert!(matches!(Color::from_str("#+f+f+f"), Err(PaletteError::InvalidColor(_))));
+        assert!(matches!(Color::from_str("#f80"), Err(PaletteError::InvalidColor(_))));
+        assert!(matches!(Color::from_str("#gg0000"), Err(PaletteError::InvalidColor(_))));
+        assert!(matches!(Color::from_str("#ff00é"), Err(PaletteError::InvalidColor(_))));
+
+        assert_eq!(PaletteSelector::from_str("12").unwrap(), PaletteSelector::Index(12));
+        assert!(matches!(PaletteSelector::from_str("#000000").unwrap(), PaletteSelector::Color(_)));
+        for selector in ["000000", "112233", "ff0000", "007", "+3"] {
+            assert!(matches!(PaletteSelector::from_str(selector), Err(PaletteError::InvalidSelector(_))));
+        }
+        assert!(matches!(PaletteMapping::from_str("3#ff0000"), Err(PaletteError::InvalidMapping(_))));
+        assert!(matches!(PaletteMapping::from_str("3=red"), Err(PaletteError::InvalidColor(_))));
+    }
+
+    #[test]
+    fn test_gpl() {
+        let palette = Palette { entries: vec![entry(0, 0, 0, 255), entry(255, 128, 7, 64)] };
+        assert_eq!(
+            palette.to_gpl("icon"),
+            "GIMP Palette\nName: icon\nColumns: 16\n#\n  0   0   0\tIndex 0\n255 128   7\tIndex 1 (alpha 64)\n"
+        );
+    }
+
+    #[test]
+    fn test_palette_cli() {
+        let dir = tempdir().expect("Error creating temporary directory");
+        let file_path = dir.path().join("icon.png");
+        indexed_png().write_file(&file_path).unwrap();
+        let file = file_path.to_str().unwrap();
+        let run = |args: &[&str]| {
+            let mut cmd = Command::cargo_bin("ImgMod").unwrap();
+            cmd.arg("palette").args(args);
+            cmd.assert()
+        };
+
+        run(&["list", file]).success().stdout(format!(
+            "File: {}, Palette entries: 4\n  entry#0{{ #000000}}\n  entry#1{{ #ff0000}}\n  entry#2{{ #00ff0080}}\n  entry#3{{ #0000ff}}\n",
+            file
+        ));
+
+        let output = run(&["list", file, "--format", "json"]).success();
+        let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+        assert!(stdout.contains("\"color_type\": 3,"));
+        assert!(stdout.contains("\"bit_depth\": 2,"));
+        assert!(stdout.contains("\"alpha\": 128,"));
+        assert!(stdout.contains("\"hex\": \"#00ff0080\""));
+
+        let gpl = dir.path().join("icon.gpl");
+        run(&["export", file, "-o", gpl.to_str().unwrap()]).success().stdout(format!("Wrote 4 palette entries to {}\n", gpl.display()));
+        assert!(fs::read_to_string(&gpl).unwrap().starts_with("GIMP Palette\nName: icon\n"));
+        run(&["export", file, "-o", "-"]).success().stdout(indexed_png().palette().unwrap().unwrap().to_gpl("icon"));
+
+        let image_data = indexed_png().image_data();
+        run(&["recolor", file, "#ff0000=#00ffff", "3=#ffffff00"]).success().stdout("Recolored 2 of 4 palette entries\n");
+        let png = Png::from_file(&file_path).unwrap();
+        assert_eq!(png.palette().unwrap().unwrap().entries[1], entry(0, 255, 255, 255));
+        assert_eq!(png.chunk_by_type("tRNS").unwrap().data(), [255, 255, 128, 0]);
+        assert_eq!(png.image_data(), image_data);
+
+        let original = fs::read(&file_path).unwrap();
+        run(&["recolor", file, "#abcdef=#000000"]).success().stdout("Recolored 0 of 4 palette entries\n");
+        run(&["recolor", file, "9=#000000"]).code(error::EXIT_NOT_FOUND);
+        run(&["recolor", file, "1=blue"]).code(error::EXIT_USAGE);
+        assert_eq!(fs::read(&file_path).unwrap(), original);
+
+        let rgba = dir.path().join("rgba.png");
+        fs::copy("./images/test_image.png", &rgba).unwrap();
+        run(&["list", rgba.to_str().unwrap()]).code(error::EXIT_NOT_FOUND);
+    }
+}
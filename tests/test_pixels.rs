@@ -0,0 +1,209 @@
+/**
+ * Integration tests for decoding PNG image data into pixels.
+ *
+ * Reference images are produced by the `png` crate (every color type and bit depth, with adaptive
+ * filtering so all five scanline filters are exercised) and by hand for Adam7-interlaced images,
+ * then decoded with `Png::pixels` and compared sample by sample.
+ *
+ * Helper Functions:
+ * - `test_samples()`: Returns deterministic pseudo-random samples that fit in a bit depth.
+ * - `pack_row()`: Packs one row of samples into scanline bytes.
+ * - `reference_png()`: Encodes samples with the `png` crate and parses the result into a `Png`.
+ * - `interlaced_png()`: Builds an Adam7-interlaced PNG with unfiltered scanlines.
+ *
+ * Test Cases:
+ * - `test_decode_all_color_types()`: Decodes every legal color type and bit depth combination.
+ * - `test_decode_interlaced()`: Decodes Adam7-interlaced images at sub-byte, 8 and 16-bit depths.
+ * - `test_decode_real_images()`: Compares the bundled images against the `image` crate's decoder.
+ * - `test_pixel_accessors()`: Checks `sample`, `set_sample` and `pixel`.
+ * - `test_decode_invalid_filter()`: Ensures that an unknown filter type byte is rejected.
+ * - `test_decode_truncated_data()`: Ensures that missing image data is reported.
+ */
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use imgmod::png::chunk::Chunk;
+    use imgmod::png::chunk_type::ChunkType;
+    use imgmod::png::ihdr::{ColorType, Ihdr};
+    use imgmod::png::image::{Png, PngError};
+    use imgmod::png::pixels::{PixelBuffer, PixelError, Samples, ADAM7_PASSES};
+    use std::str::FromStr;
+
+    const WIDTH: u32 = 13;
+    const HEIGHT: u32 = 7;
+
+    fn test_samples(count: usize, bit_depth: u8) -> Vec<u16> {
+        let mut state: u32 = 0x1234_5678;
+        (0..count)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                let max = ((1u32 << bit_depth) - 1) as u16;
+                (state >> 8) as u16 & max
+            })
+            .collect()
+    }
+
+    fn pack_row(samples: &[u16], bit_depth: u8) -> Vec<u8> {
+        match bit_depth {
+            16 => samples.iter().flat_map(|s| s.to_be_bytes()).collect(),
+            8 => samples.iter().map(|&s| s as u8).collect(),
+            _ => {
+                let mut out = vec![0u8; (samples.len() * bit_depth as usize).div_ceil(8)];
+                for (i, &s) in samples.iter().enumerate() {
+                    let bit = i * bit_depth as usize;
+                    out[bit / 8] |= (s as u8) << (8 - bit_depth as usize - bit % 8);
+                }
+                out
+            }
+        }
+    }
+
+    fn to_png_color(color_type: ColorType) -> png::ColorType {
+        match color_type {
+            ColorType::Grayscale => png::ColorType::Grayscale,
+            ColorType::Rgb => png::ColorType::Rgb,
+            ColorType::Indexed => png::ColorType::Indexed,
+            ColorType::GrayscaleAlpha => png::ColorType::GrayscaleAlpha,
+            ColorType::Rgba => png::ColorType::Rgba,
+        }
+    }
+
+    fn reference_png(color_type: ColorType, bit_depth: u8, samples: &[u16]) -> Png {
+        let row_len = WIDTH as usize * color_type.channels();
+        let data: Vec<u8> = samples
+            .chunks(row_len)
+            .flat_map(|row| pack_row(row, bit_depth))
+            .collect();
+
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, WIDTH, HEIGHT);
+            encoder.set_color(to_png_color(color_type));
+            encoder.set_depth(png::BitDepth::from_u8(bit_depth).unwrap());
+            encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive);
+            if color_type == ColorType::Indexed {
+                encoder.set_palette(vec![0u8; 3 << bit_depth]);
+            }
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&data).unwrap();
+        }
+
+        Png::try_from(bytes.as_ref()).unwrap()
+    }
+
+    fn interlaced_png(color_type: ColorType, bit_depth: u8, samples: &[u16]) -> Png {
+        let channels = color_type.channels();
+        let mut raw = Vec::new();
+
+        for (x0, y0, dx, dy) in ADAM7_PASSES {
+            for y in (y0..HEIGHT as usize).step_by(dy) {
+                let row: Vec<u16> = (x0..WIDTH as usize)
+                    .step_by(dx)
+                    .flat_map(|x| {
+                        let start = (y * WIDTH as usize + x) * channels;
+                        samples[start..start + channels].to_vec()
+                    })
+                    .collect();
+                if row.is_empty() {
+                    continue;
+                }
+                raw.push(0);
+                raw.extend(pack_row(&row, bit_depth));
+            }
+        }
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&raw).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let ihdr = Ihdr::new(WIDTH, HEIGHT, bit_depth, color_type, true).unwrap();
+        Png::from_chunks(vec![
+            ihdr.to_chunk(),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), compressed),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]),
+        ])
+    }
+
+    fn samples_as_u16(pixels: &PixelBuffer) -> Vec<u16> {
+        match pixels.samples() {
+            Samples::Eight(s) => s.iter().map(|&v| v as u16).collect(),
+            Samples::Sixteen(s) => s.clone(),
+        }
+    }
+
+    #[test]
+    fn test_decode_all_color_types() {
+        for color_type in [ColorType::Grayscale, ColorType::Rgb, ColorType::Indexed, ColorType::GrayscaleAlpha, ColorType::Rgba] {
+            for &bit_depth in color_type.allowed_bit_depths() {
+                let count = (WIDTH * HEIGHT) as usize * color_type.channels();
+                let samples = test_samples(count, bit_depth);
+                let pixels = reference_png(color_type, bit_depth, &samples).pixels().unwrap();
+
+                assert_eq!(pixels.width(), WIDTH);
+                assert_eq!(pixels.height(), HEIGHT);
+                assert_eq!(pixels.color_type(), color_type);
+                assert_eq!(pixels.bit_depth(), bit_depth);
+                assert_eq!(samples_as_u16(&pixels), samples, "{:?} at {} bits", color_type, bit_depth);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_interlaced() {
+        for (color_type, bit_depth) in [(ColorType::Grayscale, 2), (ColorType::Rgb, 8), (ColorType::Rgba, 16), (ColorType::Indexed, 4)] {
+            let count = (WIDTH * HEIGHT) as usize * color_type.channels();
+            let samples = test_samples(count, bit_depth);
+            let pixels = interlaced_png(color_type, bit_depth, &samples).pixels().unwrap();
+
+            assert_eq!(samples_as_u16(&pixels), samples, "{:?} at {} bits", color_type, bit_depth);
+        }
+    }
+
+    #[test]
+    fn test_decode_real_images() {
+        let pixels = Png::from_file("./images/test_image.png").unwrap().pixels().unwrap();
+        let expected = image::open("./images/test_image.png").unwrap().to_rgba8();
+        assert_eq!(pixels.samples(), &Samples::Eight(expected.into_raw()));
+
+        let pixels = Png::from_file("./images/Gengar.png").unwrap().pixels().unwrap();
+        let expected = image::open("./images/Gengar.png").unwrap().to_rgb8();
+        assert_eq!(pixels.samples(), &Samples::Eight(expected.into_raw()));
+    }
+
+    #[test]
+    fn test_pixel_accessors() {
+        let samples = test_samples((WIDTH * HEIGHT) as usize * 3, 8);
+        let mut pixels = reference_png(ColorType::Rgb, 8, &samples).pixels().unwrap();
+
+        let idx = (2 * WIDTH as usize + 5) * 3;
+        assert_eq!(pixels.pixel(5, 2), samples[idx..idx + 3].to_vec());
+
+        pixels.set_sample(5, 2, 1, 300);
+        assert_eq!(pixels.sample(5, 2, 1), 300 & 0xff);
+    }
+
+    #[test]
+    fn test_decode_invalid_filter() {
+        let ihdr = Ihdr::new(1, 1, 8, ColorType::Grayscale, false).unwrap();
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[9, 0]).unwrap();
+
+        let result = PixelBuffer::decode(&ihdr, &encoder.finish().unwrap());
+        assert!(matches!(result, Err(PixelError::InvalidFilterType(9))));
+    }
+
+    #[test]
+    fn test_decode_truncated_data() {
+        let ihdr = Ihdr::new(4, 4, 8, ColorType::Rgb, false).unwrap();
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[0; 20]).unwrap();
+
+        let result = PixelBuffer::decode(&ihdr, &encoder.finish().unwrap());
+        assert!(matches!(result, Err(PixelError::TruncatedImageData { expected: 52, actual: 20 })));
+
+        let png = Png::from_chunks(vec![ihdr.to_chunk()]);
+        assert!(matches!(png.pixels(), Err(PngError::Pixel(PixelError::NoImageData))));
+    }
+}
@@ -27,15 +27,24 @@
  * - `test_chunk_by_type()`: Checks that a chunk can be correctly retrieved by its type.
  * - `test_append_chunk()`: Tests that appending a chunk to a PNG works as expected.
  * - `test_remove_first_chunk()`: Tests that removing the first chunk of a given type works correctly.
+ * - `test_insert_chunk()`: Tests that a chunk is inserted at the given index and out-of-range indices fail.
+ * - `test_insert_before()`: Tests that a chunk is inserted right before the first chunk of a given type.
+ * - `test_insert_after()`: Tests that a chunk is inserted right after the first chunk of a given type.
+ * - `test_retain()`: Tests that only matching chunks are kept and the removed ones are returned in order.
+ * - `test_chunks_by_type()`: Checks that every chunk of a type is returned in order, not just the first.
+ * - `test_chunk_at()`: Tests index-based access and removal, and that out-of-range indices fail.
+ * - `test_remove_all()`: Tests that every chunk of a type is removed and returned, and that none matching is not an error.
  * - `test_png_from_image_file()`: Verifies that a PNG can be constructed from raw image file bytes.
  * - `test_as_bytes()`: Confirms that the PNG's byte representation matches the original data.
  * - `test_png_trait_impls()`: Validates that trait implementations (e.g. `Display`) for PNG work correctly.
+ * - `test_corrupted_chunk_crc()`: Ensures that a chunk with a bad CRC is reported with its index, type and offset.
+ * - `test_lenient_corrupted_chunk_crc()`: Checks that lenient parsing loads the file and records the bad chunk.
  */
 #[cfg(test)]
 mod tests {
     use imgmod::png::chunk::Chunk;
     use imgmod::png::chunk_type::ChunkType;
-    use imgmod::png::image::Png;
+    use imgmod::png::image::{Png, PngError};
     
     use std::convert::TryFrom;
 
@@ -162,6 +171,85 @@ mod tests {
         assert!(chunk.is_none());
     }
 
+    #[test]
+    fn test_insert_chunk() {
+        let mut png = testing_png();
+        png.insert_chunk(1, chunk_from_strings("TeSt", "Message").unwrap()).unwrap();
+        assert_eq!(png.chunks()[1].chunk_type().to_string(), "TeSt");
+        assert_eq!(png.chunks().len(), 4);
+
+        let result = png.insert_chunk(10, chunk_from_strings("TeSt", "Message").unwrap());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_insert_before() {
+        let mut png = testing_png();
+        png.insert_before("LASt", chunk_from_strings("TeSt", "Message").unwrap()).unwrap();
+        assert_eq!(png.chunks()[2].chunk_type().to_string(), "TeSt");
+        assert_eq!(png.chunks()[3].chunk_type().to_string(), "LASt");
+
+        let result = png.insert_before("NoNe", chunk_from_strings("TeSt", "Message").unwrap());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_insert_after() {
+        let mut png = testing_png();
+        png.insert_after("FrSt", chunk_from_strings("TeSt", "Message").unwrap()).unwrap();
+        assert_eq!(png.chunks()[0].chunk_type().to_string(), "FrSt");
+        assert_eq!(png.chunks()[1].chunk_type().to_string(), "TeSt");
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("teSt", "Message").unwrap());
+        let removed = png.retain(|chunk| chunk.chunk_type().is_critical());
+
+        let removed: Vec<String> = removed.iter().map(|c| c.chunk_type().to_string()).collect();
+        assert_eq!(removed, ["miDl", "teSt"]);
+        assert_eq!(png.chunks().len(), 2);
+        assert_eq!(png.chunks()[1].chunk_type().to_string(), "LASt");
+    }
+
+    fn duplicated_png() -> Png {
+        let mut png = testing_png();
+        png.insert_chunk(1, chunk_from_strings("teSt", "first").unwrap()).unwrap();
+        png.append_chunk(chunk_from_strings("teSt", "second").unwrap());
+        png
+    }
+
+    #[test]
+    fn test_chunks_by_type() {
+        let png = duplicated_png();
+        let messages: Vec<String> = png.chunks_by_type("teSt").map(|c| c.data_as_string().unwrap()).collect();
+        assert_eq!(messages, ["first", "second"]);
+        assert_eq!(png.chunks_by_type("NoNe").count(), 0);
+    }
+
+    #[test]
+    fn test_chunk_at() {
+        let mut png = duplicated_png();
+        assert_eq!(png.chunk_at(4).unwrap().data_as_string().unwrap(), "second");
+        assert!(matches!(png.chunk_at(5), Err(PngError::IndexOutOfBounds(5))));
+
+        let removed = png.remove_chunk_at(4).unwrap();
+        assert_eq!(removed.data_as_string().unwrap(), "second");
+        assert_eq!(png.chunks().len(), 4);
+        assert!(matches!(png.remove_chunk_at(4), Err(PngError::IndexOutOfBounds(4))));
+    }
+
+    #[test]
+    fn test_remove_all() {
+        let mut png = duplicated_png();
+        let removed: Vec<String> = png.remove_all("teSt").iter().map(|c| c.data_as_string().unwrap()).collect();
+        assert_eq!(removed, ["first", "second"]);
+        assert_eq!(png.as_bytes(), testing_png().as_bytes());
+
+        assert!(png.remove_all("teSt").is_empty());
+    }
+
     #[test]
     fn test_png_from_image_file() {
         let png = Png::try_from(&PNG_FILE[..]);
@@ -194,6 +282,41 @@ mod tests {
         let _png_string = format!("{}", png);
     }
 
+    fn corrupted_png_bytes() -> Vec<u8> {
+        let mut bytes = Png::from_chunks(testing_chunks()).as_bytes();
+
+        // Flip a data byte of the second chunk ("miDl") so its CRC no longer matches
+        let second_chunk_offset = 8 + 12 + "I am the first chunk".len();
+        bytes[second_chunk_offset + 8] ^= 0xff;
+        bytes
+    }
+
+    #[test]
+    fn test_corrupted_chunk_crc() {
+        let bytes = corrupted_png_bytes();
+
+        match Png::try_from(bytes.as_ref()) {
+            Err(PngError::CorruptedChunk(corrupted)) => {
+                assert_eq!(corrupted.index, 1);
+                assert_eq!(corrupted.chunk_type, "miDl");
+                assert_eq!(corrupted.offset, 8 + 12 + "I am the first chunk".len());
+                assert_ne!(corrupted.expected, corrupted.actual);
+            }
+            _ => panic!("expected a corrupted chunk error"),
+        }
+    }
+
+    #[test]
+    fn test_lenient_corrupted_chunk_crc() {
+        let bytes = corrupted_png_bytes();
+        let png = Png::from_bytes_lenient(&bytes).unwrap();
+
+        assert_eq!(png.chunks().len(), 3);
+        assert_eq!(png.corrupted_chunks().len(), 1);
+        assert_eq!(png.corrupted_chunks()[0].chunk_type, "miDl");
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
     // This is the raw bytes for a shrunken version of the `dice.png` image on Wikipedia
     const PNG_FILE: [u8; 4803] = [
         137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 50, 0, 0, 0, 50, 8,
@@ -0,0 +1,170 @@
+/**
+ * Integration tests for streaming PNG reading.
+ *
+ * Helper Functions:
+ * - `testing_bytes()`: Returns a small PNG file as bytes, built from sample chunks.
+ * - `Trickle`: A reader that hands out one byte per `read` call, like a slow pipe.
+ *
+ * Test Cases:
+ * - `test_reader_matches_png()`: Checks that streamed chunks match the chunks of an in-memory parse.
+ * - `test_reader_headers()`: Verifies chunk indices, offsets and lengths, and that `offset` ends at the file size.
+ * - `test_reader_skip_body()`: Ensures that skipped bodies still have their CRC verified.
+ * - `test_reader_lenient()`: Checks that lenient reading records a bad CRC and keeps going.
+ * - `test_reader_seek_body()`: Tests that seeking past bodies lands on the following chunk.
+ * - `test_reader_short_reads()`: Ensures that a reader returning one byte at a time is handled.
+ * - `test_reader_truncated()`: Ensures that a stream cut off inside a chunk fails with `UnexpectedEOF` at the offset where it ends, when reading or seeking.
+ * - `test_reader_invalid_signature()`: Ensures that a bad or short signature is rejected.
+ */
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+    use std::str::FromStr;
+    use imgmod::png::chunk::Chunk;
+    use imgmod::png::chunk_type::ChunkType;
+    use imgmod::png::image::{Png, PngError};
+    use imgmod::png::reader::PngReader;
+
+    fn testing_bytes() -> Vec<u8> {
+        let chunks = ["FrSt", "miDl", "LASt"]
+            .iter()
+            .zip(["I am the first chunk", "", "I am the last chunk"])
+            .map(|(t, d)| Chunk::new(ChunkType::from_str(t).unwrap(), d.as_bytes().to_vec()))
+            .collect();
+
+        Png::from_chunks(chunks).as_bytes()
+    }
+
+    struct Trickle(Vec<u8>, usize);
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.1 >= self.0.len() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.0[self.1];
+            self.1 += 1;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_reader_matches_png() {
+        let bytes = std::fs::read("./images/Gengar.png").unwrap();
+        let png = Png::try_from(bytes.as_slice()).unwrap();
+        let chunks: Vec<Chunk> = PngReader::new(bytes.as_slice())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(chunks.len(), png.chunks().len());
+        for (streamed, parsed) in chunks.iter().zip(png.chunks()) {
+            assert_eq!(streamed.as_bytes(), parsed.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_reader_headers() {
+        let bytes = testing_bytes();
+        let mut reader = PngReader::new(bytes.as_slice()).unwrap();
+
+        let mut headers = Vec::new();
+        while let Some(header) = reader.next_header().unwrap() {
+            headers.push(header);
+        }
+
+        let summary: Vec<(usize, u64, u32, &str)> = headers
+            .iter()
+            .map(|h| (h.index, h.offset, h.length, h.chunk_type.as_str()))
+            .collect();
+        assert_eq!(summary, [(0, 8, 20, "FrSt"), (1, 40, 0, "miDl"), (2, 52, 19, "LASt")]);
+        assert_eq!(reader.offset(), bytes.len() as u64);
+    }
+
+    #[test]
+    fn test_reader_skip_body() {
+        let mut bytes = testing_bytes();
+        bytes[8 + 8] ^= 0xff;
+        let mut reader = PngReader::new(bytes.as_slice()).unwrap();
+
+        reader.next_header().unwrap();
+        match reader.skip_body() {
+            Err(PngError::CorruptedChunk(corrupted)) => {
+                assert_eq!(corrupted.index, 0);
+                assert_eq!(corrupted.chunk_type, "FrSt");
+                assert_eq!(corrupted.offset, 8);
+            }
+            _ => panic!("expected a CRC mismatch"),
+        }
+    }
+
+    #[test]
+    fn test_reader_lenient() {
+        let mut bytes = testing_bytes();
+        bytes[52 + 8] ^= 0xff;
+        let mut reader = PngReader::new_lenient(bytes.as_slice()).unwrap();
+
+        while reader.next_header().unwrap().is_some() {}
+
+        assert_eq!(reader.corrupted_chunks().len(), 1);
+        assert_eq!(reader.corrupted_chunks()[0].chunk_type, "LASt");
+        assert!(Png::try_from(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_reader_seek_body() {
+        let bytes = testing_bytes();
+        let mut reader = PngReader::new(Cursor::new(bytes.clone())).unwrap();
+
+        reader.next_header().unwrap();
+        reader.seek_body().unwrap();
+        reader.next_header().unwrap();
+        reader.seek_body().unwrap();
+        reader.next_header().unwrap();
+        let last = reader.read_body().unwrap();
+
+        assert_eq!(last.data_as_string().unwrap(), "I am the last chunk");
+        assert!(reader.next_header().unwrap().is_none());
+        assert_eq!(reader.offset(), bytes.len() as u64);
+    }
+
+    #[test]
+    fn test_reader_short_reads() {
+        let bytes = testing_bytes();
+        let reader = PngReader::new(Trickle(bytes, 0)).unwrap();
+        let chunks: Vec<Chunk> = reader.collect::<Result<_, _>>().unwrap();
+
+        let types: Vec<String> = chunks.iter().map(|c| c.chunk_type().to_string()).collect();
+        assert_eq!(types, ["FrSt", "miDl", "LASt"]);
+    }
+
+    #[test]
+    fn test_reader_truncated() {
+        let bytes = testing_bytes();
+        let truncated = &bytes[..bytes.len() - 5];
+        let results: Vec<Result<Chunk, PngError>> = PngReader::new(truncated).unwrap().collect();
+
+        // The iterator stops after the first error
+        assert_eq!(results.len(), 3);
+        let end = truncated.len() as u64;
+        assert!(matches!(results[2], Err(PngError::UnexpectedEOF { offset }) if offset == end));
+
+        let mut reader = PngReader::new(truncated).unwrap();
+        while reader.next_header().unwrap().is_some_and(|h| h.chunk_type != "LASt") {}
+        assert!(matches!(reader.skip_body(), Err(PngError::UnexpectedEOF { offset }) if offset == end));
+
+        let mut reader = PngReader::new(Cursor::new(truncated)).unwrap();
+        while reader.next_header().unwrap().is_some_and(|h| h.chunk_type != "LASt") {}
+        assert!(matches!(reader.seek_body(), Err(PngError::UnexpectedEOF { offset }) if offset == end));
+    }
+
+    #[test]
+    fn test_reader_invalid_signature() {
+        let mut bytes = testing_bytes();
+        let found = bytes[..5].to_vec();
+        assert!(matches!(PngReader::new(&bytes[..5]), Err(PngError::InvalidSignature(b)) if b == found));
+
+        bytes[0] = 0;
+        let found = bytes[..8].to_vec();
+        assert!(matches!(PngReader::new(bytes.as_slice()), Err(PngError::InvalidSignature(b)) if b == found));
+    }
+}
@@ -0,0 +1,109 @@
+/**
+ * Unit tests for splitting payloads into sequenced pieces.
+ *
+ * Tests:
+ * - `test_split_round_trip` - Checks that split pieces join back into the original payload.
+ * - `test_split_piece_sizes` - Ensures that every piece, header included, fits the maximum size.
+ * - `test_split_empty_payload` - Checks that an empty payload still produces one piece.
+ * - `test_join_reordered` - Checks that pieces are reassembled by index regardless of their order.
+ * - `test_join_missing_piece` - Ensures that a missing piece is reported by index.
+ * - `test_join_duplicate_piece` - Ensures that a repeated piece is rejected.
+ * - `test_join_digest_mismatch` - Ensures that tampered piece data fails the digest check.
+ * - `test_join_mixed_messages` - Ensures that pieces of two different messages are not combined.
+ * - `test_split_invalid_size` - Ensures that sizes too small for the header are rejected.
+ */
+#[cfg(test)]
+mod tests {
+    use imgmod::stego::split::{in_order, join, split, Piece, SplitError, HEADER_LEN};
+
+    fn payload() -> Vec<u8> {
+        (0..1000u32).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    fn pieces(payload: &[u8], max_size: usize) -> Vec<Piece> {
+        split(payload, max_size)
+            .unwrap()
+            .iter()
+            .map(|bytes| Piece::try_from(bytes.as_slice()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_split_round_trip() {
+        let pieces = pieces(&payload(), 200);
+
+        assert_eq!(pieces.len(), 1000usize.div_ceil(200 - HEADER_LEN));
+        assert!(pieces.iter().all(|p| p.message_id == pieces[0].message_id && p.total == pieces.len() as u32));
+        assert!(in_order(&pieces));
+        assert_eq!(join(&pieces).unwrap(), payload());
+    }
+
+    #[test]
+    fn test_split_piece_sizes() {
+        let bytes = split(&payload(), 100).unwrap();
+
+        assert!(bytes.iter().all(|b| b.len() <= 100));
+        assert_eq!(bytes.iter().map(|b| b.len() - HEADER_LEN).sum::<usize>(), 1000);
+    }
+
+    #[test]
+    fn test_split_empty_payload() {
+        let pieces = pieces(&[], 100);
+
+        assert_eq!(pieces.len(), 1);
+        assert_eq!(join(&pieces).unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_join_reordered() {
+        let mut pieces = pieces(&payload(), 200);
+        pieces.swap(0, 3);
+        pieces.reverse();
+
+        assert!(!in_order(&pieces));
+        assert_eq!(join(&pieces).unwrap(), payload());
+    }
+
+    #[test]
+    fn test_join_missing_piece() {
+        let mut middle = pieces(&payload(), 200);
+        middle.remove(2);
+        assert!(matches!(join(&middle), Err(SplitError::MissingPiece(2))));
+
+        let mut tail = pieces(&payload(), 200);
+        let last = tail.pop().unwrap().index;
+        assert!(matches!(join(&tail), Err(SplitError::MissingPiece(i)) if i == last));
+    }
+
+    #[test]
+    fn test_join_duplicate_piece() {
+        let mut pieces = pieces(&payload(), 200);
+        pieces.push(pieces[1].clone());
+
+        assert!(matches!(join(&pieces), Err(SplitError::DuplicatePiece(1))));
+    }
+
+    #[test]
+    fn test_join_digest_mismatch() {
+        let mut pieces = pieces(&payload(), 200);
+        pieces[1].data[0] ^= 1;
+
+        assert!(matches!(join(&pieces), Err(SplitError::DigestMismatch)));
+    }
+
+    #[test]
+    fn test_join_mixed_messages() {
+        let mut pieces = pieces(&payload(), 200);
+        let other = self::pieces(&payload(), 200);
+        pieces[0] = other[0].clone();
+
+        assert!(matches!(join(&pieces), Err(SplitError::MixedMessages)));
+        assert!(matches!(join(&[]), Err(SplitError::NoPieces)));
+    }
+
+    #[test]
+    fn test_split_invalid_size() {
+        assert!(matches!(split(&payload(), HEADER_LEN), Err(SplitError::InvalidPieceSize(_))));
+        assert!(matches!(Piece::try_from(&[1u8; 10][..]), Err(SplitError::InvalidHeader)));
+    }
+}
@@ -0,0 +1,140 @@
+/**
+ * Integration tests for the textual metadata chunks.
+ *
+ * This module checks that tEXt, zTXt and iTXt entries round-trip through chunks, that invalid
+ * keywords and encodings are rejected, and that `Png` exposes text entries by keyword.
+ *
+ * Tests:
+ * - `test_text_round_trip` - Checks that a tEXt entry survives serialization and parsing.
+ * - `test_ztxt_round_trip` - Checks that a zTXt entry is compressed and decompressed correctly.
+ * - `test_itxt_round_trip` - Checks that compressed and uncompressed iTXt entries keep their UTF-8 text and tags.
+ * - `test_text_latin1` - Ensures that tEXt bytes are decoded as Latin-1 and non-Latin-1 text is rejected.
+ * - `test_text_invalid_keyword` - Ensures that empty, too long and badly spaced keywords are rejected.
+ * - `test_text_not_text_chunk` - Ensures that other chunk types are not parsed as text.
+ * - `test_png_text_entries` - Checks that `Png` lists, gets, replaces and removes entries by keyword.
+ * - `test_text_too_long` - Ensures that compressed text inflating past the limit is rejected.
+ * - `test_png_malformed_text` - Checks that a malformed text chunk does not get in the way of entries with other keywords.
+ */
+#[cfg(test)]
+mod tests {
+    use imgmod::png::chunk::Chunk;
+    use imgmod::png::chunk_type::ChunkType;
+    use imgmod::png::image::{Png, PngError};
+    use imgmod::png::text::{TextEntry, TextError, TextKind, MAX_TEXT_LENGTH};
+    use std::str::FromStr;
+
+    fn round_trip(entry: &TextEntry) -> TextEntry {
+        let chunk = entry.to_chunk().unwrap();
+        TextEntry::try_from(&chunk).unwrap()
+    }
+
+    fn testing_png() -> Png {
+        Png::from_chunks(vec![
+            Chunk::from_strings("IHDR", "header").unwrap(),
+            Chunk::from_strings("IDAT", "pixels").unwrap(),
+            Chunk::from_strings("IEND", "").unwrap(),
+        ])
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let entry = TextEntry::new("Title", "Gengar");
+        let chunk = entry.to_chunk().unwrap();
+
+        assert_eq!(chunk.chunk_type().to_string(), "tEXt");
+        assert_eq!(chunk.data(), b"Title\0Gengar");
+        assert_eq!(round_trip(&entry), entry);
+    }
+
+    #[test]
+    fn test_ztxt_round_trip() {
+        let text = "spooky ".repeat(100);
+        let entry = TextEntry::compressed("Comment", &text);
+        let chunk = entry.to_chunk().unwrap();
+
+        assert_eq!(chunk.chunk_type().to_string(), "zTXt");
+        assert!(chunk.data().len() < text.len());
+        assert_eq!(round_trip(&entry), entry);
+    }
+
+    #[test]
+    fn test_itxt_round_trip() {
+        let entry = TextEntry::international("Title", "ゲンガー", "ja", "Titre", false);
+        assert_eq!(entry.to_chunk().unwrap().chunk_type().to_string(), "iTXt");
+        assert_eq!(round_trip(&entry), entry);
+
+        let entry = TextEntry::international("Title", "ゲンガー", "ja", "Titre", true);
+        let parsed = round_trip(&entry);
+        assert_eq!(parsed, entry);
+        assert!(matches!(parsed.kind, TextKind::International { compressed: true, .. }));
+    }
+
+    #[test]
+    fn test_text_latin1() {
+        let chunk = Chunk::new(ChunkType::from_str("tEXt").unwrap(), b"Author\0Ren\xe9".to_vec());
+        let entry = TextEntry::try_from(&chunk).unwrap();
+        assert_eq!(entry.text, "René");
+        assert_eq!(entry.to_chunk().unwrap().data(), chunk.data());
+
+        let result = TextEntry::new("Title", "ゲンガー").to_chunk();
+        assert!(matches!(result, Err(TextError::NotLatin1)));
+    }
+
+    #[test]
+    fn test_text_invalid_keyword() {
+        for keyword in ["", " Title", "Title ", "Two  spaces", &"k".repeat(80)] {
+            let result = TextEntry::new(keyword, "text").to_chunk();
+            assert!(matches!(result, Err(TextError::InvalidKeyword(_))), "{:?}", keyword);
+        }
+        assert!(TextEntry::new("Two spaces", "text").to_chunk().is_ok());
+    }
+
+    #[test]
+    fn test_text_not_text_chunk() {
+        let chunk = Chunk::from_strings("maTt", "Title\0text").unwrap();
+        assert!(!TextEntry::is_text_chunk(&chunk));
+        assert!(matches!(TextEntry::try_from(&chunk), Err(TextError::NotTextChunk(_))));
+    }
+
+    #[test]
+    fn test_png_text_entries() {
+        let mut png = testing_png();
+        png.set_text(TextEntry::new("Title", "Gengar")).unwrap();
+        png.set_text(TextEntry::compressed("Comment", "spooky")).unwrap();
+        assert_eq!(png.text_entries().len(), 2);
+        assert_eq!(png.chunks().last().unwrap().chunk_type().to_string(), "IEND");
+
+        png.set_text(TextEntry::new("Title", "Haunter")).unwrap();
+        let entries = png.text_entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(png.text("Title").unwrap().unwrap().text, "Haunter");
+
+        assert_eq!(png.remove_text("Title"), 1);
+        assert!(png.text("Title").unwrap().is_none());
+        assert_eq!(png.remove_text("Title"), 0);
+        assert_eq!(png.chunks().len(), 4);
+    }
+
+    #[test]
+    fn test_text_too_long() {
+        let text = "a".repeat(MAX_TEXT_LENGTH + 1);
+        let chunk = TextEntry::compressed("Bomb", &text).to_chunk().unwrap();
+        assert!(chunk.data().len() < 64 * 1024);
+        assert!(matches!(TextEntry::try_from(&chunk), Err(TextError::TooLong(MAX_TEXT_LENGTH))));
+    }
+
+    #[test]
+    fn test_png_malformed_text() {
+        let mut png = testing_png();
+        png.set_text(TextEntry::new("Title", "Gengar")).unwrap();
+        png.insert_chunk(1, Chunk::from_strings("zTXt", "Comment\0\0not zlib").unwrap()).unwrap();
+
+        assert_eq!(png.text_entries(), [TextEntry::new("Title", "Gengar")]);
+        assert_eq!(png.text("Title").unwrap().unwrap().text, "Gengar");
+        assert!(matches!(png.text("Comment"), Err(PngError::Text(TextError::Compression(_)))));
+
+        assert_eq!(png.remove_text("Title"), 1);
+        assert_eq!(png.remove_text("Comment"), 1);
+        assert_eq!(png.chunks().len(), 3);
+    }
+}
@@ -0,0 +1,111 @@
+/**
+ * Integration tests for streaming PNG writing.
+ *
+ * Test Cases:
+ * - `test_writer_matches_as_bytes()`: Checks that writing every chunk produces the same bytes as `Png::as_bytes`.
+ * - `test_write_chunk_from()`: Checks that a chunk streamed from a reader matches one built with `Chunk::new`.
+ * - `test_write_chunk_from_short_reader()`: Ensures that a reader with fewer bytes than announced fails with an `UnexpectedEof` I/O error.
+ * - `test_writer_ihdr_first()`: Ensures that a checked writer rejects any first chunk other than IHDR.
+ * - `test_writer_iend_last()`: Ensures that nothing can follow IEND and that `finish` requires IEND.
+ * - `test_writer_unchecked()`: Checks that an unchecked writer accepts chunks in any order.
+ * - `test_reader_to_writer()`: Copies a file chunk by chunk from a `PngReader` to a `PngWriter`.
+ */
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use imgmod::png::chunk::Chunk;
+    use imgmod::png::chunk_type::ChunkType;
+    use imgmod::png::image::{Png, PngError};
+    use imgmod::png::reader::PngReader;
+    use imgmod::png::writer::PngWriter;
+
+    fn chunk(chunk_type: &str, data: &str) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_writer_matches_as_bytes() {
+        let png = Png::from_file("./images/Gengar.png").unwrap();
+        let mut writer = PngWriter::new(Vec::new()).unwrap();
+
+        for chunk in png.chunks() {
+            writer.write_chunk(chunk).unwrap();
+        }
+
+        assert_eq!(writer.finish().unwrap(), png.as_bytes());
+    }
+
+    #[test]
+    fn test_write_chunk_from() {
+        let data = "streamed chunk data ".repeat(1000);
+        let mut writer = PngWriter::new_unchecked(Vec::new()).unwrap();
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+
+        writer.write_chunk_from(&chunk_type, data.len() as u32, data.as_bytes()).unwrap();
+        let bytes = writer.finish().unwrap();
+
+        assert_eq!(&bytes[..8], &Png::STANDARD_HEADER);
+        assert_eq!(&bytes[8..], chunk("ruSt", &data).as_bytes());
+    }
+
+    #[test]
+    fn test_write_chunk_from_short_reader() {
+        let mut writer = PngWriter::new_unchecked(Vec::new()).unwrap();
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+
+        let result = writer.write_chunk_from(&chunk_type, 10, "short".as_bytes());
+        let Err(PngError::Io { path: None, source }) = result else {
+            panic!("expected an I/O error, got {:?}", result);
+        };
+        assert_eq!(source.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_writer_ihdr_first() {
+        let mut writer = PngWriter::new(Vec::new()).unwrap();
+
+        assert!(matches!(writer.write_chunk(&chunk("ruSt", "x")), Err(PngError::ChunkOrder(_))));
+        writer.write_chunk(&chunk("IHDR", "")).unwrap();
+        assert!(matches!(writer.write_chunk(&chunk("IHDR", "")), Err(PngError::ChunkOrder(_))));
+    }
+
+    #[test]
+    fn test_writer_iend_last() {
+        let mut writer = PngWriter::new(Vec::new()).unwrap();
+        writer.write_chunk(&chunk("IHDR", "")).unwrap();
+        writer.write_chunk(&chunk("ruSt", "x")).unwrap();
+        writer.write_chunk(&chunk("IEND", "")).unwrap();
+
+        assert!(matches!(writer.write_chunk(&chunk("ruSt", "x")), Err(PngError::ChunkOrder(_))));
+        assert!(writer.finish().is_ok());
+
+        let mut writer = PngWriter::new(Vec::new()).unwrap();
+        writer.write_chunk(&chunk("IHDR", "")).unwrap();
+        assert!(matches!(writer.finish(), Err(PngError::ChunkOrder(_))));
+    }
+
+    #[test]
+    fn test_writer_unchecked() {
+        let mut writer = PngWriter::new_unchecked(Vec::new()).unwrap();
+
+        writer.write_chunk(&chunk("IEND", "")).unwrap();
+        writer.write_chunk(&chunk("ruSt", "after IEND")).unwrap();
+
+        let bytes = writer.finish().unwrap();
+        let png = Png::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(png.chunks().len(), 2);
+    }
+
+    #[test]
+    fn test_reader_to_writer() {
+        let bytes = std::fs::read("./images/Gengar.png").unwrap();
+        let mut reader = PngReader::new(bytes.as_slice()).unwrap();
+        let mut writer = PngWriter::new(Vec::new()).unwrap();
+
+        while let Some(chunk) = reader.next_chunk().unwrap() {
+            writer.write_chunk(&chunk).unwrap();
+        }
+
+        assert_eq!(writer.finish().unwrap(), bytes);
+    }
+}